@@ -0,0 +1,64 @@
+//! Checks `AtomicGc` with threads swapping the same slot, while another roots and unroots it and
+//! another collects
+
+use std::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    thread,
+};
+
+use gc::{AtomicGc, Gc, GcAble, GcConfig};
+
+const SWAPPERS: u64 = 4;
+const SWAPS: u64 = 2000;
+
+pub fn check_all() {
+    let ctx = crate::basics::context(GcConfig::default());
+    // Not a root once it's in the object
+    let slot = ctx.alloc(AtomicGc::new(ctx.alloc(0u64)));
+    let swapped = AtomicU64::new(0);
+    let done = AtomicBool::new(false);
+
+    thread::scope(|s| {
+        let swappers: Vec<_> = (0..SWAPPERS)
+            .map(|_| {
+                s.spawn(|| {
+                    for _ in 0..SWAPS {
+                        let current = slot.load();
+                        let new = ctx.alloc(*current + 1);
+                        if slot.compare_exchange(&current, new).is_ok() {
+                            swapped.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                })
+            })
+            .collect();
+        // Whatever is in the slot stays rooted in between, however many swaps happen meanwhile
+        s.spawn(|| {
+            while !done.load(Ordering::Relaxed) {
+                unsafe { GcAble::inc_root_count(&*slot) };
+                thread::yield_now();
+                unsafe { GcAble::dec_root_count(&*slot) };
+            }
+        });
+        s.spawn(|| {
+            while !done.load(Ordering::Relaxed) {
+                ctx.force_collect();
+            }
+        });
+        let joined: Vec<_> = swappers.into_iter().map(|swapper| swapper.join()).collect();
+        // Before a panic is resumed, or the other threads would keep the scope open forever
+        done.store(true, Ordering::Relaxed);
+        joined.into_iter().for_each(|res| res.unwrap());
+    });
+
+    let last = slot.load();
+    assert_eq!(*last, swapped.load(Ordering::Relaxed));
+    // Only `last` roots it, every root moved onto it by a swap was taken off again
+    assert_eq!(Gc::strong_count(&last), 1);
+    ctx.force_collect();
+    assert_eq!(ctx.stats().live_allocations, 2);
+
+    drop((slot, last));
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
@@ -0,0 +1,179 @@
+//! Exercises every unsafe path of the Gc once: allocating, cloning, dropping, marking, sweeping and
+//! collecting cycles, through every kind of box
+//!
+//! Small enough to run under Miri, which is why every context here has a driver which never
+//! collects by itself, so nothing runs on another thread
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use gc::{
+    AtomicGc, Collector, CollectorDriver, DeepClone, DeepCloner, Finalize, Gc, GcCell, GcConfig,
+    GcContext, WeakGc,
+};
+
+/// Only collects when asked to
+struct Manual;
+
+impl CollectorDriver for Manual {
+    fn start(&self, _: Collector) {}
+}
+
+pub fn context(config: GcConfig) -> GcContext {
+    GcContext::with_config(config.driver(Manual))
+}
+
+struct Node {
+    id: u32,
+    drops: Arc<AtomicUsize>,
+    next: GcCell<Option<Gc<Node>>>,
+}
+
+gc::impl_gc_able!(Node => |node, visit| visit(&node.next));
+
+impl DeepClone for Node {
+    fn clone_deep(&self, cloner: &mut DeepCloner) -> Self {
+        Node {
+            id: self.id,
+            drops: Arc::clone(&self.drops),
+            next: GcCell::new(self.next.get().map(|next| cloner.clone_gc(&next))),
+        }
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        self.drops.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl Finalize for Node {
+    fn finalize(&self) {
+        // A finalizer may read the objects its object points to, even unreachable ones
+        if let Some(next) = &*self.next.borrow() {
+            assert!(next.id < 100);
+        }
+    }
+}
+
+#[derive(gc::GcAble)]
+struct SelfRef {
+    me: WeakGc<SelfRef>,
+}
+
+fn node(ctx: &GcContext, drops: &Arc<AtomicUsize>, id: u32, next: Option<Gc<Node>>) -> Gc<Node> {
+    ctx.alloc_finalized(Node {
+        id,
+        drops: Arc::clone(drops),
+        next: GcCell::new(next),
+    })
+}
+
+/// Builds a ring of `n` nodes and drops it, with `collect` collecting along the way
+fn check_ring(config: GcConfig, n: u32, collect: impl Fn(&GcContext)) {
+    let ctx = context(config);
+    let drops = Arc::new(AtomicUsize::new(0));
+    let first = node(&ctx, &drops, 0, None);
+    let mut last = first.clone();
+    for id in 1..n {
+        last = node(&ctx, &drops, id, Some(last));
+        collect(&ctx);
+    }
+    first.next.set(Some(last.clone()));
+    collect(&ctx);
+    assert_eq!(drops.load(Ordering::SeqCst), 0, "a rooted node was freed");
+    assert_eq!(last.next.borrow().as_ref().unwrap().id, n - 2);
+
+    drop((first, last));
+    ctx.force_collect();
+    assert_eq!(drops.load(Ordering::SeqCst), n as usize);
+    ctx.assert_no_leaks();
+}
+
+fn check_collections() {
+    check_ring(GcConfig::default(), 8, GcContext::force_collect);
+    // Every slice of marking sees one object, and every batch of sweeping reclaims one
+    let incremental = GcConfig::default().incremental(1).max_sweep_batch(1);
+    check_ring(incremental, 8, |ctx| {
+        ctx.collect_step();
+    });
+    // Minor collections, with the older half of the ring promoted before the rest is allocated
+    let generational = GcConfig::default().promotion_threshold(1).major_interval(4);
+    check_ring(generational, 8, |ctx| {
+        ctx.collect_step();
+    });
+}
+
+fn check_handles() {
+    let ctx = context(GcConfig::default());
+    let drops = Arc::new(AtomicUsize::new(0));
+    let a = node(&ctx, &drops, 1, None);
+
+    let raw = Gc::into_raw(a.clone());
+    let b = unsafe { Gc::from_raw(raw) };
+    assert!(Gc::ptr_eq(&a, &b));
+    assert_eq!(Gc::strong_count(&a), 2);
+
+    let weak = Gc::downgrade(&b);
+    let atomic = AtomicGc::new(b);
+    let copy = a.clone_deep();
+    assert!(!Gc::ptr_eq(&a, &copy));
+    assert_eq!(copy.id, 1);
+
+    let cyclic = ctx.alloc_cyclic(|me: &WeakGc<SelfRef>| {
+        assert!(me.upgrade().is_none());
+        SelfRef { me: me.clone() }
+    });
+    let slice = ctx.alloc_slice(&[a.clone(), copy.clone()]);
+    let mut uninit = ctx.alloc_uninit::<Gc<Node>>();
+    uninit.get_mut().unwrap().write(a.clone());
+    let init = unsafe { Gc::assume_init(uninit) };
+
+    drop(a);
+    ctx.force_collect();
+    assert_eq!(drops.load(Ordering::SeqCst), 0);
+    assert_eq!(atomic.load().id, 1);
+    assert_eq!(slice[1].id, 1);
+    assert_eq!(init.id, 1);
+    let me = cyclic.me.upgrade().unwrap();
+    assert!(Gc::ptr_eq(&me, &cyclic));
+
+    drop((atomic, slice, init, me, cyclic, copy));
+    ctx.force_collect();
+    assert!(weak.upgrade().is_none());
+    assert_eq!(drops.load(Ordering::SeqCst), 2);
+    ctx.assert_no_leaks();
+}
+
+fn check_mutation() {
+    let ctx = context(GcConfig::default());
+    let drops = Arc::new(AtomicUsize::new(0));
+    let mut a = ctx.alloc(5u32);
+    *a.get_mut().unwrap() += 1;
+    assert_eq!(a.replace_in_place(7), Ok(6));
+
+    let b = node(&ctx, &drops, 1, None);
+    // Stored while a collection is partway through marking, past the write barrier
+    let incremental = context(GcConfig::default().incremental(1));
+    let holder = node(&incremental, &drops, 2, None);
+    let _filler = node(&incremental, &drops, 3, None);
+    incremental.collect_step();
+    holder.next.set(Some(node(&incremental, &drops, 4, None)));
+    while !incremental.collect_step() {}
+    assert_eq!(holder.next.borrow().as_ref().unwrap().id, 4);
+
+    drop((a, b, holder, _filler));
+    ctx.force_collect();
+    incremental.force_collect();
+    assert_eq!(drops.load(Ordering::SeqCst), 4);
+    ctx.assert_no_leaks();
+    incremental.assert_no_leaks();
+}
+
+pub fn check_all() {
+    check_collections();
+    check_handles();
+    check_mutation();
+}
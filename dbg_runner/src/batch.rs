@@ -0,0 +1,49 @@
+//! Checks that a `GcBatch` holds off the collections its context requests until the last batch is
+//! dropped, which then wakes the collector to run them, while explicit collections still run
+//!
+//! The timing this is meant to save is measured by `cargo bench -p gc --bench batch`
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use gc::{GcConfig, GcContext};
+
+pub fn check_all() {
+    // Only the watermark wakes the collector
+    let ctx = GcContext::with_config(
+        GcConfig::default()
+            .alloc_watermark(10)
+            .max_interval(Duration::from_secs(3600)),
+    );
+
+    let (outer, inner) = (ctx.batch(), ctx.batch());
+    for i in 0..100u32 {
+        ctx.alloc(i);
+    }
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(ctx.stats().live_allocations, 100);
+
+    // Only the last batch to be dropped runs the requested collection
+    drop(inner);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(ctx.stats().live_allocations, 100);
+    drop(outer);
+    let start = Instant::now();
+    while ctx.stats().live_allocations > 0 {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "the requested collection never ran"
+        );
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    // Explicit collections aren't held off
+    let batch = ctx.batch();
+    ctx.alloc(0u32);
+    ctx.force_collect();
+    assert_eq!(ctx.stats().live_allocations, 0);
+    drop(batch);
+    ctx.assert_no_leaks();
+}
@@ -0,0 +1,96 @@
+//! Checks that `GcCell` and `GcMut` borrows conflict like `RefCell`'s on the borrowing thread,
+//! panicking or failing with `try_borrow(_mut)`, while another thread's conflicting borrow blocks
+//! until the first one is released
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use gc::{Gc, GcCell, GcConfig, GcContext, GcMut};
+
+struct List(Vec<Gc<u32>>);
+
+gc::impl_gc_able!(List => |list, visit| list.0.iter().for_each(|gc| visit(gc)));
+
+/// Runs `f`, returning the message it panics with
+fn panics(f: impl FnOnce()) -> String {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    panic::set_hook(hook);
+    let payload = result.expect_err("a conflicting borrow didn't panic");
+    payload.downcast_ref::<String>().unwrap().clone()
+}
+
+fn check_same_thread(ctx: &GcContext) {
+    let cell = GcMut::from(ctx.alloc(GcCell::new(List(vec![ctx.alloc(1u32)]))));
+
+    // Shared borrows don't conflict with each other
+    let (first, second) = (cell.borrow(), cell.try_borrow().unwrap());
+    assert_eq!(*first.0[0] + *second.0[0], 2);
+    assert!(cell.try_borrow_mut().is_err());
+    assert_eq!(panics(|| drop(cell.borrow_mut())), "already borrowed");
+    assert_eq!(panics(|| cell.set(List(Vec::new()))), "already borrowed");
+    drop((first, second));
+
+    let mut borrowed = cell.borrow_mut();
+    borrowed.0.push(ctx.alloc(2));
+    assert!(cell.try_borrow().is_err());
+    assert!(cell.try_borrow_mut().is_err());
+    assert_eq!(panics(|| drop(cell.borrow())), "already mutably borrowed");
+    assert_eq!(panics(|| drop(cell.borrow_mut())), "already borrowed");
+    drop(borrowed);
+
+    // Every flag was released, including by the borrows which panicked
+    let values: Vec<u32> = cell
+        .try_borrow_mut()
+        .unwrap()
+        .0
+        .iter()
+        .map(|gc| **gc)
+        .collect();
+    assert_eq!(values, [1, 2]);
+    cell.set(List(Vec::new()));
+}
+
+fn check_other_thread(ctx: &GcContext) {
+    let cell = GcMut::from(ctx.alloc(GcCell::new(0u32)));
+    let (borrowed, is_borrowed) = mpsc::channel();
+    let (release, released) = mpsc::channel::<()>();
+    thread::scope(|s| {
+        let cell = &cell;
+        let holder = s.spawn(move || {
+            let mut value = cell.borrow_mut();
+            *value = 1;
+            borrowed.send(()).unwrap();
+            released.recv().unwrap();
+            *value = 2;
+        });
+        is_borrowed.recv().unwrap();
+        // Another thread's borrow isn't a panic, but can't be taken right now
+        assert!(cell.try_borrow().is_err());
+        assert!(cell.try_borrow_mut().is_err());
+
+        let waiter = s.spawn(|| *cell.borrow());
+        thread::sleep(Duration::from_millis(50));
+        assert!(
+            !waiter.is_finished(),
+            "a borrow didn't wait for a mutable one"
+        );
+        release.send(()).unwrap();
+        holder.join().unwrap();
+        assert_eq!(waiter.join().unwrap(), 2);
+    });
+    assert_eq!(*cell.borrow(), 2);
+}
+
+pub fn check_all() {
+    let ctx = GcContext::with_config(GcConfig::default().deterministic(true));
+    check_same_thread(&ctx);
+    check_other_thread(&ctx);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
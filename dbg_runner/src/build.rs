@@ -0,0 +1,95 @@
+//! Checks `GcContext::build`
+//!
+//! A long chain is built one node at a time while the background collector is asked to collect
+//! after every few allocations and another thread forces collections in a tight loop. No
+//! collection may run while it's built, and every node has to be there afterwards.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use gc::{Gc, GcCell, GcConfig, GcContext};
+
+const NODES: usize = 100_000;
+
+struct Node {
+    index: usize,
+    dropped: Arc<AtomicUsize>,
+    next: GcCell<Option<Gc<Node>>>,
+}
+
+gc::impl_gc_able!(Node => |node, visit| visit(&node.next));
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        self.dropped.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+pub fn check_all() {
+    let config = GcConfig::default()
+        .alloc_watermark(16)
+        .max_interval(Duration::from_millis(1));
+    let ctx = GcContext::with_config(config);
+    let dropped = Arc::new(AtomicUsize::new(0));
+
+    let done = Arc::new(AtomicBool::new(false));
+    let collector = {
+        let ctx = ctx.clone();
+        let done = Arc::clone(&done);
+        thread::spawn(move || {
+            while !done.load(Ordering::Relaxed) {
+                ctx.force_collect();
+            }
+        })
+    };
+
+    let head = ctx.build(|ctx| {
+        let before = ctx.runtime_metrics().collections;
+        let mut head = None::<Gc<Node>>;
+        for index in 0..NODES {
+            let node = ctx.alloc(Node {
+                index,
+                dropped: Arc::clone(&dropped),
+                next: GcCell::new(None),
+            });
+            // Stored after allocating, so the previous head is only reachable from the new node
+            node.next.set(head.take());
+            head = Some(node);
+            // Garbage the collector would have reclaimed if it ran
+            ctx.alloc(index as u64);
+        }
+        assert_eq!(
+            ctx.runtime_metrics().collections,
+            before,
+            "collected while building"
+        );
+        head.unwrap()
+    });
+    ctx.force_collect();
+    assert_eq!(
+        dropped.load(Ordering::SeqCst),
+        0,
+        "a node was freed while building"
+    );
+
+    let mut len = 0;
+    let mut node = Some(head.clone());
+    while let Some(current) = node {
+        assert_eq!(current.index, NODES - 1 - len);
+        len += 1;
+        node = current.next.get();
+    }
+    assert_eq!(len, NODES);
+
+    done.store(true, Ordering::Relaxed);
+    collector.join().unwrap();
+    drop(head);
+    ctx.force_collect();
+    assert_eq!(dropped.load(Ordering::SeqCst), NODES);
+}
@@ -0,0 +1,153 @@
+//! Checks that `GcBuilder` rejects settings which don't make sense together, and that a context
+//! built from the settings it accepts collects the way they say
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use gc::{GcAble, GcBuilder, GcConfigError, GcContext};
+
+/// Garbage which sets its flag once dropped, which only a collection does
+struct Sentinel(Arc<AtomicBool>);
+
+// SAFETY: there are no `Gc<_>`s in a `Sentinel`
+unsafe impl GcAble for Sentinel {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+impl Drop for Sentinel {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+fn check_invalid() {
+    let invalid = [
+        (
+            GcBuilder::new().max_interval(Duration::ZERO),
+            GcConfigError::ZeroInterval,
+        ),
+        (
+            GcBuilder::new().alloc_watermark(0),
+            GcConfigError::ZeroWatermark,
+        ),
+        (
+            GcBuilder::new().major_interval(0),
+            GcConfigError::ZeroMajorInterval,
+        ),
+        (
+            GcBuilder::new().incremental(0),
+            GcConfigError::ZeroStepBudget,
+        ),
+        (
+            GcBuilder::new().growth_factor(1.0),
+            GcConfigError::GrowthFactor(1.0),
+        ),
+        // Only the first setting which doesn't make sense is reported
+        (
+            GcBuilder::new().incremental(0).max_interval(Duration::ZERO),
+            GcConfigError::ZeroInterval,
+        ),
+    ];
+    for (builder, expected) in invalid {
+        let err = builder.build().expect_err("an invalid config was built");
+        assert_eq!(err, expected);
+        assert!(!err.to_string().is_empty());
+    }
+    // NaN isn't equal to itself, so isn't compared
+    let err = GcBuilder::new()
+        .growth_factor(f64::NAN)
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, GcConfigError::GrowthFactor(factor) if factor.is_nan()));
+}
+
+/// Only the watermark wakes the collector, and only once it's reached
+fn check_watermark() {
+    let config = GcBuilder::new()
+        .alloc_watermark(10)
+        .max_interval(Duration::from_secs(3600))
+        .build()
+        .unwrap();
+    let ctx = GcContext::with_config(config);
+
+    let collected = Arc::new(AtomicBool::new(false));
+    ctx.alloc(Sentinel(Arc::clone(&collected)));
+    let kept = (0..8).map(|i| ctx.alloc(i)).collect::<Vec<_>>();
+    thread::sleep(Duration::from_millis(100));
+    assert!(
+        !collected.load(Ordering::SeqCst),
+        "collected below the watermark"
+    );
+    drop(ctx.alloc(8));
+    let start = Instant::now();
+    while !collected.load(Ordering::SeqCst) {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "the watermark was reached without a collection"
+        );
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    drop(kept);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
+
+/// A collection run a step at a time takes a step per budget's worth of objects
+fn check_incremental() {
+    // Only collected by `collect_step` and `force_collect`
+    let config = GcBuilder::new()
+        .incremental(4)
+        .alloc_watermark(usize::MAX)
+        .max_interval(Duration::from_secs(3600))
+        .build()
+        .unwrap();
+    let ctx = GcContext::with_config(config);
+    let kept = (0..100).map(|i| ctx.alloc(i)).collect::<Vec<_>>();
+    for i in 0..100 {
+        ctx.alloc(i);
+    }
+    assert_eq!(ctx.stats().live_allocations, 200);
+
+    // Nothing wakes the collector thread once it's asleep, so it can't take any of the steps
+    thread::sleep(Duration::from_millis(100));
+    let mut steps = 1;
+    while !ctx.collect_step() {
+        steps += 1;
+    }
+    assert!(steps >= 100 / 4, "{steps} steps");
+    assert_eq!(ctx.stats().live_allocations, kept.len());
+    assert!(kept.iter().enumerate().all(|(i, num)| **num == i as u32));
+
+    drop(kept);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
+
+/// The global context can only be configured before its first use
+fn check_global() {
+    GcContext::global();
+    assert_eq!(
+        gc::init_gc_with(GcBuilder::new().build().unwrap()),
+        Err(GcConfigError::AlreadyInitialized)
+    );
+}
+
+pub fn check_all() {
+    check_invalid();
+    check_watermark();
+    check_incremental();
+    check_global();
+}
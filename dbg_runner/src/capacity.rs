@@ -0,0 +1,79 @@
+//! Checks `GcConfig::capacity`
+//!
+//! The objects themselves are allocated by `System` directly, so every allocation the global
+//! allocator sees while they're created is the context's bookkeeping growing. With enough capacity
+//! there mustn't be any. Only checked in release builds, since debug builds also keep a set of
+//! every live object to catch uses after free.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use gc::{Gc, GcConfig};
+
+const OBJECTS: usize = 10_000;
+
+/// `System`, counting the allocations made by threads which are counting
+struct Counting;
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count() {
+    if COUNTING.try_with(Cell::get).unwrap_or(false) {
+        ALLOCATIONS.with(|allocs| allocs.set(allocs.get() + 1));
+    }
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Allocates `OBJECTS` objects in a context created with `capacity`, returning how many times the
+/// global allocator was called meanwhile
+fn allocations(capacity: usize) -> usize {
+    let ctx = crate::basics::context(GcConfig::default().capacity(capacity).allocator(System));
+    // Anything set up lazily on the first allocation isn't counted
+    drop(ctx.alloc(0u64));
+
+    ALLOCATIONS.set(0);
+    COUNTING.set(true);
+    let objects: Vec<Gc<u64>> = (0..OBJECTS as u64).map(|i| ctx.alloc(i)).collect();
+    COUNTING.set(false);
+    let allocations = ALLOCATIONS.get();
+
+    drop(objects);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+    allocations
+}
+
+pub fn check_all() {
+    // The `Vec` holding the objects only allocates once, since `collect` knows how many there are
+    let without = allocations(0);
+    let with = allocations(OBJECTS);
+    println!("{OBJECTS} objects without capacity: {without} allocations, with capacity: {with}");
+    // The set kept by debug builds grows by however many nodes its tree needs
+    if !cfg!(debug_assertions) {
+        assert!(without > 1, "the bookkeeping never grew");
+        assert_eq!(with, 1, "the bookkeeping grew despite the capacity");
+    }
+}
@@ -0,0 +1,136 @@
+//! Swaps the children of objects thousands of times with `GcCell::set` and `GcCell::replace`,
+//! collecting along the way
+//!
+//! A child may only be freed once no cell holds it and no handle to it is left, and every child
+//! has to be freed once that's the case
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use gc::{Gc, GcAble, GcCell, GcContext};
+
+const SWAPS: usize = 5000;
+
+struct Child {
+    id: usize,
+    dropped: Arc<AtomicUsize>,
+}
+
+// SAFETY: there are no `Gc<_>`s in a `Child`
+unsafe impl GcAble for Child {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        self.dropped.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// A `Gc<_>` can only be put in a `GcCell` as part of a `GcAble` value
+#[derive(Clone)]
+struct ChildRef(Gc<Child>);
+
+// SAFETY: every method forwards to the `Gc<_>` in a `ChildRef`
+unsafe impl GcAble for ChildRef {
+    unsafe fn mark(&self) {
+        unsafe { self.0.mark() }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { self.0.inc_root_count() }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self.0.dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { self.0.set_not_root() }
+    }
+}
+
+struct Parent {
+    child: GcCell<ChildRef>,
+}
+
+// SAFETY: every method forwards to `child`, the only `GcAble` field of a `Parent`
+unsafe impl GcAble for Parent {
+    unsafe fn mark(&self) {
+        unsafe { self.child.mark() }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { self.child.inc_root_count() }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self.child.dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { self.child.set_not_root() }
+    }
+}
+
+pub fn check_all() {
+    let ctx = GcContext::new();
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let child = |id| {
+        ChildRef(ctx.alloc(Child {
+            id,
+            dropped: Arc::clone(&dropped),
+        }))
+    };
+    let (a, b) = (
+        ctx.alloc(Parent {
+            child: GcCell::new(child(0)),
+        }),
+        ctx.alloc(Parent {
+            child: GcCell::new(child(1)),
+        }),
+    );
+
+    let mut taken = Vec::new();
+    for id in 2..SWAPS {
+        match id % 3 {
+            0 => a.child.set(child(id)),
+            // Moves `a`'s child into `b`, dropping `b`'s
+            1 => b.child.set(a.child.replace(child(id))),
+            // Keeps `b`'s old child around as a root
+            _ => taken.push(b.child.replace(child(id))),
+        }
+        if id % 100 == 0 {
+            ctx.force_collect();
+            assert_eq!(
+                ctx.stats().live_allocations,
+                2 + 2 + taken.len(),
+                "a replaced child wasn't freed"
+            );
+        }
+    }
+    ctx.force_collect();
+    // Reading every child left would be a use after free if any of them had been freed early
+    let (a_child, b_child) = (a.child.get().0.id, b.child.get().0.id);
+    assert!(a_child >= SWAPS - 3 && b_child >= SWAPS - 3);
+    // After the first, each was moved from `a` to `b` right after being stored in `a`
+    assert!(taken.iter().skip(1).all(|child| child.0.id % 3 == 0));
+    assert_eq!(
+        dropped.load(Ordering::SeqCst),
+        SWAPS - 2 - taken.len(),
+        "a child was freed twice, or never"
+    );
+
+    drop((a, b, taken));
+    ctx.force_collect();
+    assert_eq!(dropped.load(Ordering::SeqCst), SWAPS);
+    ctx.assert_no_leaks();
+}
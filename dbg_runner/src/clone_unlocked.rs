@@ -0,0 +1,103 @@
+//! Checks that cloning and dropping a `Gc<_>` never takes its context's lock, so it works while a
+//! collection holds it, such as from a `Drop` run by the sweep, and keeps the root count exact
+//! while threads clone it alongside collections
+//!
+//! How much this saves is measured by `cargo bench -p gc --bench clone`
+
+use std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    thread,
+};
+
+use gc::{Gc, GcAble, GcConfig, GcContext};
+
+/// Clones its target as it's dropped, while the sweep holds the context's lock
+struct Cloner {
+    target: Gc<u32>,
+    clones: &'static AtomicUsize,
+}
+
+// SAFETY: every method forwards to `target`, the only `Gc<_>` in a `Cloner`
+unsafe impl GcAble for Cloner {
+    unsafe fn mark(&self) {
+        unsafe { self.target.mark() }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { self.target.inc_root_count() }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self.target.dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { self.target.set_not_root() }
+    }
+}
+
+impl Drop for Cloner {
+    fn drop(&mut self) {
+        let clones = (0..10).map(|_| self.target.clone()).collect::<Vec<_>>();
+        assert!(clones.iter().all(|clone| Gc::ptr_eq(clone, &self.target)));
+        self.clones.fetch_add(clones.len(), Ordering::SeqCst);
+    }
+}
+
+fn check_in_sweep() {
+    static CLONES: AtomicUsize = AtomicUsize::new(0);
+    let ctx = GcContext::with_config(GcConfig::default().deterministic(true));
+    let target = ctx.alloc(7u32);
+    for _ in 0..100 {
+        ctx.alloc(Cloner {
+            target: target.clone(),
+            clones: &CLONES,
+        });
+    }
+    ctx.force_collect();
+    assert_eq!(CLONES.load(Ordering::SeqCst), 1000);
+    assert_eq!(Gc::strong_count(&target), 1);
+    assert_eq!(ctx.stats().live_allocations, 1);
+
+    drop(target);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
+
+fn check_threads() {
+    let ctx = GcContext::with_config(GcConfig::default().deterministic(true));
+    let target = ctx.alloc(7u32);
+    let done = AtomicBool::new(false);
+    thread::scope(|s| {
+        s.spawn(|| {
+            while !done.load(Ordering::SeqCst) {
+                ctx.force_collect();
+            }
+        });
+        let cloners = (0..4)
+            .map(|_| {
+                s.spawn(|| {
+                    for _ in 0..100_000 {
+                        let clone = target.clone();
+                        assert_eq!(*clone, 7);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for cloner in cloners {
+            cloner.join().unwrap();
+        }
+        done.store(true, Ordering::SeqCst);
+    });
+    assert_eq!(Gc::strong_count(&target), 1);
+    ctx.consistency_check();
+
+    drop(target);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
+
+pub fn check_all() {
+    check_in_sweep();
+    check_threads();
+}
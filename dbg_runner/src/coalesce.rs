@@ -0,0 +1,42 @@
+//! Checks that threads calling `force_collect` at the same time share a collection, instead of
+//! each running a full collection right after the other
+
+use std::{
+    sync::{Arc, Barrier},
+    thread,
+};
+
+use gc::{Gc, GcConfig, GcContext};
+
+const THREADS: usize = 8;
+
+pub fn check_all() {
+    let ctx = GcContext::with_config(GcConfig::default().deterministic(true));
+    // Enough live objects that a collection takes a while, so every thread is waiting for the
+    // first one to finish
+    let live: Vec<Gc<u64>> = (0..50_000).map(|i| ctx.alloc(i)).collect();
+    let before = ctx.runtime_metrics().collections;
+
+    let barrier = Arc::new(Barrier::new(THREADS));
+    let threads: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let ctx = ctx.clone();
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                ctx.force_collect();
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    let collections = ctx.runtime_metrics().collections - before;
+    println!("{THREADS} concurrent force_collects ran {collections} collections");
+    assert!(
+        (1..THREADS as u64).contains(&collections),
+        "concurrent force_collects weren't coalesced: {collections} collections",
+    );
+    drop(live);
+}
@@ -0,0 +1,74 @@
+//! Checks that a context's collector thread is named by `GcConfig::thread_name`, and runs at a
+//! raised nice value if `GcConfig::low_priority` is set
+//!
+//! Threads are found through `/proc`, so this only checks anything on Linux
+
+use std::{
+    fs,
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use gc::{GcConfig, GcContext};
+
+/// The name and nice value of the thread whose `/proc` directory is `task`, `None` if it exited
+fn name_and_nice(task: &Path) -> Option<(String, i32)> {
+    let comm = fs::read_to_string(task.join("comm")).ok()?;
+    let stat = fs::read_to_string(task.join("stat")).ok()?;
+    // The fields after the parenthesized name start at the 3rd, and the nice value is the 19th
+    let mut fields = stat[stat.rfind(')').unwrap() + 1..].split_whitespace();
+    let nice = fields.nth(19 - 3).unwrap().parse().unwrap();
+    Some((comm.trim_end().to_string(), nice))
+}
+
+/// The nice value of every thread of this process named `name`, which Linux truncates to 15 bytes
+fn nice_values(name: &str) -> Vec<i32> {
+    let name = &name[..name.len().min(15)];
+    fs::read_dir("/proc/self/task")
+        .unwrap()
+        .filter_map(|task| name_and_nice(&task.unwrap().path()))
+        .filter(|(comm, _)| comm == name)
+        .map(|(_, nice)| nice)
+        .collect()
+}
+
+/// Waits up to 10 seconds for the nice values of the threads named `name` to be `expected`, since
+/// a thread names itself and sets its priority once it's started
+fn wait_for_threads(name: &str, expected: impl Fn(&[i32]) -> bool) {
+    let start = Instant::now();
+    loop {
+        let found = nice_values(name);
+        if expected(&found) {
+            return;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "found threads named {name:?} with nice values {found:?}"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+pub fn check_all() {
+    if !cfg!(target_os = "linux") {
+        return;
+    }
+    let (_, base) = name_and_nice(Path::new("/proc/thread-self")).unwrap();
+
+    let named = GcContext::with_config(GcConfig::default().thread_name("gc-named"));
+    wait_for_threads("gc-named", |found| found == [base]);
+    let low = GcContext::with_config(
+        GcConfig::default()
+            .thread_name("gc-low-priority-check")
+            .low_priority(true),
+    );
+    wait_for_threads("gc-low-priority-check", |found| {
+        found == [(base + 10).min(19)]
+    });
+
+    // Every other context is named the default
+    let default = GcContext::new();
+    wait_for_threads("wasm_gc-collector", |found| !found.is_empty());
+    drop((named, low, default));
+}
@@ -0,0 +1,161 @@
+//! Checks that contexts are independent heaps: collecting one never touches another's objects,
+//! objects outlive the `GcContext` they were allocated with, and mixing contexts up is caught
+
+use std::{
+    mem,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use gc::{Gc, GcAble, GcConfig, GcContext};
+
+struct Counted(Arc<AtomicUsize>);
+
+// SAFETY: there are no `Gc<_>`s in a `Counted`
+unsafe impl GcAble for Counted {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+struct Holder(Gc<Counted>);
+
+// SAFETY: every method forwards to the `Gc<_>` in a `Holder`
+unsafe impl GcAble for Holder {
+    unsafe fn mark(&self) {
+        unsafe { self.0.mark() }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { self.0.inc_root_count() }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self.0.dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { self.0.set_not_root() }
+    }
+}
+
+fn check_independent() {
+    let (a, b) = (GcContext::new(), GcContext::new());
+    let (a_dropped, b_dropped) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+    let a_kept = a.alloc(Counted(Arc::clone(&a_dropped)));
+    let mut b_kept = b.alloc(Counted(Arc::clone(&b_dropped)));
+    // Holding a `GcRefMut` skips every collection of `b`, including by its own collector
+    let borrow = b_kept.get_mut().unwrap();
+    for _ in 0..10 {
+        a.alloc(Counted(Arc::clone(&a_dropped)));
+        b.alloc(Counted(Arc::clone(&b_dropped)));
+    }
+
+    a.force_collect();
+    assert_eq!(a_dropped.load(Ordering::SeqCst), 10);
+    // Nothing of `b`'s was collected, not even its garbage
+    assert_eq!(b_dropped.load(Ordering::SeqCst), 0);
+
+    drop(borrow);
+    b.force_collect();
+    assert_eq!(b_dropped.load(Ordering::SeqCst), 10);
+    assert_eq!(a_dropped.load(Ordering::SeqCst), 10);
+
+    drop((a_kept, b_kept));
+    a.force_collect();
+    b.force_collect();
+    assert_eq!(
+        (
+            a_dropped.load(Ordering::SeqCst),
+            b_dropped.load(Ordering::SeqCst)
+        ),
+        (11, 11)
+    );
+}
+
+/// The global context is just another context
+fn check_global() {
+    let ctx = GcContext::new();
+    let global = Gc::new(1u32);
+    let local = ctx.alloc(2u32);
+    ctx.force_collect();
+    gc::force_collect();
+    assert_eq!((*global, *local), (1, 2));
+}
+
+/// Objects keep their context alive once every `GcContext` for it is dropped, and are still
+/// collected by it
+fn check_outlives_handle() {
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let gc = {
+        let ctx = GcContext::new();
+        ctx.alloc(Counted(Arc::clone(&dropped)))
+    };
+    thread::sleep(Duration::from_millis(10));
+    assert_eq!(dropped.load(Ordering::SeqCst), 0);
+
+    drop(gc);
+    let start = Instant::now();
+    while dropped.load(Ordering::SeqCst) == 0 {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "an object outliving its `GcContext` wasn't collected"
+        );
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Storing a `Gc<_>` of one context in an object of another panics once the other is collected,
+/// instead of marking an object that context doesn't own
+fn check_mixed() {
+    // Only collected when asked to, so the panic happens here and not on `a`'s collector thread
+    let a = GcContext::with_config(
+        GcConfig::default()
+            .alloc_watermark(usize::MAX)
+            .max_interval(Duration::from_secs(3600)),
+    );
+    let b = GcContext::new();
+    let dropped = Arc::new(AtomicUsize::new(0));
+    // Rooted, since `b` doesn't know about the reference from `a`'s object
+    let foreign = b.alloc(Counted(Arc::clone(&dropped)));
+    let holder = a.alloc(Holder(foreign.clone()));
+
+    let collected = panic::catch_unwind(AssertUnwindSafe(|| a.force_collect()));
+    let payload = collected.expect_err("collecting a context with a foreign `Gc` didn't panic");
+    let msg = payload.downcast_ref::<String>().unwrap();
+    assert!(
+        msg.contains("a `Gc<_>` from one `GcContext` was stored in an object of another"),
+        "{msg}"
+    );
+
+    // The panic poisoned `a`, so it's leaked, but `b` is still usable
+    mem::forget((holder, a));
+    b.force_collect();
+    assert_eq!(dropped.load(Ordering::SeqCst), 0);
+    drop(foreign);
+    b.force_collect();
+    assert_eq!(dropped.load(Ordering::SeqCst), 1);
+    b.assert_no_leaks();
+}
+
+pub fn check_all() {
+    check_independent();
+    check_global();
+    check_outlives_handle();
+    check_mixed();
+}
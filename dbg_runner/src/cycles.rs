@@ -0,0 +1,76 @@
+//! Checks that cycles with no root outside of them are collected
+//!
+//! Each topology is built from nodes which count their drops, every root is dropped, then a single
+//! full collection has to free every node
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use gc::{Gc, GcCell};
+
+struct Node {
+    dropped: Arc<AtomicUsize>,
+    edges: GcCell<Vec<Gc<Node>>>,
+}
+
+gc::impl_gc_able!(Node => |node, visit| visit(&node.edges));
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        self.dropped.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Builds `n` nodes linked by `edges`, then checks they're all freed once unrooted
+fn check(name: &str, n: usize, edges: &[(usize, usize)]) {
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let nodes: Vec<Gc<Node>> = (0..n)
+        .map(|_| {
+            Gc::new(Node {
+                dropped: Arc::clone(&dropped),
+                edges: GcCell::new(Vec::new()),
+            })
+        })
+        .collect();
+    for &(from, to) in edges {
+        let mut out = nodes[from].edges.get();
+        out.push(nodes[to].clone());
+        nodes[from].edges.set(out);
+    }
+
+    gc::force_collect();
+    assert_eq!(
+        dropped.load(Ordering::SeqCst),
+        0,
+        "{name}: a node was freed while rooted",
+    );
+    drop(nodes);
+    gc::force_collect();
+    assert_eq!(
+        dropped.load(Ordering::SeqCst),
+        n,
+        "{name}: not every node was freed",
+    );
+    gc::consistency_check();
+}
+
+pub fn check_all() {
+    check("self-loop", 1, &[(0, 0)]);
+    check("2-cycle", 2, &[(0, 1), (1, 0)]);
+    check("3-cycle", 3, &[(0, 1), (1, 2), (2, 0)]);
+    // Two cycles sharing node 0
+    check(
+        "figure-eight",
+        5,
+        &[(0, 1), (1, 2), (2, 0), (0, 3), (3, 4), (4, 0)],
+    );
+    // Nodes 3 and 4 lead into the cycle without being part of it
+    check(
+        "cycle-with-tail",
+        5,
+        &[(0, 1), (1, 2), (2, 0), (3, 4), (4, 0)],
+    );
+    assert_eq!(gc::stats().live_allocations, 0);
+}
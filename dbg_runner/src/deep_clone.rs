@@ -0,0 +1,93 @@
+//! Checks `Gc::clone_deep` on a diamond
+//!
+//! The copy has to have the same shape, sharing the bottom node between both sides just like the
+//! original, while sharing no object with the original
+
+use std::collections::HashSet;
+
+use gc::{DeepClone, DeepCloner, Gc, GcAble, GcContext};
+
+struct Node {
+    name: &'static str,
+    edges: Vec<Gc<Node>>,
+}
+
+// SAFETY: every method forwards to `edges`, the only `Gc<_>`s in a `Node`
+unsafe impl GcAble for Node {
+    unsafe fn mark(&self) {
+        self.edges.iter().for_each(|edge| unsafe { edge.mark() })
+    }
+
+    unsafe fn inc_root_count(&self) {
+        self.edges
+            .iter()
+            .for_each(|edge| unsafe { edge.inc_root_count() })
+    }
+
+    unsafe fn dec_root_count(&self) {
+        self.edges
+            .iter()
+            .for_each(|edge| unsafe { edge.dec_root_count() })
+    }
+
+    unsafe fn set_not_root(&self) {
+        self.edges
+            .iter()
+            .for_each(|edge| unsafe { edge.set_not_root() })
+    }
+}
+
+impl DeepClone for Node {
+    fn clone_deep(&self, cloner: &mut DeepCloner) -> Self {
+        Node {
+            name: self.name,
+            edges: self
+                .edges
+                .iter()
+                .map(|edge| cloner.clone_gc(edge))
+                .collect(),
+        }
+    }
+}
+
+/// Every node reachable from `top`, by address
+fn reachable(top: &Gc<Node>) -> HashSet<*const Node> {
+    let mut seen = HashSet::new();
+    let mut stack = vec![top.clone()];
+    while let Some(node) = stack.pop() {
+        if seen.insert(Gc::as_ptr(&node)) {
+            stack.extend(node.edges.iter().cloned());
+        }
+    }
+    seen
+}
+
+pub fn check_all() {
+    let ctx = GcContext::new();
+    let node = |name, edges| ctx.alloc(Node { name, edges });
+    let bottom = node("bottom", Vec::new());
+    let left = node("left", vec![bottom.clone()]);
+    let right = node("right", vec![bottom]);
+    let top = node("top", vec![left, right]);
+
+    let copy = top.clone_deep();
+    ctx.force_collect();
+    assert_eq!(ctx.stats().live_allocations, 8);
+
+    // The same shape, with the bottom shared
+    let (left, right) = (&copy.edges[0], &copy.edges[1]);
+    assert_eq!((left.name, right.name), ("left", "right"));
+    let bottom = &left.edges[0];
+    assert_eq!(Gc::as_ptr(bottom), Gc::as_ptr(&right.edges[0]));
+    assert_eq!(bottom.name, "bottom");
+
+    // Nothing is shared
+    assert!(reachable(&top).is_disjoint(&reachable(&copy)));
+
+    drop(top);
+    ctx.force_collect();
+    assert_eq!(ctx.stats().live_allocations, 4);
+    drop(copy);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
@@ -0,0 +1,187 @@
+//! Checks `GcConfig::deterministic`: nothing is collected unless the caller asks, and the same
+//! operations free the same objects in the same order every time
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use gc::{Gc, GcAble, GcCell, GcConfig, GcContext};
+
+#[derive(Clone)]
+struct Link(Option<Gc<Node>>);
+
+// SAFETY: every method forwards to the `Gc<_>` in a `Link`, if there is one
+unsafe impl GcAble for Link {
+    unsafe fn mark(&self) {
+        self.0.iter().for_each(|gc| unsafe { gc.mark() })
+    }
+
+    unsafe fn inc_root_count(&self) {
+        self.0.iter().for_each(|gc| unsafe { gc.inc_root_count() })
+    }
+
+    unsafe fn dec_root_count(&self) {
+        self.0.iter().for_each(|gc| unsafe { gc.dec_root_count() })
+    }
+
+    unsafe fn set_not_root(&self) {
+        self.0.iter().for_each(|gc| unsafe { gc.set_not_root() })
+    }
+}
+
+struct Node {
+    id: usize,
+    dropped: Arc<Mutex<Vec<usize>>>,
+    next: GcCell<Link>,
+}
+
+// SAFETY: every method forwards to `next`, the only `GcAble` field of a `Node`
+unsafe impl GcAble for Node {
+    unsafe fn mark(&self) {
+        unsafe { self.next.mark() }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { self.next.inc_root_count() }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self.next.dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { self.next.set_not_root() }
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        self.dropped.lock().unwrap().push(self.id);
+    }
+}
+
+/// Would collect every millisecond and every 10 allocations if it weren't deterministic
+fn context() -> GcContext {
+    GcContext::with_config(
+        GcConfig::default()
+            .deterministic(true)
+            .incremental(10)
+            .alloc_watermark(10)
+            .max_interval(Duration::from_millis(1))
+            .thread_name(THREAD_NAME),
+    )
+}
+
+/// Short enough to fit in a Linux thread name
+const THREAD_NAME: &str = "deterministic";
+
+/// Whether a thread named `name` is running in this process
+#[cfg(target_os = "linux")]
+fn thread_running(name: &str) -> bool {
+    std::fs::read_dir("/proc/self/task").unwrap().any(|task| {
+        let comm = std::fs::read_to_string(task.unwrap().path().join("comm")).unwrap_or_default();
+        comm.trim_end() == name
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn thread_running(_: &str) -> bool {
+    false
+}
+
+/// Allocates cycles and chains of garbage with a few survivors, collecting a step at a time
+///
+/// Returns the order the nodes were dropped in and a dump of what's left
+fn run() -> (Vec<usize>, String) {
+    let ctx = context();
+    let dropped = Arc::new(Mutex::new(Vec::new()));
+    let mut kept: Vec<Gc<Node>> = Vec::new();
+    for id in 0..1000 {
+        let node = ctx.alloc(Node {
+            id,
+            dropped: Arc::clone(&dropped),
+            next: GcCell::new(Link(kept.last().cloned())),
+        });
+        if id % 3 == 0 {
+            node.next.set(Link(Some(node.clone())));
+        }
+        if id % 100 == 0 {
+            kept.push(node);
+        }
+        if id % 250 == 0 {
+            while !ctx.collect_step() {}
+        }
+    }
+    ctx.force_collect();
+    let dump = ctx.dump_dot();
+    drop(kept);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+    let dropped = dropped.lock().unwrap().clone();
+    (dropped, dump)
+}
+
+/// Nothing carries on with a collection between the caller's steps, however long it waits
+fn check_only_when_asked() {
+    let ctx = context();
+    let dropped = Arc::new(Mutex::new(Vec::new()));
+    let mut kept = Vec::new();
+    for id in 0..100 {
+        let node = ctx.alloc(Node {
+            id,
+            dropped: Arc::clone(&dropped),
+            next: GcCell::new(Link(None)),
+        });
+        if id % 2 == 0 {
+            kept.push(node);
+        }
+    }
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(ctx.stats().live_allocations, 100);
+    assert!(
+        !thread_running(THREAD_NAME),
+        "a collector thread was started"
+    );
+
+    // Each step leaves the collection where it was until the next one
+    let mut steps = 0;
+    loop {
+        steps += 1;
+        let done = ctx.collect_step();
+        let freed = dropped.lock().unwrap().len();
+        thread::sleep(Duration::from_millis(1));
+        assert_eq!(dropped.lock().unwrap().len(), freed);
+        if done {
+            break;
+        }
+    }
+    // Marking the kept half takes a step per 10 of them
+    assert!(steps >= 50 / 10, "{steps} steps");
+    assert_eq!(dropped.lock().unwrap().len(), 50);
+    drop(kept);
+
+    // Waiting for a collection is asking for one
+    ctx.alloc(Node {
+        id: 100,
+        dropped: Arc::clone(&dropped),
+        next: GcCell::new(Link(None)),
+    });
+    ctx.wait_for_collection();
+    assert_eq!(dropped.lock().unwrap().len(), 101);
+    ctx.assert_no_leaks();
+}
+
+fn check_reproducible() {
+    let (dropped, dump) = run();
+    assert_eq!(dropped.len(), 1000);
+    for _ in 0..3 {
+        assert_eq!(run(), (dropped.clone(), dump.clone()));
+    }
+}
+
+pub fn check_all() {
+    check_only_when_asked();
+    check_reproducible();
+}
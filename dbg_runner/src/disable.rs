@@ -0,0 +1,80 @@
+//! Checks that `GcContext::disable` stops every collection, explicit ones included, until
+//! `GcContext::enable` was called as many times, which then runs the collections held off
+//! meanwhile
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    thread,
+    time::{Duration, Instant},
+};
+
+use gc::{GcConfig, GcContext};
+
+/// Waits for up to 10 seconds for the collector to free everything in `ctx`
+fn wait_for_empty(ctx: &GcContext) {
+    let start = Instant::now();
+    while ctx.stats().live_allocations > 0 {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "nothing was collected"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn check_nested() {
+    // Nothing wakes the collector but `enable`
+    let ctx = GcContext::with_config(
+        GcConfig::default()
+            .alloc_watermark(usize::MAX)
+            .max_interval(Duration::from_secs(3600)),
+    );
+
+    ctx.disable();
+    ctx.disable();
+    for i in 0..100u32 {
+        ctx.alloc(i);
+    }
+    ctx.force_collect();
+    assert!(!ctx.collect_step());
+    assert_eq!(ctx.stats().live_allocations, 100);
+
+    ctx.enable();
+    ctx.force_collect();
+    assert_eq!(ctx.stats().live_allocations, 100);
+
+    // The skipped collection was queued, and runs once enabled
+    ctx.enable();
+    wait_for_empty(&ctx);
+
+    let enabled = panic::catch_unwind(AssertUnwindSafe(|| ctx.enable()));
+    let payload = enabled.expect_err("enabling more than disabling didn't panic");
+    assert_eq!(
+        payload.downcast_ref::<String>().unwrap(),
+        "`GcContext::enable` was called more times than `GcContext::disable`"
+    );
+    ctx.assert_no_leaks();
+}
+
+/// The background collector doesn't collect while disabled, and collects once enabled
+fn check_background() {
+    let ctx = GcContext::with_config(
+        GcConfig::default()
+            .alloc_watermark(100)
+            .max_interval(Duration::from_millis(10)),
+    );
+    ctx.disable();
+    for i in 0..1000u32 {
+        ctx.alloc(i);
+    }
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(ctx.stats().live_allocations, 1000);
+    ctx.enable();
+    wait_for_empty(&ctx);
+    ctx.assert_no_leaks();
+}
+
+pub fn check_all() {
+    check_nested();
+    check_background();
+}
@@ -0,0 +1,115 @@
+//! Checks a custom `CollectorDriver` standing in for an executor: it's started once, woken only
+//! once a collection is requested, and collects entirely through `Collector::collect_step` on a
+//! task of its own, which stops once the context is gone
+//!
+//! Like the default driver, the task also collects once `Collector::max_interval` passes without
+//! a wake, since garbage made while a collection is partway done only goes once the next one runs
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use gc::{Collector, CollectorDriver, GcConfig, GcContext};
+
+/// Runs a collection on its task every time it's woken, and whenever `max_interval` passes
+/// without a wake
+struct Executor {
+    wakes: Arc<AtomicUsize>,
+    /// The number of collections the task finished
+    collections: Arc<AtomicUsize>,
+    sender: Mutex<Sender<()>>,
+    receiver: Mutex<Option<Receiver<()>>>,
+    task: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+/// Waits for a wake, or for `max_interval` if there is one, returning `false` once the sender is
+/// gone
+fn wait(receiver: &Receiver<()>, max_interval: Option<Duration>) -> bool {
+    match max_interval {
+        Some(interval) => match receiver.recv_timeout(interval) {
+            Ok(()) | Err(RecvTimeoutError::Timeout) => true,
+            Err(RecvTimeoutError::Disconnected) => false,
+        },
+        None => receiver.recv().is_ok(),
+    }
+}
+
+impl CollectorDriver for Executor {
+    fn start(&self, collector: Collector) {
+        let receiver = self.receiver.lock().unwrap().take().expect("started twice");
+        let collections = Arc::clone(&self.collections);
+        let task = thread::spawn(move || {
+            // Ends once the context, which owns the driver and so the sender, is dropped
+            while wait(&receiver, collector.max_interval()) {
+                loop {
+                    match collector.collect_step() {
+                        Some(true) => drop(collections.fetch_add(1, Ordering::SeqCst)),
+                        Some(false) => continue,
+                        None => return,
+                    }
+                    if !collector.is_requested() {
+                        break;
+                    }
+                }
+            }
+        });
+        *self.task.lock().unwrap() = Some(task);
+    }
+
+    fn wake(&self) {
+        self.wakes.fetch_add(1, Ordering::SeqCst);
+        let _ = self.sender.lock().unwrap().send(());
+    }
+}
+
+pub fn check_all() {
+    let (sender, receiver) = mpsc::channel();
+    let (wakes, collections, task) = Default::default();
+    let executor = Executor {
+        wakes: Arc::clone(&wakes),
+        collections: Arc::clone(&collections),
+        sender: Mutex::new(sender),
+        receiver: Mutex::new(Some(receiver)),
+        task: Arc::clone(&task),
+    };
+    let ctx = GcContext::with_config(
+        GcConfig::default()
+            .alloc_watermark(100)
+            .max_interval(Duration::from_millis(10))
+            .incremental(10)
+            .driver(executor),
+    );
+    assert!(task.lock().unwrap().is_some(), "the driver wasn't started");
+
+    let kept = (0..99u32).map(|i| ctx.alloc(i)).collect::<Vec<_>>();
+    assert_eq!(wakes.load(Ordering::SeqCst), 0);
+    for i in 0..1000u32 {
+        ctx.alloc(i);
+    }
+    assert!(wakes.load(Ordering::SeqCst) > 0);
+
+    // Everything but `kept` is collected by the task, without being asked any other way
+    let start = Instant::now();
+    while ctx.stats().live_allocations > kept.len() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "the task didn't collect"
+        );
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert!(collections.load(Ordering::SeqCst) > 0);
+    assert_eq!(*kept[98], 98);
+
+    drop(kept);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+    drop(ctx);
+    let task = task.lock().unwrap().take().unwrap();
+    task.join().unwrap();
+}
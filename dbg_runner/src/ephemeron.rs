@@ -0,0 +1,87 @@
+//! Checks that the value of an `Ephemeron` is kept alive exactly as long as its key is reachable,
+//! including through chains of ephemerons and with incremental marking
+
+use gc::{Ephemeron, Gc, GcCell, GcConfig, GcContext, WeakGc};
+
+struct Obj {
+    id: u32,
+    /// Only set on values, pointing back to their key
+    key: Option<Gc<Obj>>,
+}
+
+gc::impl_gc_able!(Obj => |obj, visit| visit(&obj.key));
+
+type Table = Gc<GcCell<Vec<Ephemeron<Obj, Obj>>>>;
+
+fn obj(ctx: &GcContext, id: u32, key: Option<&Gc<Obj>>) -> Gc<Obj> {
+    ctx.alloc(Obj {
+        id,
+        key: key.cloned(),
+    })
+}
+
+/// Maps `key` to a new value in `table`, returning a handle to the value which doesn't keep it
+/// alive
+fn insert(ctx: &GcContext, table: &Table, key: &Gc<Obj>, id: u32) -> WeakGc<Obj> {
+    let value = obj(ctx, id, Some(key));
+    let mut entries = table.get();
+    entries.push(Ephemeron::new(key, &value));
+    table.set(entries);
+    Gc::downgrade(&value)
+}
+
+fn check(config: GcConfig) {
+    let ctx = GcContext::with_config(config.deterministic(true));
+    let table: Table = ctx.alloc(GcCell::new(Vec::new()));
+
+    let (alive, dead) = (obj(&ctx, 1, None), obj(&ctx, 2, None));
+    let alive_value = insert(&ctx, &table, &alive, 10);
+    let dead_value = insert(&ctx, &table, &dead, 20);
+    // `chained` is only reachable as the value of `chain`'s entry, and is the key of the entry
+    // before it, so reaching its value takes a second pass over the ephemerons
+    let chain = obj(&ctx, 3, None);
+    let (chained, chained_value) = {
+        let chained = obj(&ctx, 4, Some(&chain));
+        let chained_value = obj(&ctx, 40, Some(&chained));
+        let mut entries = table.get();
+        entries.insert(0, Ephemeron::new(&chained, &chained_value));
+        entries.push(Ephemeron::new(&chain, &chained));
+        table.set(entries);
+        (Gc::downgrade(&chained), Gc::downgrade(&chained_value))
+    };
+    drop(dead);
+
+    ctx.force_collect();
+    assert_eq!(
+        alive_value
+            .upgrade()
+            .expect("a live key's value was freed")
+            .id,
+        10
+    );
+    assert!(
+        dead_value.upgrade().is_none(),
+        "a dead key's value was kept"
+    );
+    assert!(chained.upgrade().is_some() && chained_value.upgrade().is_some());
+    let entries = table.get();
+    assert!(entries[2].get().is_none());
+    assert_eq!(entries[1].value().unwrap().key.as_ref().unwrap().id, 1);
+
+    // Values stay alive only through their key, even while the table is reachable
+    drop((alive, chain, entries));
+    ctx.force_collect();
+    assert!(alive_value.upgrade().is_none());
+    assert!(chained.upgrade().is_none() && chained_value.upgrade().is_none());
+    assert_eq!(ctx.stats().live_allocations, 1);
+
+    drop(table);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
+
+pub fn check_all() {
+    check(GcConfig::default());
+    check(GcConfig::default().incremental(1));
+    check(GcConfig::default().major_interval(3).promotion_threshold(1));
+}
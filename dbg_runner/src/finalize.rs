@@ -0,0 +1,116 @@
+//! Checks `Finalize`: every unreachable object is finalized exactly once, before any of the values
+//! found unreachable with it are dropped, and reachable objects never are
+
+use std::sync::{Arc, Mutex};
+
+use gc::{Finalize, Gc, GcAble};
+
+#[derive(Debug, PartialEq, Eq)]
+enum Event {
+    Finalized(u32),
+    Dropped(u32),
+}
+
+struct Node {
+    id: u32,
+    events: Arc<Mutex<Vec<Event>>>,
+    next: Option<Gc<Node>>,
+}
+
+// SAFETY: every method forwards to `next`, the only `Gc<_>` in a `Node`
+unsafe impl GcAble for Node {
+    unsafe fn mark(&self) {
+        if let Some(next) = &self.next {
+            unsafe { next.mark() }
+        }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        if let Some(next) = &self.next {
+            unsafe { next.inc_root_count() }
+        }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        if let Some(next) = &self.next {
+            unsafe { next.dec_root_count() }
+        }
+    }
+
+    unsafe fn set_not_root(&self) {
+        if let Some(next) = &self.next {
+            unsafe { next.set_not_root() }
+        }
+    }
+}
+
+impl Finalize for Node {
+    fn finalize(&self) {
+        self.events.lock().unwrap().push(Event::Finalized(self.id));
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        self.events.lock().unwrap().push(Event::Dropped(self.id));
+    }
+}
+
+/// Allocates a chain of finalized nodes with the given ids, returning its first node
+fn chain(events: &Arc<Mutex<Vec<Event>>>, ids: &[u32]) -> Gc<Node> {
+    let mut next = None;
+    for &id in ids.iter().rev() {
+        next = Some(Gc::new_finalized(Node {
+            id,
+            events: Arc::clone(events),
+            next,
+        }));
+    }
+    next.unwrap()
+}
+
+/// A chain found unreachable at once is finalized once, however many collections run after it
+/// becomes unreachable, and only then dropped
+fn check_once() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    drop(chain(&events, &[0, 1, 2]));
+    for _ in 0..3 {
+        gc::force_collect();
+    }
+
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 6, "{events:?}");
+    for id in 0..3 {
+        assert!(events[..3].contains(&Event::Finalized(id)), "{events:?}");
+        assert!(events[3..].contains(&Event::Dropped(id)), "{events:?}");
+    }
+}
+
+/// An object which is only reachable through another isn't finalized until that one is unrooted
+fn check_reachable() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let root = chain(&events, &[0, 1]);
+    for _ in 0..3 {
+        gc::force_collect();
+        assert!(
+            events.lock().unwrap().is_empty(),
+            "a reachable object was finalized"
+        );
+    }
+    assert_eq!(root.next.as_ref().unwrap().id, 1);
+
+    drop(root);
+    gc::force_collect();
+    let finalized = events
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|event| matches!(event, Event::Finalized(_)))
+        .count();
+    assert_eq!(finalized, 2);
+}
+
+pub fn check_all() {
+    check_once();
+    check_reachable();
+}
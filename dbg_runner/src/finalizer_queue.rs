@@ -0,0 +1,94 @@
+//! Checks `GcConfig::defer_finalizers`
+//!
+//! Deferred finalizers allocate in their own context, which would panic if they ran while the
+//! collector held its lock
+
+use std::{
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use gc::{Finalize, Gc, GcConfig, GcContext};
+
+static CTX: OnceLock<GcContext> = OnceLock::new();
+static FINALIZED: Mutex<Vec<Gc<u32>>> = Mutex::new(Vec::new());
+
+struct Resource {
+    child: Gc<u32>,
+}
+
+gc::impl_gc_able!(Resource => |res, visit| visit(&res.child));
+
+impl Finalize for Resource {
+    fn finalize(&self) {
+        // The child is kept alive until this runs, and the lock isn't held
+        let copy = CTX.get().unwrap().alloc(*self.child);
+        FINALIZED.lock().unwrap().push(copy);
+    }
+}
+
+fn finalized() -> Vec<u32> {
+    FINALIZED.lock().unwrap().iter().map(|num| **num).collect()
+}
+
+fn check_flush() {
+    let ctx = CTX.get().unwrap();
+    let res = ctx.alloc_finalized(Resource {
+        child: ctx.alloc(1),
+    });
+    let weak = Gc::downgrade(&res);
+    drop(res);
+
+    ctx.force_collect();
+    assert!(finalized().is_empty(), "a finalizer ran before the flush");
+    assert!(weak.upgrade().is_some(), "a queued object was freed");
+    assert_eq!(ctx.stats().live_allocations, 2);
+
+    assert_eq!(ctx.flush_finalizers(), 1);
+    assert_eq!(finalized(), [1]);
+    ctx.force_collect();
+    assert!(weak.upgrade().is_none(), "a finalized object wasn't freed");
+    // Only the finalizer's allocation is left, and nothing was finalized twice
+    assert_eq!(ctx.stats().live_allocations, 1);
+    assert_eq!(ctx.flush_finalizers(), 0);
+    assert_eq!(finalized(), [1]);
+
+    FINALIZED.lock().unwrap().clear();
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
+
+/// The collector's thread runs the queue by itself
+fn check_collector_flushes() {
+    let ctx = GcContext::with_config(
+        GcConfig::default()
+            .defer_finalizers(true)
+            .max_interval(Duration::from_millis(10)),
+    );
+    let res = ctx.alloc_finalized(Resource {
+        child: ctx.alloc(2),
+    });
+    drop(res);
+    let start = Instant::now();
+    while finalized().is_empty() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "the collector didn't run the queued finalizer",
+        );
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(finalized(), [2]);
+    FINALIZED.lock().unwrap().clear();
+}
+
+pub fn check_all() {
+    CTX.get_or_init(|| {
+        GcContext::with_config(
+            GcConfig::default()
+                .deterministic(true)
+                .defer_finalizers(true),
+        )
+    });
+    check_flush();
+    check_collector_flushes();
+}
@@ -0,0 +1,168 @@
+//! Checks `GcConfig::free_list_capacity`
+//!
+//! A churning workload is run with and without the free list against an allocator which counts
+//! its calls, then boxes reused from the list are checked to hold exactly what they were given,
+//! and `GcContext::trim` is checked to give the whole list back
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use gc::{Gc, GcCell, GcConfig, GcContext};
+
+#[derive(Clone, Default)]
+struct CountingAlloc {
+    allocs: Arc<AtomicUsize>,
+    freed_bytes: Arc<AtomicUsize>,
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.allocs.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.freed_bytes.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+struct Node {
+    id: u64,
+    payload: [u64; 4],
+    next: GcCell<Option<Gc<Node>>>,
+}
+
+gc::impl_gc_able!(Node => |node, visit| visit(&node.next));
+
+fn context(free_list_capacity: usize) -> (GcContext, CountingAlloc) {
+    let alloc = CountingAlloc::default();
+    let ctx = GcContext::with_config(
+        GcConfig::default()
+            .deterministic(true)
+            .allocator(alloc.clone())
+            .free_list_capacity(free_list_capacity),
+    );
+    (ctx, alloc)
+}
+
+/// Allocates `rounds` batches of short-lived lists, collecting after each, and returns how many
+/// times the allocator was called
+fn churn(free_list_capacity: usize, rounds: usize, per_round: usize) -> usize {
+    let (ctx, alloc) = context(free_list_capacity);
+    let start = Instant::now();
+    for round in 0..rounds {
+        let mut head: Option<Gc<Node>> = None;
+        for i in 0..per_round {
+            head = Some(ctx.alloc(Node {
+                id: (round * per_round + i) as u64,
+                payload: [i as u64; 4],
+                next: GcCell::new(head),
+            }));
+        }
+        drop(head);
+        ctx.force_collect();
+    }
+    let allocs = alloc.allocs.load(Ordering::Relaxed);
+    println!(
+        "free list capacity {free_list_capacity}: {allocs} allocator calls in {:?}",
+        start.elapsed(),
+    );
+    ctx.assert_no_leaks();
+    allocs
+}
+
+fn check_reuse_reinitializes() {
+    let (ctx, alloc) = context(16);
+    let stale = ctx.alloc(Node {
+        id: 1,
+        payload: [0xdead; 4],
+        next: GcCell::new(Some(ctx.alloc(Node {
+            id: 2,
+            payload: [0xbeef; 4],
+            next: GcCell::new(None),
+        }))),
+    });
+    drop(stale);
+    ctx.force_collect();
+    let before = alloc.allocs.load(Ordering::Relaxed);
+
+    let child = ctx.alloc(Node {
+        id: 3,
+        payload: [3; 4],
+        next: GcCell::new(None),
+    });
+    let parent = ctx.alloc(Node {
+        id: 4,
+        payload: [4; 4],
+        next: GcCell::new(Some(child)),
+    });
+    assert_eq!(
+        alloc.allocs.load(Ordering::Relaxed),
+        before,
+        "the freed boxes weren't reused",
+    );
+
+    // The reused boxes start over as young, unmarked objects with their own children
+    ctx.force_collect();
+    assert_eq!((parent.id, parent.payload), (4, [4; 4]));
+    let child = parent.next.get().expect("the child was lost");
+    assert_eq!((child.id, child.payload), (3, [3; 4]));
+    assert_eq!(ctx.stats().live_allocations, 2);
+    ctx.consistency_check();
+
+    drop((parent, child));
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
+
+fn check_trim() {
+    let (ctx, alloc) = context(1000);
+    let nodes: Vec<Gc<Node>> = (0..100)
+        .map(|id| {
+            ctx.alloc(Node {
+                id,
+                payload: [0; 4],
+                next: GcCell::new(None),
+            })
+        })
+        .collect();
+    drop(nodes);
+    ctx.force_collect();
+    assert_eq!(
+        alloc.freed_bytes.load(Ordering::Relaxed),
+        0,
+        "collected boxes weren't kept",
+    );
+
+    let released = ctx.trim();
+    assert!(released >= 100 * std::mem::size_of::<Node>());
+    assert_eq!(released, alloc.freed_bytes.load(Ordering::Relaxed));
+    assert_eq!(ctx.trim(), 0, "the free list wasn't emptied");
+
+    // Nothing is left to reuse
+    let before = alloc.allocs.load(Ordering::Relaxed);
+    let _node = ctx.alloc(Node {
+        id: 0,
+        payload: [0; 4],
+        next: GcCell::new(None),
+    });
+    assert_eq!(alloc.allocs.load(Ordering::Relaxed), before + 1);
+}
+
+pub fn check_all() {
+    let without = churn(0, 100, 1000);
+    let with = churn(1000, 100, 1000);
+    assert!(
+        with * 10 < without,
+        "the free list didn't save allocator calls: {with} with it, {without} without",
+    );
+    check_reuse_reinitializes();
+    check_trim();
+}
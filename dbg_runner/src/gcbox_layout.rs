@@ -0,0 +1,86 @@
+//! Checks that values are found where they were put in boxes of every shape: sized values of any
+//! alignment, zero-sized values, slices, uninitialized boxes and `Gc<dyn Trait>`s
+//!
+//! Every box is checked against its erased `GcBox<dyn GcAble>` as it's registered in debug builds,
+//! collections read the headers through the erased boxes, and `Gc::from_raw` finds them again from
+//! the value's address
+
+use std::fmt::Debug;
+
+use gc::{gc_unsize, Gc, GcAble, GcConfig, GcContext};
+
+#[derive(GcAble, Debug, Clone, PartialEq)]
+#[repr(align(64))]
+struct Aligned(u8);
+
+#[derive(GcAble, Debug, Clone, PartialEq)]
+struct Empty;
+
+trait Describe: GcAble {
+    fn describe(&self) -> String;
+}
+
+impl Describe for Aligned {
+    fn describe(&self) -> String {
+        format!("aligned {}", self.0)
+    }
+}
+
+impl Describe for u16 {
+    fn describe(&self) -> String {
+        format!("u16 {self}")
+    }
+}
+
+/// Checks `gc`'s value is aligned and reads back as `val`, through a collection and a round trip
+/// through a raw pointer
+fn check_sized<T: GcAble + Debug + PartialEq>(ctx: &GcContext, gc: Gc<T>, val: T) {
+    assert_eq!(gc.as_ptr() as usize % align_of::<T>(), 0);
+    ctx.force_collect();
+    assert_eq!(*gc, val);
+    let gc = unsafe { Gc::from_raw(Gc::into_raw(gc)) };
+    ctx.force_collect();
+    assert_eq!(*gc, val);
+}
+
+fn check_slice<T: GcAble + Debug + PartialEq + Clone>(ctx: &GcContext, vals: &[T]) {
+    let gc = ctx.alloc_slice(vals);
+    assert_eq!(gc.as_ptr() as *const T as usize % align_of::<T>(), 0);
+    ctx.force_collect();
+    assert_eq!(&*gc, vals);
+}
+
+pub fn check_all() {
+    let ctx = crate::basics::context(GcConfig::default());
+    check_sized(&ctx, ctx.alloc(1u8), 1);
+    check_sized(&ctx, ctx.alloc(u64::MAX), u64::MAX);
+    check_sized(&ctx, ctx.alloc(u128::MAX), u128::MAX);
+    check_sized(&ctx, ctx.alloc(()), ());
+    check_sized(&ctx, ctx.alloc(Empty), Empty);
+    check_sized(&ctx, ctx.alloc(Aligned(3)), Aligned(3));
+    check_sized(&ctx, ctx.alloc(ctx.alloc(5u8)), ctx.alloc(5u8));
+
+    check_slice::<u8>(&ctx, &[]);
+    check_slice(&ctx, &[1u8, 2, 3]);
+    check_slice(&ctx, &[u128::MAX, 0]);
+    check_slice(&ctx, &[Aligned(1), Aligned(2)]);
+    check_slice(&ctx, &[(), ()]);
+
+    let mut uninit = ctx.alloc_uninit::<Aligned>();
+    uninit.get_mut().unwrap().write(Aligned(4));
+    check_sized(&ctx, unsafe { Gc::assume_init(uninit) }, Aligned(4));
+
+    let aligned = ctx.alloc(Aligned(6));
+    let small = ctx.alloc(7u16);
+    let shapes: Vec<Gc<dyn Describe>> = vec![
+        gc_unsize!(aligned as dyn Describe),
+        gc_unsize!(small as dyn Describe),
+    ];
+    ctx.force_collect();
+    let described: Vec<_> = shapes.iter().map(|shape| shape.describe()).collect();
+    assert_eq!(described, ["aligned 6", "u16 7"]);
+
+    drop(shapes);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
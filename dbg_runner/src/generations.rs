@@ -0,0 +1,117 @@
+//! Times minor collections against major ones, with a large set of old objects which never change
+//! next to a steady churn of short-lived young ones
+//!
+//! Every object of the old set counts the times the collector scans it, which a minor collection
+//! never does, since it treats every old object as alive. Each round allocates exactly enough to
+//! wake the collector once
+
+use std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use gc::{Gc, GcAble, GcConfig, GcContext};
+
+const OLD: usize = 50_000;
+const ROUNDS: usize = 50;
+const PER_ROUND: u64 = 2000;
+/// One collection in this many is major when collecting generationally
+const MAJOR_INTERVAL: u64 = 10;
+
+static SCANNED: AtomicUsize = AtomicUsize::new(0);
+static SENTINEL_FREED: AtomicBool = AtomicBool::new(false);
+
+/// A long-lived object
+struct Old;
+
+unsafe impl GcAble for Old {
+    unsafe fn mark(&self) {
+        SCANNED.fetch_add(1, Ordering::Relaxed);
+    }
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+/// The first object allocated each round, which is garbage by the time the collector is woken
+struct Sentinel;
+
+unsafe impl GcAble for Sentinel {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+impl Drop for Sentinel {
+    fn drop(&mut self) {
+        SENTINEL_FREED.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Runs the workload, returning how long the collections took and how many times old objects were
+/// scanned
+fn run(major_interval: u64) -> (Duration, usize) {
+    let ctx = GcContext::with_config(
+        GcConfig::default()
+            .promotion_threshold(1)
+            .major_interval(major_interval)
+            .alloc_watermark(PER_ROUND as usize)
+            .max_interval(Duration::from_secs(3600)),
+    );
+    let old: Vec<Gc<Old>> = (0..OLD).map(|_| ctx.alloc(Old)).collect();
+    // Promotes the old set
+    ctx.force_collect();
+    SCANNED.store(0, Ordering::Relaxed);
+
+    let mut kept = Vec::new();
+    let mut elapsed = Duration::ZERO;
+    for round in 0..ROUNDS as u64 {
+        SENTINEL_FREED.store(false, Ordering::SeqCst);
+        ctx.alloc(Sentinel);
+        for i in 1..PER_ROUND {
+            let young = ctx.alloc(round * PER_ROUND + i);
+            if i % 100 == 0 {
+                kept.push(young);
+            }
+        }
+        // The last allocation woke the collector
+        let start = Instant::now();
+        while !SENTINEL_FREED.load(Ordering::SeqCst) {
+            thread::yield_now();
+        }
+        elapsed += start.elapsed();
+    }
+    let scanned = SCANNED.load(Ordering::Relaxed);
+
+    // Nothing reachable was freed by the minor collections
+    assert!(kept
+        .iter()
+        .enumerate()
+        .all(|(i, gc)| **gc == i as u64 / 19 * PER_ROUND + (i as u64 % 19 + 1) * 100));
+    ctx.force_collect();
+    assert_eq!(ctx.stats().live_allocations, OLD + kept.len());
+    drop((old, kept));
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+    (elapsed, scanned)
+}
+
+pub fn check_all() {
+    let (major_time, major_scanned) = run(1);
+    assert_eq!(major_scanned, OLD * ROUNDS);
+    let (minor_time, minor_scanned) = run(MAJOR_INTERVAL);
+    assert_eq!(major_scanned / minor_scanned, MAJOR_INTERVAL as usize);
+    println!(
+        "{ROUNDS} collections with {OLD} old objects: {major_time:?} all major, \
+         {minor_time:?} with 1 in {MAJOR_INTERVAL} major, old objects scanned {major_scanned} \
+         and {minor_scanned} times",
+    );
+}
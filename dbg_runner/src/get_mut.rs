@@ -0,0 +1,48 @@
+//! Checks `Gc::get_mut`, including moving `Gc<_>`s into and out of the value through it
+
+use gc::{Gc, GcConfig, GcContext};
+
+/// A `Gc<_>` taken out of the value is a root, and one put in stops being one
+fn check_move_out() {
+    let ctx = crate::basics::context(GcConfig::default());
+    let mut slot = ctx.alloc(Some(ctx.alloc(1u32)));
+    let taken = slot.get_mut().unwrap().take().unwrap();
+    ctx.force_collect();
+    assert!(
+        taken.is_alive(),
+        "a `Gc<_>` moved out through `get_mut` was freed"
+    );
+    assert_eq!(*taken, 1);
+
+    let weak = Gc::downgrade(&taken);
+    *slot.get_mut().unwrap() = Some(taken);
+    ctx.force_collect();
+    assert_eq!(**(*slot).as_ref().unwrap(), 1);
+    // Only reachable through `slot`, so it isn't a root anymore
+    drop(slot);
+    ctx.force_collect();
+    assert!(weak.upgrade().is_none());
+    ctx.assert_no_leaks();
+}
+
+/// Only a `Gc<_>` with no other handles or weak handles to its object gets one
+fn check_unique() {
+    let ctx = GcContext::new();
+    let mut gc = ctx.alloc(1u32);
+    *gc.get_mut().unwrap() += 1;
+
+    let other = gc.clone();
+    assert!(gc.get_mut().is_none());
+    drop(other);
+    let weak = Gc::downgrade(&gc);
+    assert!(gc.get_mut().is_none());
+    drop(weak);
+    ctx.force_collect();
+    *gc.get_mut().unwrap() += 1;
+    assert_eq!(*gc, 3);
+}
+
+pub fn check_all() {
+    check_move_out();
+    check_unique();
+}
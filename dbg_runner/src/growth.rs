@@ -0,0 +1,128 @@
+//! Checks that `GcConfig::growth_factor` collects less often as more of the heap survives
+//!
+//! The same blocks are allocated twice, once all kept and once all dropped right away. Kept, the
+//! heap doubles between collections, so there are only logarithmically many of them. Dropped, it
+//! only ever grows to twice the 64 KiB minimum before being collected back down.
+//!
+//! Nothing else wakes the collector, so a collection has to follow each time the heap outgrows
+//! twice its size after the last one, and never happen before that.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use gc::{GcAble, GcConfig, GcContext};
+
+const BLOCKS: usize = 20_000;
+const MIN_BASE: usize = 64 * 1024;
+/// More than the size of a `Block`'s box
+const SLACK: usize = 1024;
+
+struct Block([u64; 32]);
+
+// SAFETY: there are no `Gc<_>`s in a `Block`
+unsafe impl GcAble for Block {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+/// Garbage which sets its flag once dropped, which only a collection does
+struct Sentinel(Arc<AtomicBool>);
+
+// SAFETY: there are no `Gc<_>`s in a `Sentinel`
+unsafe impl GcAble for Sentinel {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+impl Drop for Sentinel {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Allocates garbage which sets the returned flag once collected
+fn sentinel(ctx: &GcContext) -> Arc<AtomicBool> {
+    let collected = Arc::new(AtomicBool::new(false));
+    ctx.alloc(Sentinel(Arc::clone(&collected)));
+    collected
+}
+
+/// Allocates every block, and returns the number of collections
+fn collections(keep: bool) -> usize {
+    let ctx = GcContext::with_config(
+        GcConfig::default()
+            .alloc_watermark(usize::MAX)
+            .max_interval(Duration::from_secs(3600))
+            .growth_factor(2.0),
+    );
+
+    let mut kept = Vec::new();
+    let mut collections = 0;
+    let mut base = 0;
+    let mut collected = sentinel(&ctx);
+    for i in 0..BLOCKS {
+        // Nothing can wake the collector before this allocation
+        let before = ctx.stats().total_bytes;
+        let threshold = 2 * base.max(MIN_BASE);
+        let block = ctx.alloc(Block([i as u64; 32]));
+        if keep {
+            kept.push(block);
+        }
+        // Read before the flag, or a collection finishing in between would go unnoticed until the
+        // next block
+        let after = ctx.stats().total_bytes;
+        if !collected.load(Ordering::SeqCst) {
+            if after <= threshold {
+                continue;
+            }
+            let start = Instant::now();
+            while !collected.load(Ordering::SeqCst) {
+                assert!(
+                    start.elapsed() < Duration::from_secs(10),
+                    "the heap doubled without a collection"
+                );
+                thread::yield_now();
+            }
+        }
+        assert!(
+            before + SLACK > threshold,
+            "collected at {before} bytes, before the heap doubled from {base}"
+        );
+        collections += 1;
+        // The collector holds the lock until it's done
+        base = ctx.stats().total_bytes;
+        collected = sentinel(&ctx);
+    }
+    assert!(kept
+        .iter()
+        .enumerate()
+        .all(|(i, block)| block.0[0] == i as u64));
+    drop(kept);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+    collections
+}
+
+pub fn check_all() {
+    let kept = collections(true);
+    let dropped = collections(false);
+    // Doubling from 128 KiB to the roughly 6 MiB of blocks
+    assert!((4..=8).contains(&kept), "{kept} collections");
+    assert!(dropped > 5 * kept, "{dropped} collections");
+}
@@ -0,0 +1,77 @@
+//! Checks that `GcContext::heap_snapshot` lists exactly the objects and references of a known
+//! graph
+
+use std::collections::HashSet;
+
+use gc::{Gc, GcCell, GcConfig, GcContext, HeapSnapshot};
+
+struct Node {
+    name: &'static str,
+    edges: GcCell<Vec<Gc<Node>>>,
+}
+
+gc::impl_gc_able!(Node => |node, visit| visit(&node.edges));
+
+/// The edges of `snapshot` by the names of their ends
+fn named_edges(
+    snapshot: &HeapSnapshot,
+    nodes: &[&Gc<Node>],
+) -> HashSet<(&'static str, &'static str)> {
+    let name = |i: usize| {
+        let addr = snapshot.objects[i].addr;
+        nodes
+            .iter()
+            .find(|node| node.as_ptr() as usize == addr)
+            .map(|node| node.name)
+            .expect("an edge points to an unknown object")
+    };
+    snapshot
+        .edges
+        .iter()
+        .map(|edge| (name(edge.from), name(edge.to)))
+        .collect()
+}
+
+pub fn check_all() {
+    let ctx = GcContext::with_config(GcConfig::default().deterministic(true));
+    let node = |name| {
+        ctx.alloc(Node {
+            name,
+            edges: GcCell::new(Vec::new()),
+        })
+    };
+    let (a, b, c) = (node("a"), node("b"), node("c"));
+    // a -> b -> a is a cycle, and c points to both
+    a.edges.set(vec![b.clone()]);
+    b.edges.set(vec![a.clone()]);
+    c.edges.set(vec![a.clone(), b.clone()]);
+    drop(b);
+    ctx.force_collect();
+
+    let snapshot = ctx.heap_snapshot();
+    assert!(snapshot.traced);
+    let objects: Vec<_> = snapshot
+        .objects
+        .iter()
+        .map(|obj| (obj.serial, obj.type_name, obj.root_count, obj.marked))
+        .collect();
+    let type_name = std::any::type_name::<Node>();
+    assert_eq!(
+        objects,
+        [
+            (0, type_name, 1, true),
+            (1, type_name, 0, true),
+            (2, type_name, 1, true),
+        ],
+    );
+    let b = a.edges.get()[0].clone();
+    let expected = HashSet::from([("a", "b"), ("b", "a"), ("c", "a"), ("c", "b")]);
+    assert_eq!(named_edges(&snapshot, &[&a, &b, &c]), expected);
+    assert_eq!(snapshot.edges.len(), expected.len());
+    drop(b);
+    assert_eq!(
+        snapshot,
+        ctx.heap_snapshot(),
+        "taking a snapshot changed the heap"
+    );
+}
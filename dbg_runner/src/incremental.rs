@@ -0,0 +1,155 @@
+//! Checks `GcConfig::incremental` on a graph far larger than a slice
+//!
+//! Marking takes many slices, an object moved into an already scanned one between slices has
+//! to survive, and once the collection finishes exactly the unreachable objects are freed
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use gc::{Gc, GcAble, GcConfig, GcContext};
+
+const LIVE: usize = 5000;
+const GARBAGE: usize = 5000;
+const BUDGET: usize = 100;
+const MOVES: usize = 2000;
+
+struct Node {
+    dropped: Arc<AtomicUsize>,
+    edges: Vec<Gc<Node>>,
+}
+
+// SAFETY: every method forwards to `edges`, the only `Gc<_>`s in a `Node`
+unsafe impl GcAble for Node {
+    unsafe fn mark(&self) {
+        self.edges.iter().for_each(|edge| unsafe { edge.mark() })
+    }
+
+    unsafe fn inc_root_count(&self) {
+        self.edges
+            .iter()
+            .for_each(|edge| unsafe { edge.inc_root_count() })
+    }
+
+    unsafe fn dec_root_count(&self) {
+        self.edges
+            .iter()
+            .for_each(|edge| unsafe { edge.dec_root_count() })
+    }
+
+    unsafe fn set_not_root(&self) {
+        self.edges
+            .iter()
+            .for_each(|edge| unsafe { edge.set_not_root() })
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        self.dropped.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn node(ctx: &GcContext, dropped: &Arc<AtomicUsize>, edges: Vec<Gc<Node>>) -> Gc<Node> {
+    ctx.alloc(Node {
+        dropped: Arc::clone(dropped),
+        edges,
+    })
+}
+
+/// Links `n` nodes into a chain where every node also points to the one halfway to the start, so
+/// most nodes are reached more than once, returning the last, which reaches all of them
+fn graph(ctx: &GcContext, n: usize, dropped: &Arc<AtomicUsize>) -> Gc<Node> {
+    let mut nodes = vec![node(ctx, dropped, Vec::new())];
+    for i in 1..n {
+        let edges = vec![nodes[i - 1].clone(), nodes[i / 2].clone()];
+        nodes.push(node(ctx, dropped, edges));
+    }
+    nodes.pop().unwrap()
+}
+
+/// The collector runs a slice every 100 microseconds, whether or not anything was allocated
+fn context() -> GcContext {
+    GcContext::with_config(
+        GcConfig::default()
+            .incremental(BUDGET)
+            .max_interval(Duration::from_micros(100)),
+    )
+}
+
+/// Waits for up to 10 seconds for `dropped` to reach `n`
+fn wait_for_drops(dropped: &AtomicUsize, n: usize) {
+    let start = Instant::now();
+    while dropped.load(Ordering::SeqCst) < n {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "only {} of {n} unreachable objects were collected",
+            dropped.load(Ordering::SeqCst)
+        );
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn check_slices() {
+    let ctx = context();
+    let (live_dropped, garbage_dropped) =
+        (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+    let live = graph(&ctx, LIVE, &live_dropped);
+    drop(graph(&ctx, GARBAGE, &garbage_dropped));
+
+    wait_for_drops(&garbage_dropped, GARBAGE);
+    assert_eq!(
+        live_dropped.load(Ordering::SeqCst),
+        0,
+        "a live object was freed"
+    );
+    assert_eq!(garbage_dropped.load(Ordering::SeqCst), GARBAGE);
+    assert_eq!(ctx.stats().live_allocations, LIVE);
+
+    drop(live);
+    ctx.force_collect();
+    assert_eq!(live_dropped.load(Ordering::SeqCst), LIVE);
+    ctx.assert_no_leaks();
+}
+
+/// Moves a large graph back and forth between two roots while the collector marks in slices
+///
+/// Whenever the root it's moved into was already scanned and the one it's moved out of wasn't, the
+/// graph is only found through the write barrier
+fn check_barrier() {
+    let ctx = context();
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let mut a = node(&ctx, &dropped, vec![graph(&ctx, LIVE, &dropped)]);
+    let mut b = node(&ctx, &dropped, Vec::new());
+
+    for i in 0..MOVES {
+        let (from, to) = if i % 2 == 0 {
+            (&mut a, &mut b)
+        } else {
+            (&mut b, &mut a)
+        };
+        let mut from = from.get_mut().unwrap();
+        let mut to = to.get_mut().unwrap();
+        let moved = from.edges.pop().unwrap();
+        to.edges.push(moved);
+        drop((from, to));
+        // Lets the collector run a slice between moves
+        thread::sleep(Duration::from_micros(50));
+    }
+    assert_eq!(dropped.load(Ordering::SeqCst), 0, "a live object was freed");
+
+    drop((a, b));
+    ctx.force_collect();
+    assert_eq!(dropped.load(Ordering::SeqCst), LIVE + 2);
+    ctx.assert_no_leaks();
+}
+
+pub fn check_all() {
+    check_slices();
+    check_barrier();
+}
@@ -0,0 +1,116 @@
+//! Checks `LocalGc`: objects on the thread's local heap survive as long as they're reachable, also
+//! through containers and cells, and are freed once they aren't, without touching any other
+//! thread's heap
+
+use std::{cell::Cell, thread};
+
+use gc::{force_collect_local, LocalGc, LocalGcAble, LocalGcCell};
+
+thread_local! {
+    /// The number of `Node`s dropped on this thread since the last `reset_drops`
+    static DROPS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn drops() -> usize {
+    DROPS.get()
+}
+
+/// Collects whatever an earlier check left behind, then starts counting drops from 0
+fn reset_drops() {
+    force_collect_local();
+    DROPS.set(0);
+}
+
+#[derive(LocalGcAble)]
+struct Node {
+    children: LocalGcCell<Vec<LocalGc<Node>>>,
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        DROPS.set(DROPS.get() + 1);
+    }
+}
+
+fn node(children: Vec<LocalGc<Node>>) -> LocalGc<Node> {
+    LocalGc::new(Node {
+        children: LocalGcCell::new(children),
+    })
+}
+
+/// A shared child survives until every node pointing to it is unreachable
+fn check_reachable() {
+    reset_drops();
+    let shared = node(Vec::new());
+    let left = node(vec![shared.clone()]);
+    let right = node(vec![shared]);
+    let top = node(vec![left, right.clone()]);
+    force_collect_local();
+    assert_eq!(drops(), 0, "a reachable node was freed");
+
+    drop(top);
+    force_collect_local();
+    assert_eq!(drops(), 2, "only `top` and `left` are unreachable");
+    assert_eq!(right.children.borrow()[0].children.borrow().len(), 0);
+
+    drop(right);
+    force_collect_local();
+    assert_eq!(drops(), 4);
+}
+
+/// Cycles through `LocalGcCell`, `Option`, `Vec` and `Box` are freed once unreachable
+fn check_cycles() {
+    reset_drops();
+    let (a, b, c) = (node(Vec::new()), node(Vec::new()), node(Vec::new()));
+    a.children.set(vec![b.clone(), c.clone()]);
+    b.children.set(vec![a.clone()]);
+    c.children.set(vec![c.clone(), b.clone()]);
+    let boxed = LocalGc::new(Some(Box::new(a.clone())));
+    drop((a, b, c));
+    force_collect_local();
+    assert_eq!(drops(), 0, "a reachable node was freed");
+
+    // Taken out of a node, so it's a root on its own
+    let b = boxed
+        .as_ref()
+        .unwrap()
+        .children
+        .replace(Vec::new())
+        .remove(0);
+    drop(boxed);
+    force_collect_local();
+    assert_eq!(drops(), 1, "only `c` is unreachable");
+    assert_eq!(b.children.borrow().len(), 1);
+
+    drop(b);
+    force_collect_local();
+    assert_eq!(drops(), 3);
+}
+
+/// Enough garbage to cross the watermark is collected without being asked to, and collecting
+/// another thread's heap leaves this one's alone
+fn check_threads() {
+    reset_drops();
+    let kept = node(Vec::new());
+    for _ in 0..10_000 {
+        node(Vec::new());
+    }
+    assert!(drops() > 0, "crossing the watermark didn't collect");
+
+    thread::spawn(|| {
+        let other = LocalGc::new(1u32);
+        force_collect_local();
+        assert_eq!(*other, 1);
+    })
+    .join()
+    .unwrap();
+    force_collect_local();
+    assert_eq!(drops(), 10_000);
+    assert_eq!(kept.children.borrow().len(), 0);
+}
+
+pub fn check_all() {
+    check_reachable();
+    check_cycles();
+    check_threads();
+}
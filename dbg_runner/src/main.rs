@@ -1,5 +1,124 @@
+//! Runs every check in turn
+//!
+//! Only the checks which run no collector threads and stay small, `basics` and `gcbox_layout`, run
+//! under Miri:
+//!
+//! `MIRIFLAGS=-Zmiri-tree-borrows cargo +nightly miri run -p dbg_runner`
+//!
+//! Tree Borrows is needed since the elements of a `Gc<[T]>` and the value of a `Gc::new_uninit`
+//! box are reached from their erased zero-sized value, which Stacked Borrows doesn't allow.
+
+mod atomic_gc;
+mod basics;
+mod batch;
+mod borrows;
+mod build;
+mod builder;
+mod capacity;
+mod cell;
+mod clone_unlocked;
+mod coalesce;
+mod collector_thread;
+mod contexts;
+mod cycles;
+mod deep_clone;
+mod deterministic;
+mod disable;
+mod driver;
+mod ephemeron;
+mod finalize;
+mod finalizer_queue;
+mod free_list;
+mod gcbox_layout;
+mod generations;
+mod get_mut;
+mod growth;
+mod heap_snapshot;
+mod incremental;
 mod linked_list;
+mod local;
+mod out_of_memory;
+mod panics;
+mod pause_stats;
+mod reentrant;
+mod register_twice;
+#[cfg(feature = "debug-tracing")]
+mod root_counts;
+mod root_overflow;
+mod root_set;
+mod runtime_metrics;
+mod scope;
+mod serialize;
+mod shutdown;
+mod slices;
+mod soak;
+mod stats;
+mod stress;
+mod structural;
+mod sweep_batch;
+mod trace_freed;
+mod urgent;
+mod use_after_free;
+mod verify_roots;
+mod wakeups;
+mod with_roots;
 
 fn main() {
-    loop {}
+    basics::check_all();
+    gcbox_layout::check_all();
+    if cfg!(miri) {
+        return;
+    }
+    atomic_gc::check_all();
+    batch::check_all();
+    borrows::check_all();
+    build::check_all();
+    builder::check_all();
+    capacity::check_all();
+    cell::check_all();
+    clone_unlocked::check_all();
+    cycles::check_all();
+    coalesce::check_all();
+    collector_thread::check_all();
+    contexts::check_all();
+    deep_clone::check_all();
+    deterministic::check_all();
+    disable::check_all();
+    driver::check_all();
+    ephemeron::check_all();
+    finalize::check_all();
+    finalizer_queue::check_all();
+    free_list::check_all();
+    generations::check_all();
+    get_mut::check_all();
+    growth::check_all();
+    heap_snapshot::check_all();
+    incremental::check_all();
+    local::check_all();
+    out_of_memory::check_all();
+    panics::check_all();
+    pause_stats::check_all();
+    soak::check_all();
+    stats::check_all();
+    stress::check_all();
+    structural::check_all();
+    sweep_batch::check_all();
+    reentrant::check_all();
+    register_twice::check_all();
+    root_overflow::check_all();
+    root_set::check_all();
+    runtime_metrics::check_all();
+    scope::check_all();
+    serialize::check_all();
+    slices::check_all();
+    trace_freed::check_all();
+    urgent::check_all();
+    use_after_free::check_all();
+    verify_roots::check_all();
+    wakeups::check_all();
+    with_roots::check_all();
+    #[cfg(feature = "debug-tracing")]
+    root_counts::check_all();
+    shutdown::check_all();
+    println!("all checks passed");
 }
@@ -0,0 +1,104 @@
+//! Checks that `GcContext::try_alloc` returns `GcError::OutOfMemory` instead of aborting when the
+//! context's allocator fails, and that it collects and retries first
+//!
+//! The allocator only has room for a fixed number of bytes
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use gc::{GcAble, GcConfig, GcContext, GcError};
+
+const LIMIT: usize = 64 * 1024;
+
+/// Fails once `LIMIT` bytes are allocated
+struct Limited(Arc<AtomicUsize>);
+
+unsafe impl GlobalAlloc for Limited {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let used = self.0.fetch_add(layout.size(), Ordering::SeqCst);
+        if used + layout.size() > LIMIT {
+            self.0.fetch_sub(layout.size(), Ordering::SeqCst);
+            return std::ptr::null_mut();
+        }
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.0.fetch_sub(layout.size(), Ordering::SeqCst);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+struct Block {
+    _data: [u64; 32],
+    dropped: Arc<AtomicUsize>,
+}
+
+// SAFETY: there are no `Gc<_>`s in a `Block`
+unsafe impl GcAble for Block {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+impl Drop for Block {
+    fn drop(&mut self) {
+        self.dropped.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+pub fn check_all() {
+    let used = Arc::new(AtomicUsize::new(0));
+    let ctx = GcContext::with_config(
+        GcConfig::default()
+            .deterministic(true)
+            .allocator(Limited(Arc::clone(&used))),
+    );
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let block = || Block {
+        _data: [0; 32],
+        dropped: Arc::clone(&dropped),
+    };
+
+    // Fill the allocator with blocks which are all kept
+    let mut kept = Vec::new();
+    let err = loop {
+        match ctx.try_alloc(block()) {
+            Ok(gc) => kept.push(gc),
+            Err(err) => break err,
+        }
+    };
+    assert_eq!(err, GcError::OutOfMemory);
+    assert!(used.load(Ordering::SeqCst) <= LIMIT);
+    assert!(
+        kept.len() >= LIMIT / 2 / size_of::<Block>(),
+        "{} blocks",
+        kept.len()
+    );
+    // Only the block which failed to allocate was dropped
+    assert_eq!(dropped.load(Ordering::SeqCst), 1);
+    assert_eq!(ctx.stats().live_allocations, kept.len());
+
+    // Once they're unreachable, the failed allocation collects them and succeeds on its retry
+    let blocks = kept.len();
+    drop(kept);
+    let retried = ctx
+        .try_alloc(block())
+        .expect("nothing was collected before retrying");
+    assert_eq!(dropped.load(Ordering::SeqCst), 1 + blocks);
+    assert_eq!(ctx.stats().live_allocations, 1);
+
+    drop(retried);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+    assert_eq!(used.load(Ordering::SeqCst), 0);
+}
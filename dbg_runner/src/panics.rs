@@ -0,0 +1,165 @@
+//! Checks that a panic in user code run by a collection leaves the context usable
+//!
+//! A panicking `Drop` or finalizer is resumed once every other unreachable object is reclaimed,
+//! while a panicking `GcAble::mark` abandons the collection without freeing anything
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use gc::{Finalize, GcAble, GcConfig, GcContext};
+
+struct Counted(Arc<AtomicUsize>);
+
+// SAFETY: there are no `Gc<_>`s in a `Counted`
+unsafe impl GcAble for Counted {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+struct PanicsOnDrop;
+
+// SAFETY: there are no `Gc<_>`s in a `PanicsOnDrop`
+unsafe impl GcAble for PanicsOnDrop {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+impl Drop for PanicsOnDrop {
+    fn drop(&mut self) {
+        panic!("dropped");
+    }
+}
+
+struct PanicsOnFinalize;
+
+// SAFETY: there are no `Gc<_>`s in a `PanicsOnFinalize`
+unsafe impl GcAble for PanicsOnFinalize {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+impl Finalize for PanicsOnFinalize {
+    fn finalize(&self) {
+        panic!("finalized");
+    }
+}
+
+static MARK_PANICS: AtomicBool = AtomicBool::new(false);
+
+struct PanicsOnMark;
+
+// SAFETY: there are no `Gc<_>`s in a `PanicsOnMark`
+unsafe impl GcAble for PanicsOnMark {
+    unsafe fn mark(&self) {
+        if MARK_PANICS.load(Ordering::SeqCst) {
+            panic!("marked");
+        }
+    }
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+/// A context which is only collected when asked to, so every panic happens on this thread
+fn context() -> GcContext {
+    GcContext::with_config(
+        GcConfig::default()
+            .alloc_watermark(usize::MAX)
+            .max_interval(Duration::from_secs(3600)),
+    )
+}
+
+/// Collects `ctx`, returning the message of the panic it resumes
+fn collect_panics(ctx: &GcContext) -> String {
+    let collected = panic::catch_unwind(AssertUnwindSafe(|| ctx.force_collect()));
+    let payload = collected.expect_err("the collection didn't panic");
+    payload.downcast_ref::<&str>().unwrap().to_string()
+}
+
+/// Allocates garbage which counts its drops around `alloc`, returning the counter
+fn garbage_around(ctx: &GcContext, alloc: impl FnOnce()) -> Arc<AtomicUsize> {
+    let dropped = Arc::new(AtomicUsize::new(0));
+    ctx.alloc(Counted(Arc::clone(&dropped)));
+    alloc();
+    ctx.alloc(Counted(Arc::clone(&dropped)));
+    dropped
+}
+
+/// The context can still allocate and collect
+fn check_usable(ctx: &GcContext) {
+    let dropped = garbage_around(ctx, || ());
+    let kept = ctx.alloc(7u32);
+    ctx.force_collect();
+    assert_eq!((dropped.load(Ordering::SeqCst), *kept), (2, 7));
+    drop(kept);
+    ctx.assert_no_leaks();
+}
+
+fn check_drop() {
+    let ctx = context();
+    let dropped = garbage_around(&ctx, || drop(ctx.alloc(PanicsOnDrop)));
+    assert_eq!(collect_panics(&ctx), "dropped");
+    assert_eq!(dropped.load(Ordering::SeqCst), 2);
+    assert_eq!(ctx.stats().live_allocations, 0);
+    check_usable(&ctx);
+}
+
+fn check_finalize() {
+    let ctx = context();
+    let dropped = garbage_around(&ctx, || drop(ctx.alloc_finalized(PanicsOnFinalize)));
+    assert_eq!(collect_panics(&ctx), "finalized");
+    assert_eq!(dropped.load(Ordering::SeqCst), 2);
+    assert_eq!(ctx.stats().live_allocations, 0);
+    check_usable(&ctx);
+}
+
+fn check_mark() {
+    let ctx = context();
+    let marked = ctx.alloc(PanicsOnMark);
+    let dropped = garbage_around(&ctx, || ());
+    MARK_PANICS.store(true, Ordering::SeqCst);
+    assert_eq!(collect_panics(&ctx), "marked");
+    MARK_PANICS.store(false, Ordering::SeqCst);
+    assert_eq!(dropped.load(Ordering::SeqCst), 0);
+    assert_eq!(ctx.stats().live_allocations, 3);
+
+    drop(marked);
+    check_usable(&ctx);
+    assert_eq!(dropped.load(Ordering::SeqCst), 2);
+}
+
+pub fn check_all() {
+    check_drop();
+    check_finalize();
+    check_mark();
+}
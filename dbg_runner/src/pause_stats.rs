@@ -0,0 +1,154 @@
+//! Checks the distribution of collection pauses reported by `GcStats::pauses`: one pause per
+//! collection or incremental slice, nonzero on a sizable heap, ordered sensibly, and covering only
+//! the most recent pauses
+
+use std::time::Duration;
+
+use gc::{Gc, GcAble, GcCell, GcConfig, GcContext, PauseStats};
+
+/// The number of pauses a context remembers
+const WINDOW: usize = 256;
+
+#[derive(Clone)]
+struct Children(Vec<Gc<u64>>);
+
+// SAFETY: every method forwards to the `Gc<_>`s in a `Children`
+unsafe impl GcAble for Children {
+    unsafe fn mark(&self) {
+        self.0.iter().for_each(|child| unsafe { child.mark() })
+    }
+
+    unsafe fn inc_root_count(&self) {
+        self.0
+            .iter()
+            .for_each(|child| unsafe { child.inc_root_count() })
+    }
+
+    unsafe fn dec_root_count(&self) {
+        self.0
+            .iter()
+            .for_each(|child| unsafe { child.dec_root_count() })
+    }
+
+    unsafe fn set_not_root(&self) {
+        self.0
+            .iter()
+            .for_each(|child| unsafe { child.set_not_root() })
+    }
+}
+
+struct Node {
+    children: GcCell<Children>,
+}
+
+// SAFETY: every method forwards to `children`, the only `GcAble` field of a `Node`
+unsafe impl GcAble for Node {
+    unsafe fn mark(&self) {
+        unsafe { self.children.mark() }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { self.children.inc_root_count() }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self.children.dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { self.children.set_not_root() }
+    }
+}
+
+/// A config which never collects by itself, so every pause is one of the check's own
+fn manual() -> GcConfig {
+    GcConfig::default()
+        .alloc_watermark(usize::MAX)
+        .max_interval(Duration::from_secs(3600))
+}
+
+/// A heap of `nodes * 100` objects which all stay alive
+fn heap(ctx: &GcContext, nodes: u64) -> Vec<Gc<Node>> {
+    (0..nodes)
+        .map(|i| {
+            let children = (0..99).map(|j| ctx.alloc(i * 100 + j)).collect();
+            ctx.alloc(Node {
+                children: GcCell::new(Children(children)),
+            })
+        })
+        .collect()
+}
+
+fn check_ordered(pauses: &PauseStats) {
+    assert!(pauses.min > Duration::ZERO, "{pauses:?}");
+    assert!(
+        pauses.min <= pauses.mean && pauses.mean <= pauses.max,
+        "{pauses:?}"
+    );
+    assert!(
+        pauses.min <= pauses.p99 && pauses.p99 <= pauses.max,
+        "{pauses:?}"
+    );
+}
+
+fn check_forced() {
+    let ctx = GcContext::with_config(manual());
+    let pauses = ctx.stats().pauses;
+    assert_eq!(pauses.count, 0);
+    assert_eq!(pauses.max, Duration::ZERO);
+
+    let nodes = heap(&ctx, 200);
+    for _ in 0..10 {
+        ctx.force_collect();
+    }
+    let pauses = ctx.stats().pauses;
+    assert_eq!(pauses.count, 10);
+    check_ordered(&pauses);
+
+    drop(nodes);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
+
+fn check_slices() {
+    let ctx = GcContext::with_config(manual().incremental(100));
+    let nodes = heap(&ctx, 100);
+    // Keeps the collector thread from taking any of the slices
+    let batch = ctx.batch();
+    let mut slices = 1;
+    while !ctx.collect_step() {
+        slices += 1;
+    }
+    drop(batch);
+    assert!(slices > 1);
+    let pauses = ctx.stats().pauses;
+    assert_eq!(pauses.count, slices);
+    check_ordered(&pauses);
+
+    drop(nodes);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
+
+/// Once more pauses than fit in the window were taken, only the most recent are covered
+fn check_window() {
+    let ctx = GcContext::with_config(manual());
+    let nodes = heap(&ctx, 1000);
+    ctx.force_collect();
+    let slow = ctx.stats().pauses.max;
+    drop(nodes);
+    ctx.force_collect();
+    for _ in 0..WINDOW {
+        ctx.force_collect();
+    }
+    let pauses = ctx.stats().pauses;
+    assert_eq!(pauses.count, WINDOW);
+    assert!(pauses.max < slow, "{pauses:?} {slow:?}");
+    ctx.assert_no_leaks();
+}
+
+pub fn check_all() {
+    check_forced();
+    check_slices();
+    check_window();
+}
@@ -0,0 +1,132 @@
+//! Checks that user code run by a collection panics with a diagnostic when it calls back into the
+//! context being collected, instead of deadlocking on its lock, and that the context stays usable
+//!
+//! Another context may still be used from the same code
+
+use std::panic::{self, AssertUnwindSafe};
+
+use gc::{Finalize, GcAble, GcConfig, GcContext};
+
+/// What a finalizer or `Drop` does with the context it was allocated in
+#[derive(Clone, Copy)]
+enum Call {
+    Alloc,
+    Stats,
+    Collect,
+}
+
+fn call(ctx: &GcContext, call: Call) {
+    match call {
+        Call::Alloc => drop(ctx.alloc(1u32)),
+        Call::Stats => drop(ctx.stats()),
+        Call::Collect => ctx.force_collect(),
+    }
+}
+
+struct Finalized {
+    ctx: GcContext,
+    call: Call,
+}
+
+// SAFETY: there are no `Gc<_>`s in a `Finalized`
+unsafe impl GcAble for Finalized {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+impl Finalize for Finalized {
+    fn finalize(&self) {
+        call(&self.ctx, self.call);
+    }
+}
+
+struct Dropped {
+    ctx: GcContext,
+    call: Call,
+}
+
+// SAFETY: there are no `Gc<_>`s in a `Dropped`
+unsafe impl GcAble for Dropped {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+impl Drop for Dropped {
+    fn drop(&mut self) {
+        call(&self.ctx, self.call);
+    }
+}
+
+/// A context which only collects when the check asks it to
+fn context() -> GcContext {
+    GcContext::with_config(GcConfig::default().deterministic(true))
+}
+
+/// Collects `ctx`, returning the message of the panic it resumes
+fn collect_panics(ctx: &GcContext) -> String {
+    let collected = panic::catch_unwind(AssertUnwindSafe(|| ctx.force_collect()));
+    let payload = collected.expect_err("calling back into the context didn't panic");
+    payload.downcast_ref::<&str>().unwrap().to_string()
+}
+
+fn check_reentrant(alloc: impl Fn(&GcContext, Call)) {
+    for call in [Call::Alloc, Call::Stats, Call::Collect] {
+        let ctx = context();
+        alloc(&ctx, call);
+        let msg = collect_panics(&ctx);
+        assert!(
+            msg.starts_with("Gc operation attempted from within a Gc callback"),
+            "{msg}"
+        );
+
+        // The lock was released, and nothing was left behind
+        ctx.consistency_check();
+        assert_eq!(ctx.stats().live_allocations, 0);
+        drop(ctx.alloc(2u32));
+        ctx.force_collect();
+        ctx.assert_no_leaks();
+    }
+}
+
+/// Only the context being collected is locked
+fn check_other_context() {
+    let ctx = context();
+    let other = context();
+    ctx.alloc_finalized(Finalized {
+        ctx: other.clone(),
+        call: Call::Alloc,
+    });
+    ctx.alloc(Dropped {
+        ctx: other.clone(),
+        call: Call::Collect,
+    });
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+    other.assert_no_leaks();
+}
+
+pub fn check_all() {
+    check_reentrant(|ctx, call| {
+        ctx.alloc_finalized(Finalized {
+            ctx: ctx.clone(),
+            call,
+        });
+    });
+    check_reentrant(|ctx, call| {
+        ctx.alloc(Dropped {
+            ctx: ctx.clone(),
+            call,
+        });
+    });
+    check_other_context();
+}
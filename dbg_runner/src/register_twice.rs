@@ -0,0 +1,33 @@
+//! Checks that registering a box a second time panics with the box's type and address, without
+//! changing the context's bookkeeping
+
+use std::panic::{self, AssertUnwindSafe};
+
+use gc::{Gc, GcContext};
+
+pub fn check_all() {
+    let ctx = GcContext::new();
+    let num = ctx.alloc(7u32);
+    let before = ctx.stats();
+
+    let registered = panic::catch_unwind(AssertUnwindSafe(|| gc::__private::register_again(&num)));
+    let payload = registered.expect_err("a box was registered twice");
+    let msg = payload.downcast_ref::<String>().unwrap();
+    assert!(msg.starts_with("the `GcBox<"), "{msg}");
+    assert!(msg.contains("u32>` at 0x"), "{msg}");
+    assert!(msg.ends_with(" was registered twice"), "{msg}");
+
+    // Still registered once, and the context still works
+    let after = ctx.stats();
+    assert_eq!(
+        (after.live_allocations, after.total_bytes),
+        (before.live_allocations, before.total_bytes)
+    );
+    ctx.force_collect();
+    assert_eq!(*num, 7);
+    let weak = Gc::downgrade(&num);
+    drop(num);
+    ctx.force_collect();
+    assert!(weak.upgrade().is_none());
+    ctx.assert_no_leaks();
+}
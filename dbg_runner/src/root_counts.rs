@@ -0,0 +1,79 @@
+//! Checks that `gc::assert_root_counts_balance` accepts a correct `GcAble` impl and flags one
+//! which misses a field, using the counters of the `debug-tracing` feature
+
+use std::panic::{self, AssertUnwindSafe};
+
+use gc::{Gc, GcAble};
+
+struct Pair {
+    left: Gc<u32>,
+    right: Gc<u32>,
+}
+
+gc::impl_gc_able!(Pair => |pair, visit| {
+    visit(&pair.left);
+    visit(&pair.right);
+});
+
+/// Forgets `right` in `dec_root_count`, so it's leaked every time the pair is unrooted
+struct LeakyPair {
+    left: Gc<u32>,
+    right: Gc<u32>,
+}
+
+unsafe impl GcAble for LeakyPair {
+    unsafe fn mark(&self) {
+        unsafe {
+            self.left.mark();
+            self.right.mark();
+        }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe {
+            self.left.inc_root_count();
+            self.right.inc_root_count();
+        }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self.left.dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe {
+            self.left.set_not_root();
+            self.right.set_not_root();
+        }
+    }
+}
+
+pub fn check_all() {
+    let pair = Pair {
+        left: Gc::new(1),
+        right: Gc::new(2),
+    };
+    let before = gc::tracing_stats();
+    gc::assert_root_counts_balance(&pair);
+    let calls = gc::tracing_stats() - before;
+    assert_eq!(calls.inc_root_count, 2);
+    assert_eq!(calls.root_count_delta, 0);
+
+    // Moving the value in and out of the Gc balances as well
+    let boxed = Gc::new(pair);
+    let pair = Gc::try_unwrap(boxed).ok().unwrap();
+    gc::assert_root_counts_balance(&pair);
+
+    let leaky = LeakyPair {
+        left: Gc::new(1),
+        right: Gc::new(2),
+    };
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let checked = panic::catch_unwind(AssertUnwindSafe(|| gc::assert_root_counts_balance(&leaky)));
+    panic::set_hook(hook);
+    let msg = checked.expect_err("the leaky impl wasn't flagged");
+    let msg = msg.downcast_ref::<String>().unwrap();
+    assert!(msg.contains("`dec_root_count` 1"), "{msg}");
+    assert_eq!(Gc::strong_count(&leaky.right), 2, "the leak wasn't real");
+}
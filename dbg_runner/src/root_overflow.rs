@@ -0,0 +1,39 @@
+//! Checks that `Gc::try_clone` fails and `Gc::clone` panics with a clear message once an object's
+//! root count is at its maximum, and that the object is unaffected by either
+
+use std::panic::{self, AssertUnwindSafe};
+
+use gc::{Gc, GcConfig, GcContext};
+
+pub fn check_all() {
+    let ctx = GcContext::with_config(GcConfig::default().deterministic(true));
+    let num = ctx.alloc(7u32);
+    let clone = Gc::try_clone(&num).expect("a clone with a root count of 1 failed");
+    assert_eq!(Gc::strong_count(&num), 2);
+    drop(clone);
+
+    unsafe { gc::__private::set_root_count(&num, u32::MAX) };
+    let err = Gc::try_clone(&num).expect_err("a root count overflowed");
+    assert_eq!(err.type_name(), "u32");
+    assert_eq!(Gc::strong_count(&num), u32::MAX);
+
+    let cloned = panic::catch_unwind(AssertUnwindSafe(|| drop(num.clone())));
+    let payload = cloned.expect_err("a root count overflowed");
+    let msg = payload.downcast_ref::<String>().unwrap();
+    assert_eq!(msg, &err.to_string());
+    assert!(
+        msg.starts_with("the root count of a `Gc<u32>` overflowed"),
+        "{msg}"
+    );
+    assert_eq!(Gc::strong_count(&num), u32::MAX);
+    unsafe { gc::__private::set_root_count(&num, 1) };
+
+    // The failed clone left nothing behind
+    ctx.force_collect();
+    assert_eq!(*num, 7);
+    let weak = Gc::downgrade(&num);
+    drop(num);
+    ctx.force_collect();
+    assert!(weak.upgrade().is_none());
+    ctx.assert_no_leaks();
+}
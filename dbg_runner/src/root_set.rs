@@ -0,0 +1,126 @@
+//! Checks `RootSet`, and times it against keeping the same stack in a rooted container object
+//!
+//! The stack is used like an interpreter's value stack: a deep base of long-lived values, with
+//! short-lived values pushed and popped on top of it between collections
+
+use std::time::{Duration, Instant};
+
+use gc::{Gc, GcCell, GcConfig, GcContext, RootSet};
+
+const BASE: u64 = 10_000;
+const ROUNDS: u64 = 20;
+const PER_ROUND: u64 = 1000;
+
+/// The two ways of keeping the stack
+trait Stack {
+    /// The number of objects the stack itself takes up
+    const OBJECTS: u64;
+
+    fn push_all(&mut self, gcs: Vec<Gc<u64>>);
+    fn pop_n(&mut self, n: u64) -> Vec<Gc<u64>>;
+}
+
+impl Stack for RootSet<u64> {
+    const OBJECTS: u64 = 0;
+
+    fn push_all(&mut self, gcs: Vec<Gc<u64>>) {
+        for gc in gcs {
+            self.push(gc);
+        }
+    }
+
+    fn pop_n(&mut self, n: u64) -> Vec<Gc<u64>> {
+        (0..n).map(|_| self.pop().unwrap()).collect()
+    }
+}
+
+/// Changed a whole round at a time, since every change roots and unroots everything in it
+impl Stack for Gc<GcCell<Vec<Gc<u64>>>> {
+    const OBJECTS: u64 = 1;
+
+    fn push_all(&mut self, gcs: Vec<Gc<u64>>) {
+        let mut vals = self.get();
+        vals.extend(gcs);
+        self.set(vals);
+    }
+
+    fn pop_n(&mut self, n: u64) -> Vec<Gc<u64>> {
+        let mut vals = self.get();
+        let popped = vals.split_off(vals.len() - n as usize);
+        self.set(vals);
+        popped.into_iter().rev().collect()
+    }
+}
+
+/// Runs the workload, checking that nothing on the stack is freed and that everything popped is,
+/// and returns how long it took
+fn run<S: Stack>(ctx: &GcContext, stack: &mut S) -> Duration {
+    stack.push_all((0..BASE).map(|i| ctx.alloc(i)).collect());
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        stack.push_all((0..PER_ROUND).map(|i| ctx.alloc(BASE + i)).collect());
+        ctx.force_collect();
+        let popped = stack.pop_n(PER_ROUND);
+        assert!(popped
+            .iter()
+            .rev()
+            .map(|gc| **gc)
+            .eq(BASE..BASE + PER_ROUND));
+    }
+    let elapsed = start.elapsed();
+    ctx.force_collect();
+    assert_eq!(ctx.stats().live_allocations as u64, BASE + S::OBJECTS);
+    elapsed
+}
+
+/// Pushes and pops in steps interleaved with an incremental collection
+fn check_incremental() {
+    let ctx = crate::basics::context(GcConfig::default().incremental(1));
+    let mut stack = ctx.root_set();
+    stack.push(ctx.alloc(0u64));
+    ctx.collect_step();
+    stack.push(ctx.alloc(1u64));
+    let popped = stack.pop().unwrap();
+    stack.push(ctx.alloc(2u64));
+    ctx.collect_step();
+    let other: RootSet<u64> = ctx.root_set();
+    while !ctx.collect_step() {}
+    assert_eq!((*stack[0], *popped, *stack[1]), (0, 1, 2));
+    assert!(other.is_empty());
+
+    drop((stack, popped, other));
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
+
+/// Objects only in a set survive a scope
+fn check_scope() {
+    let ctx = crate::basics::context(GcConfig::default());
+    let mut stack = ctx.root_set();
+    unsafe {
+        ctx.scope(|| {
+            stack.push(ctx.alloc(GcCell::new(Some(ctx.alloc(1u64)))));
+            ctx.alloc(2u64);
+        })
+    };
+    assert_eq!(ctx.stats().live_allocations, 2);
+    assert_eq!(*stack[0].get().unwrap(), 1);
+}
+
+pub fn check_all() {
+    let ctx = crate::basics::context(GcConfig::default());
+    let mut set = ctx.root_set();
+    let with_set = run(&ctx, &mut set);
+    drop(set);
+
+    let ctx = crate::basics::context(GcConfig::default());
+    let mut container = ctx.alloc(GcCell::new(Vec::new()));
+    let with_container = run(&ctx, &mut container);
+    println!(
+        "{ROUNDS} rounds on a {BASE} value stack: {with_set:?} as a RootSet, \
+         {with_container:?} in a rooted container",
+    );
+
+    check_incremental();
+    check_scope();
+}
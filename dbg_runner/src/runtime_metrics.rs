@@ -0,0 +1,70 @@
+//! Checks `GcContext::runtime_metrics` while the background collector runs under load: the
+//! metrics are populated, the time spent collecting fits in the time that passed, and the rate
+//! matches the count over the context's lifetime
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use gc::{GcConfig, GcContext};
+
+fn check_background() {
+    let outer = Instant::now();
+    let ctx = GcContext::with_config(
+        GcConfig::default()
+            .alloc_watermark(100)
+            .max_interval(Duration::from_millis(10)),
+    );
+    let inner = Instant::now();
+    let kept = (0..1000u32).map(|i| ctx.alloc(i)).collect::<Vec<_>>();
+    while inner.elapsed() < Duration::from_millis(300) {
+        for i in 0..100u32 {
+            ctx.alloc(i);
+        }
+        thread::yield_now();
+    }
+    let inner = inner.elapsed();
+    let metrics = ctx.runtime_metrics();
+    let outer = outer.elapsed();
+
+    assert!(metrics.collections > 0, "{metrics:?}");
+    assert!(metrics.collection_time > Duration::ZERO, "{metrics:?}");
+    assert!(metrics.collection_time <= outer, "{metrics:?} in {outer:?}");
+    // Every collection so far finished within the last 10 seconds
+    let collections = metrics.collections as f64;
+    assert!(
+        metrics.collections_per_sec >= collections / outer.as_secs_f64(),
+        "{metrics:?} in {outer:?}"
+    );
+    assert!(
+        metrics.collections_per_sec <= collections / inner.as_secs_f64(),
+        "{metrics:?} in {inner:?}"
+    );
+
+    drop(kept);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
+
+/// Each forced collection is counted once, along with its time
+fn check_forced() {
+    let ctx = GcContext::with_config(GcConfig::default().deterministic(true));
+    let metrics = ctx.runtime_metrics();
+    assert_eq!(metrics.collections, 0);
+    assert_eq!(metrics.collection_time, Duration::ZERO);
+    assert_eq!(metrics.collections_per_sec, 0.0);
+
+    for _ in 0..5 {
+        ctx.force_collect();
+    }
+    let metrics = ctx.runtime_metrics();
+    assert_eq!(metrics.collections, 5);
+    assert!(metrics.collection_time > Duration::ZERO);
+    assert!(metrics.collections_per_sec > 0.0);
+}
+
+pub fn check_all() {
+    check_background();
+    check_forced();
+}
@@ -0,0 +1,99 @@
+//! Checks `GcContext::scope`
+//!
+//! Everything allocated in a scope is freed when it ends, without a collection, except what's
+//! reachable from a root, and a reference from outside the scope is caught in debug builds
+
+use std::panic::{self, AssertUnwindSafe};
+
+use gc::{Gc, GcCell, GcConfig, GcContext};
+
+struct Node {
+    id: u32,
+    next: GcCell<Option<Gc<Node>>>,
+}
+
+gc::impl_gc_able!(Node => |node, visit| visit(&node.next));
+
+fn node(ctx: &GcContext, id: u32, next: Option<Gc<Node>>) -> Gc<Node> {
+    ctx.alloc(Node {
+        id,
+        next: GcCell::new(next),
+    })
+}
+
+fn context() -> GcContext {
+    GcContext::with_config(GcConfig::default().deterministic(true))
+}
+
+fn check_frees_everything() {
+    let ctx = context();
+    let weak = unsafe {
+        ctx.scope(|| {
+            let a = node(&ctx, 1, None);
+            let b = node(&ctx, 2, Some(a.clone()));
+            // A cycle, which only a collection could find otherwise
+            a.next.set(Some(b));
+            Gc::downgrade(&a)
+        })
+    };
+    assert_eq!(ctx.stats().live_allocations, 0, "the scope wasn't freed");
+    assert_eq!(ctx.runtime_metrics().collections, 0, "the scope collected");
+    assert!(weak.upgrade().is_none());
+}
+
+fn check_escaped_roots_are_kept() {
+    let ctx = context();
+    let outer = node(&ctx, 0, None);
+    let (kept, inner) = unsafe {
+        ctx.scope(|| {
+            let _garbage = node(&ctx, 1, None);
+            let child = node(&ctx, 2, None);
+            let kept = node(&ctx, 3, Some(child));
+            // Kept objects join the enclosing scope
+            let inner = ctx.scope(|| {
+                let _garbage = node(&ctx, 4, None);
+                node(&ctx, 5, None)
+            });
+            assert_eq!(ctx.stats().live_allocations, 5);
+            drop(inner);
+            (kept, Gc::downgrade(&node(&ctx, 6, None)))
+        })
+    };
+    // `outer` wasn't allocated in the scope, and `kept` took its child along
+    assert_eq!(ctx.stats().live_allocations, 3);
+    assert!(inner.upgrade().is_none());
+    assert_eq!(outer.id, 0);
+    assert_eq!(kept.next.get().expect("the child was freed").id, 2);
+    ctx.consistency_check();
+
+    drop((outer, kept));
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
+
+fn check_escape_is_flagged() {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    let ctx = context();
+    let outer = node(&ctx, 0, None);
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let res = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+        ctx.scope(|| outer.next.set(Some(node(&ctx, 1, None))))
+    }));
+    panic::set_hook(hook);
+    assert!(res.is_err(), "a reference out of the scope wasn't caught");
+    assert_eq!(outer.next.get().expect("the escaped object was lost").id, 1);
+    assert_eq!(ctx.stats().live_allocations, 2);
+
+    drop(outer);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
+
+pub fn check_all() {
+    check_frees_everything();
+    check_escaped_roots_are_kept();
+    check_escape_is_flagged();
+}
@@ -0,0 +1,160 @@
+//! Checks the `serde` feature: a tree of `Gc<_>`s round-trips through JSON, shared objects are
+//! written out once per reference, and cycles fail to serialize rather than recursing forever
+
+use gc::{Gc, GcAble, GcCell};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct ExampleNum(u64);
+
+// SAFETY: there are no `Gc<_>`s in an `ExampleNum`
+unsafe impl GcAble for ExampleNum {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Tree {
+    num: Gc<ExampleNum>,
+    children: Vec<Gc<Tree>>,
+}
+
+// SAFETY: every method forwards to `num` and `children`, the only `Gc<_>`s in a `Tree`
+unsafe impl GcAble for Tree {
+    unsafe fn mark(&self) {
+        unsafe { self.num.mark() };
+        self.children
+            .iter()
+            .for_each(|child| unsafe { child.mark() })
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { self.num.inc_root_count() };
+        self.children
+            .iter()
+            .for_each(|child| unsafe { child.inc_root_count() })
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self.num.dec_root_count() };
+        self.children
+            .iter()
+            .for_each(|child| unsafe { child.dec_root_count() })
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { self.num.set_not_root() };
+        self.children
+            .iter()
+            .for_each(|child| unsafe { child.set_not_root() })
+    }
+}
+
+/// A complete tree of the given depth, with every node numbered in pre-order
+fn tree(depth: u32, next: &mut u64) -> Gc<Tree> {
+    let num = Gc::new(ExampleNum(*next));
+    *next += 1;
+    let children = match depth {
+        0 => Vec::new(),
+        _ => (0..3).map(|_| tree(depth - 1, next)).collect(),
+    };
+    Gc::new(Tree { num, children })
+}
+
+fn check_round_trip() {
+    let original = tree(3, &mut 0);
+    let json = serde_json::to_string(&original).unwrap();
+    let copy: Gc<Tree> = serde_json::from_str(&json).unwrap();
+    assert_ne!(copy.as_ptr(), original.as_ptr());
+    assert_eq!(copy.children[2].children[2].children[2].num.0, 39);
+    assert_eq!(serde_json::to_string(&copy).unwrap(), json);
+}
+
+fn check_shared() {
+    let leaf = Gc::new(Tree {
+        num: Gc::new(ExampleNum(1)),
+        children: Vec::new(),
+    });
+    let root = Gc::new(Tree {
+        num: Gc::new(ExampleNum(0)),
+        children: vec![leaf.clone(), leaf],
+    });
+    let json = serde_json::to_string(&root).unwrap();
+    let copy: Gc<Tree> = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&copy).unwrap(), json);
+    assert_ne!(copy.children[0].as_ptr(), copy.children[1].as_ptr());
+    assert_eq!(*copy.children[1].num, ExampleNum(1));
+}
+
+#[derive(Serialize)]
+struct Link(Option<Gc<Node>>);
+
+// SAFETY: every method forwards to the `Gc<_>` in a `Link`, if there is one
+unsafe impl GcAble for Link {
+    unsafe fn mark(&self) {
+        self.0.iter().for_each(|gc| unsafe { gc.mark() })
+    }
+
+    unsafe fn inc_root_count(&self) {
+        self.0.iter().for_each(|gc| unsafe { gc.inc_root_count() })
+    }
+
+    unsafe fn dec_root_count(&self) {
+        self.0.iter().for_each(|gc| unsafe { gc.dec_root_count() })
+    }
+
+    unsafe fn set_not_root(&self) {
+        self.0.iter().for_each(|gc| unsafe { gc.set_not_root() })
+    }
+}
+
+#[derive(Serialize)]
+struct Node {
+    next: GcCell<Link>,
+}
+
+// SAFETY: every method forwards to `next`, the only `GcAble` field of a `Node`
+unsafe impl GcAble for Node {
+    unsafe fn mark(&self) {
+        unsafe { self.next.mark() }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { self.next.inc_root_count() }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self.next.dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { self.next.set_not_root() }
+    }
+}
+
+fn check_cycle() {
+    let a = Gc::new(Node {
+        next: GcCell::new(Link(None)),
+    });
+    let b = Gc::new(Node {
+        next: GcCell::new(Link(Some(a.clone()))),
+    });
+    assert!(serde_json::to_string(&b).is_ok());
+    a.next.set(Link(Some(b.clone())));
+    let err = serde_json::to_string(&b).unwrap_err();
+    assert!(err.to_string().contains("cycle"), "{err}");
+    // A failed attempt leaves nothing behind which would fail the next one
+    a.next.set(Link(None));
+    assert!(serde_json::to_string(&b).is_ok());
+}
+
+pub fn check_all() {
+    check_round_trip();
+    check_shared();
+    check_cycle();
+}
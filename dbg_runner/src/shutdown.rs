@@ -0,0 +1,55 @@
+//! Checks that `Gc<_>`s can be dropped once their context is shut down, including from
+//! thread-local destructors, and that `WeakGc<_>`s to the objects it collected don't upgrade
+//!
+//! Shuts down the global context, so this has to run after every other check
+
+use std::{cell::RefCell, thread};
+
+use gc::{Gc, GcCell, GcContext};
+
+type List = Gc<GcCell<Vec<Gc<u32>>>>;
+
+thread_local! {
+    static KEPT: RefCell<Option<List>> = const { RefCell::new(None) };
+}
+
+/// Shuts down a context of its own, and drops it while `WeakGc<_>`s into it are still around
+fn check_weak() {
+    let ctx = GcContext::new();
+    let kept = ctx.alloc(1u32);
+    let (kept_weak, collected) = (Gc::downgrade(&kept), Gc::downgrade(&ctx.alloc(2u32)));
+    ctx.shutdown();
+    drop(ctx);
+    assert!(collected.upgrade().is_none());
+    assert_eq!(*kept_weak.upgrade().unwrap(), 1);
+
+    drop(kept);
+    GcContext::global().force_collect();
+    // Only the global context was collected
+    assert_eq!(*kept_weak.upgrade().unwrap(), 1);
+}
+
+pub fn check_all() {
+    check_weak();
+    let before = Gc::new(GcCell::new(vec![Gc::new(1u32)]));
+    let collected = Gc::downgrade(&Gc::new(3u32));
+    let handle = before.clone();
+    // Dropped by the thread's thread-local destructors, after the context was shut down
+    let thread = thread::spawn(move || {
+        KEPT.with_borrow_mut(|kept| *kept = Some(handle));
+    });
+
+    gc::shutdown();
+    assert!(Gc::try_new(2u32).is_err());
+    assert!(collected.upgrade().is_none());
+    thread
+        .join()
+        .expect("a thread-local `Gc<_>` panicked when dropped");
+    assert_eq!(*before.get()[0], 1);
+    let weak = Gc::downgrade(&before);
+    drop(before);
+    assert!(weak.upgrade().is_some());
+    // Still collects what's unreachable
+    gc::force_collect();
+    assert!(weak.upgrade().is_none());
+}
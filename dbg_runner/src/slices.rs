@@ -0,0 +1,100 @@
+//! Checks `Gc<[T]>`: the elements are found where they were put, every `Gc<_>` in them is traced
+//! through the slice, and they're all collected together once the slice is unreachable
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use gc::{Gc, GcAble, GcContext};
+
+struct ExampleNum {
+    num: u64,
+    dropped: Arc<AtomicUsize>,
+}
+
+// SAFETY: there are no `Gc<_>`s in an `ExampleNum`
+unsafe impl GcAble for ExampleNum {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+impl Drop for ExampleNum {
+    fn drop(&mut self) {
+        self.dropped.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// An element of a slice, which has to be `GcAble` itself
+#[derive(Clone)]
+struct NumRef(Gc<ExampleNum>);
+
+// SAFETY: every method forwards to the `Gc<_>` in a `NumRef`
+unsafe impl GcAble for NumRef {
+    unsafe fn mark(&self) {
+        unsafe { self.0.mark() }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { self.0.inc_root_count() }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self.0.dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { self.0.set_not_root() }
+    }
+}
+
+const LEN: u64 = 100;
+
+fn check_traced() {
+    let ctx = GcContext::new();
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let nums: Vec<_> = (0..LEN)
+        .map(|num| {
+            NumRef(ctx.alloc(ExampleNum {
+                num,
+                dropped: Arc::clone(&dropped),
+            }))
+        })
+        .collect();
+    let slice = ctx.alloc_slice(&nums);
+    // The elements are now only reachable through the slice
+    drop(nums);
+    ctx.force_collect();
+    assert_eq!(dropped.load(Ordering::SeqCst), 0, "an element was freed");
+    assert_eq!(slice.len(), LEN as usize);
+    assert!(slice
+        .iter()
+        .enumerate()
+        .all(|(i, num)| num.0.num == i as u64));
+    assert_eq!(slice[LEN as usize - 1].0.num, LEN - 1);
+    assert_eq!(ctx.stats().live_allocations, LEN as usize + 1);
+
+    drop(slice);
+    ctx.force_collect();
+    assert_eq!(dropped.load(Ordering::SeqCst), LEN as usize);
+    ctx.assert_no_leaks();
+}
+
+fn check_plain() {
+    let empty: Gc<[u8]> = Gc::from_slice(&[]);
+    assert!(empty.is_empty());
+    let bytes = Gc::from_slice(&[1u8, 2, 3]);
+    let wide: Gc<[u128]> = vec![u128::MAX, 0].into();
+    gc::force_collect();
+    assert_eq!((&*bytes, &*wide), (&[1, 2, 3][..], &[u128::MAX, 0][..]));
+}
+
+pub fn check_all() {
+    check_traced();
+    check_plain();
+}
@@ -0,0 +1,173 @@
+//! Randomized allocations, clones, drops, links and collections, with `consistency_check` run
+//! after every one of them
+//!
+//! The operations come from a seeded generator, so a failing seed is reproduced by running it
+//! again. Every live handle remembers the value its object was given, which has to still be there.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use gc::{Gc, GcAble, GcCell, GcConfig, GcContext};
+
+const SEEDS: u64 = 3;
+const OPS: usize = 3000;
+/// Handles are dropped at random once there are this many, to keep the heap small
+const MAX_HANDLES: usize = 200;
+
+/// xorshift64*, which is plenty for picking operations
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) as usize % n
+    }
+}
+
+#[derive(Clone, Default)]
+struct Edges(Vec<Gc<Node>>);
+
+// SAFETY: every method forwards to the `Gc<_>`s in an `Edges`
+unsafe impl GcAble for Edges {
+    unsafe fn mark(&self) {
+        self.0.iter().for_each(|edge| unsafe { edge.mark() })
+    }
+
+    unsafe fn inc_root_count(&self) {
+        self.0
+            .iter()
+            .for_each(|edge| unsafe { edge.inc_root_count() })
+    }
+
+    unsafe fn dec_root_count(&self) {
+        self.0
+            .iter()
+            .for_each(|edge| unsafe { edge.dec_root_count() })
+    }
+
+    unsafe fn set_not_root(&self) {
+        self.0
+            .iter()
+            .for_each(|edge| unsafe { edge.set_not_root() })
+    }
+}
+
+struct Node {
+    id: usize,
+    dropped: Arc<AtomicUsize>,
+    edges: GcCell<Edges>,
+}
+
+// SAFETY: every method forwards to `edges`, the only `GcAble` field of a `Node`
+unsafe impl GcAble for Node {
+    unsafe fn mark(&self) {
+        unsafe { self.edges.mark() }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { self.edges.inc_root_count() }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self.edges.dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { self.edges.set_not_root() }
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        self.dropped.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn run(seed: u64, config: GcConfig) {
+    // Only collected by the operations below
+    let ctx = GcContext::with_config(
+        config
+            .alloc_watermark(usize::MAX)
+            .max_interval(Duration::from_secs(3600)),
+    );
+    let mut rng = Rng(seed);
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let mut handles: Vec<(usize, Gc<Node>)> = Vec::new();
+    let mut allocated = 0;
+
+    for _ in 0..OPS {
+        match rng.below(10) {
+            0..=2 => {
+                let node = ctx.alloc(Node {
+                    id: allocated,
+                    dropped: Arc::clone(&dropped),
+                    edges: GcCell::new(Edges::default()),
+                });
+                handles.push((allocated, node));
+                allocated += 1;
+            }
+            3 if !handles.is_empty() => {
+                let (id, node) = &handles[rng.below(handles.len())];
+                handles.push((*id, node.clone()));
+            }
+            4 | 5 if !handles.is_empty() => {
+                let (from, to) = (rng.below(handles.len()), rng.below(handles.len()));
+                let Edges(mut edges) = handles[from].1.edges.get();
+                match edges.len() {
+                    0..=3 => edges.push(handles[to].1.clone()),
+                    n => edges[rng.below(n)] = handles[to].1.clone(),
+                }
+                handles[from].1.edges.set(Edges(edges));
+            }
+            6 if !handles.is_empty() => {
+                // Follows an edge, which has to lead to a live object
+                let (_, node) = &handles[rng.below(handles.len())];
+                if let Some(next) = node.edges.get().0.first() {
+                    let id = next.id;
+                    handles.push((id, next.clone()));
+                }
+            }
+            7 => {
+                ctx.collect_step();
+            }
+            8 if rng.below(10) == 0 => ctx.force_collect(),
+            _ if handles.len() > MAX_HANDLES / 2 => {
+                let i = rng.below(handles.len());
+                handles.swap_remove(i);
+            }
+            _ => {}
+        }
+        while handles.len() > MAX_HANDLES {
+            let i = rng.below(handles.len());
+            handles.swap_remove(i);
+        }
+        ctx.consistency_check();
+        for (id, node) in &handles {
+            assert_eq!(node.id, *id, "seed {seed}: an object's value changed");
+        }
+    }
+
+    drop(handles);
+    ctx.force_collect();
+    ctx.consistency_check();
+    assert_eq!(dropped.load(Ordering::SeqCst), allocated, "seed {seed}");
+    ctx.assert_no_leaks();
+}
+
+pub fn check_all() {
+    for seed in 1..=SEEDS {
+        run(seed, GcConfig::default());
+        run(seed, GcConfig::default().incremental(8));
+        run(
+            seed,
+            GcConfig::default().promotion_threshold(1).major_interval(4),
+        );
+    }
+}
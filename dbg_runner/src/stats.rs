@@ -0,0 +1,81 @@
+//! Checks that a context's `total_bytes` is the size of every live box, header included, and
+//! that zero-sized values are no exception
+
+use std::collections::HashSet;
+
+use gc::{Gc, GcAble, GcContext};
+
+struct Bytes<const N: usize>([u8; N]);
+
+// SAFETY: there are no `Gc<_>`s in a `Bytes`
+unsafe impl<const N: usize> GcAble for Bytes<N> {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+/// `total_bytes` grows by the same amount a box later gives back, and boxes of values whose sizes
+/// are both multiples of the header's alignment differ by exactly the difference of those sizes
+fn check_total_bytes() {
+    let ctx = GcContext::new();
+    assert_eq!(ctx.stats().total_bytes, 0);
+
+    let small = ctx.alloc(Bytes([0; 8]));
+    let small_size = ctx.stats().total_bytes;
+    assert!(small_size > 8, "the header wasn't counted");
+    let big = ctx.alloc(Bytes([0; 1024]));
+    let big_size = ctx.stats().total_bytes - small_size;
+    assert_eq!(big_size - small_size, 1024 - 8);
+
+    drop(small);
+    ctx.force_collect();
+    assert_eq!(ctx.stats().total_bytes, big_size);
+    assert_eq!(big.0[1023], 0);
+    drop(big);
+    ctx.force_collect();
+    assert_eq!(ctx.stats().total_bytes, 0);
+    ctx.assert_no_leaks();
+}
+
+/// Every zero-sized value gets its own box, counted and freed like any other
+fn check_many_zero_sized() {
+    const COUNT: usize = 1000;
+    let ctx = GcContext::new();
+    let mut units: Vec<Gc<()>> = (0..COUNT).map(|_| ctx.alloc(())).collect();
+    let box_size = ctx.stats().total_bytes / COUNT;
+    assert!(box_size > 0, "the header wasn't counted");
+    assert!(units.windows(2).all(|pair| !Gc::ptr_eq(&pair[0], &pair[1])));
+    let addrs: HashSet<_> = units.iter().map(|unit| unit.as_ptr()).collect();
+    assert_eq!(addrs.len(), COUNT);
+    assert!(Gc::ptr_eq(&units[0], &units[0].clone()));
+    assert_eq!(
+        (ctx.stats().live_allocations, ctx.stats().total_bytes),
+        (COUNT, COUNT * box_size)
+    );
+
+    let mut keep = false;
+    units.retain(|_| {
+        keep = !keep;
+        keep
+    });
+    ctx.force_collect();
+    ctx.consistency_check();
+    assert_eq!(
+        (ctx.stats().live_allocations, ctx.stats().total_bytes),
+        (COUNT / 2, COUNT / 2 * box_size)
+    );
+
+    drop(units);
+    ctx.force_collect();
+    assert_eq!(ctx.stats().total_bytes, 0);
+    ctx.assert_no_leaks();
+}
+
+pub fn check_all() {
+    check_total_bytes();
+    check_many_zero_sized();
+}
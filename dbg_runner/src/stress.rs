@@ -0,0 +1,123 @@
+//! Many threads allocating, cloning, linking and dropping objects in one context, while another
+//! thread collects it without pause and its own collector runs too
+//!
+//! Nothing may deadlock, every object which is still reachable has to survive, every one which
+//! isn't has to be dropped exactly once, and every root count has to end up where it started
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use gc::{Gc, GcAble, GcContext};
+
+const THREADS: usize = 8;
+const ROUNDS: usize = 2000;
+
+struct Node {
+    id: usize,
+    dropped: Arc<AtomicUsize>,
+    next: Option<Gc<Node>>,
+}
+
+// SAFETY: every method forwards to `next`, the only `Gc<_>` in a `Node`
+unsafe impl GcAble for Node {
+    unsafe fn mark(&self) {
+        if let Some(next) = &self.next {
+            unsafe { next.mark() }
+        }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        if let Some(next) = &self.next {
+            unsafe { next.inc_root_count() }
+        }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        if let Some(next) = &self.next {
+            unsafe { next.dec_root_count() }
+        }
+    }
+
+    unsafe fn set_not_root(&self) {
+        if let Some(next) = &self.next {
+            unsafe { next.set_not_root() }
+        }
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        self.dropped.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Allocates nodes pointing to `shared`, cloning and dropping each a few times, and keeps a few
+fn work(ctx: &GcContext, dropped: &Arc<AtomicUsize>, shared: &Gc<Node>) -> Vec<Gc<Node>> {
+    let mut kept = Vec::new();
+    for id in 1..=ROUNDS {
+        let node = ctx.alloc(Node {
+            id,
+            dropped: Arc::clone(dropped),
+            next: Some(shared.clone()),
+        });
+        let clones: Vec<_> = (0..4).map(|_| node.clone()).collect();
+        drop(clones);
+        if id % 100 == 0 {
+            kept.push(node);
+        }
+        // Everything this thread kept is still there, and still points where it did
+        for node in &kept {
+            let next = node.next.as_ref().unwrap();
+            assert_eq!(next.id, 0, "a kept node's link was lost");
+        }
+    }
+    kept
+}
+
+pub fn check_all() {
+    let ctx = GcContext::new();
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let shared = ctx.alloc(Node {
+        id: 0,
+        dropped: Arc::clone(&dropped),
+        next: None,
+    });
+    let done = AtomicBool::new(false);
+
+    let kept: Vec<Gc<Node>> = thread::scope(|s| {
+        s.spawn(|| {
+            while !done.load(Ordering::Relaxed) {
+                ctx.force_collect();
+            }
+        });
+        let workers: Vec<_> = (0..THREADS)
+            .map(|_| s.spawn(|| work(&ctx, &dropped, &shared)))
+            .collect();
+        let kept: Vec<_> = workers.into_iter().map(|worker| worker.join()).collect();
+        // Before a panic is resumed, or the collecting thread would keep the scope open forever
+        done.store(true, Ordering::Relaxed);
+        kept.into_iter().flat_map(Result::unwrap).collect()
+    });
+
+    ctx.force_collect();
+    let live = ctx.live_allocations();
+    assert_eq!(live.len(), kept.len() + 1);
+    // Each kept node is only rooted by `kept`, and `shared` only by itself
+    assert!(live.iter().all(|alloc| alloc.root_count == 1), "{live:#?}");
+    let allocated = THREADS * ROUNDS + 1;
+    assert_eq!(
+        dropped.load(Ordering::SeqCst),
+        allocated - kept.len() - 1,
+        "an object was dropped twice, or not at all"
+    );
+
+    drop((kept, shared));
+    ctx.force_collect();
+    assert_eq!(dropped.load(Ordering::SeqCst), allocated);
+    ctx.assert_no_leaks();
+}
@@ -0,0 +1,100 @@
+//! Checks `Gc::structural_eq` and `Gc::structural_hash` on cyclic graphs
+//!
+//! Every node links to the next in a ring and to a few others, so every node is reached along
+//! several paths and through several cycles
+
+use std::hash::{BuildHasher, Hash, Hasher, RandomState};
+
+use gc::{
+    DeepClone, DeepCloner, Gc, GcCell, GcContext, StructuralCmp, StructuralEq, StructuralHash,
+    StructuralHasher,
+};
+
+const NODES: u32 = 1000;
+
+struct Node {
+    id: u32,
+    links: GcCell<Vec<Gc<Node>>>,
+}
+
+gc::impl_gc_able!(Node => |node, visit| visit(&node.links));
+
+impl StructuralEq for Node {
+    fn structural_eq(&self, other: &Self, cmp: &mut StructuralCmp) -> bool {
+        let (links, other_links) = (self.links.get(), other.links.get());
+        self.id == other.id
+            && links.len() == other_links.len()
+            && links.iter().zip(&other_links).all(|(a, b)| cmp.eq_gc(a, b))
+    }
+}
+
+impl StructuralHash for Node {
+    fn structural_hash(&self, hasher: &mut StructuralHasher<'_>) {
+        let links = self.links.get();
+        self.id.hash(hasher);
+        links.len().hash(hasher);
+        for link in &links {
+            hasher.hash_gc(link);
+        }
+    }
+}
+
+impl DeepClone for Node {
+    fn clone_deep(&self, cloner: &mut DeepCloner) -> Self {
+        let links = self.links.get();
+        Node {
+            id: self.id,
+            links: GcCell::new(links.iter().map(|link| cloner.clone_gc(link)).collect()),
+        }
+    }
+}
+
+/// A ring of `NODES` nodes, each also linking to the node `skip` ahead of it
+fn graph(ctx: &GcContext, skip: u32) -> Gc<Node> {
+    let nodes: Vec<_> = (0..NODES)
+        .map(|id| {
+            ctx.alloc(Node {
+                id,
+                links: GcCell::new(Vec::new()),
+            })
+        })
+        .collect();
+    for (i, node) in nodes.iter().enumerate() {
+        let next = nodes[(i + 1) % nodes.len()].clone();
+        let skipped = nodes[(i + skip as usize) % nodes.len()].clone();
+        node.links.set(vec![next, skipped]);
+    }
+    nodes[0].clone()
+}
+
+fn hash(state: &RandomState, gc: &Gc<Node>) -> u64 {
+    let mut hasher = state.build_hasher();
+    Gc::structural_hash(gc, &mut hasher);
+    hasher.finish()
+}
+
+pub fn check_all() {
+    let ctx = GcContext::new();
+    let state = RandomState::new();
+    let (a, b) = (graph(&ctx, 7), graph(&ctx, 7));
+    assert!(Gc::structural_eq(&a, &b));
+    assert!(Gc::structural_eq(&a, &a));
+    assert_eq!(hash(&state, &a), hash(&state, &b));
+
+    let copy = a.clone_deep();
+    assert!(Gc::structural_eq(&a, &copy));
+    assert_eq!(hash(&state, &a), hash(&state, &copy));
+
+    // Same ids in the same ring, linked differently
+    let c = graph(&ctx, 8);
+    assert!(!Gc::structural_eq(&a, &c));
+    assert_ne!(hash(&state, &a), hash(&state, &c));
+
+    // Same shape, one node further round the ring differs
+    let far = b.links.get()[1].links.get()[1].clone();
+    let mut links = far.links.get();
+    links.reverse();
+    far.links.set(links);
+    assert!(!Gc::structural_eq(&a, &b));
+    assert_ne!(hash(&state, &a), hash(&state, &b));
+}
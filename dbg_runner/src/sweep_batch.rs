@@ -0,0 +1,111 @@
+//! Checks `GcConfig::max_sweep_batch`
+//!
+//! A large chain of garbage is swept a batch at a time, with the lock released in between, and has
+//! to be reclaimed entirely without disturbing the objects which are still alive
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use gc::{Gc, GcCell, GcConfig, GcContext};
+
+const GARBAGE: usize = 10_000;
+const BATCH: usize = 1000;
+
+struct Node {
+    dropped: Arc<AtomicUsize>,
+    next: GcCell<Option<Gc<Node>>>,
+}
+
+gc::impl_gc_able!(Node => |node, visit| visit(&node.next));
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        self.dropped.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Links `n` nodes into a chain, returning its head
+fn chain(ctx: &GcContext, n: usize, dropped: &Arc<AtomicUsize>) -> Gc<Node> {
+    let mut head = None;
+    for _ in 0..n {
+        head = Some(ctx.alloc(Node {
+            dropped: Arc::clone(dropped),
+            next: GcCell::new(head),
+        }));
+    }
+    head.unwrap()
+}
+
+fn check_steps() {
+    let ctx = GcContext::with_config(
+        GcConfig::default()
+            .deterministic(true)
+            .max_sweep_batch(BATCH),
+    );
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let kept_dropped = Arc::new(AtomicUsize::new(0));
+    let kept = chain(&ctx, 100, &kept_dropped);
+    drop(chain(&ctx, GARBAGE, &dropped));
+
+    let mut steps = 0u32;
+    let mut allocated = Vec::new();
+    loop {
+        steps += 1;
+        let before = dropped.load(Ordering::SeqCst);
+        let done = ctx.collect_step();
+        assert!(dropped.load(Ordering::SeqCst) - before <= BATCH);
+        ctx.consistency_check();
+        // Other threads can use the context between batches
+        allocated.push(ctx.alloc(steps));
+        if done {
+            break;
+        }
+    }
+    // Every object is finalized, dropped, then deallocated, a batch at a time, the first batch in
+    // the same step as marking. Nothing else collects in between, so that's exact.
+    assert_eq!(steps as usize, 3 * GARBAGE / BATCH);
+    assert_eq!(dropped.load(Ordering::SeqCst), GARBAGE);
+    assert_eq!(kept_dropped.load(Ordering::SeqCst), 0);
+    assert_eq!(ctx.stats().live_allocations, 100 + allocated.len());
+    ctx.consistency_check();
+
+    drop((kept, allocated));
+    ctx.force_collect();
+    assert_eq!(kept_dropped.load(Ordering::SeqCst), 100);
+    ctx.assert_no_leaks();
+}
+
+/// `force_collect` reclaims everything, including what's left of a batched sweep
+fn check_force_collect() {
+    let ctx = GcContext::with_config(
+        GcConfig::default()
+            .deterministic(true)
+            .max_sweep_batch(BATCH),
+    );
+    let dropped = Arc::new(AtomicUsize::new(0));
+    drop(chain(&ctx, GARBAGE, &dropped));
+    assert!(!ctx.collect_step());
+    drop(chain(&ctx, GARBAGE, &dropped));
+    ctx.force_collect();
+    assert_eq!(dropped.load(Ordering::SeqCst), 2 * GARBAGE);
+    ctx.assert_no_leaks();
+}
+
+/// The background collector sweeps in batches while another thread keeps allocating
+fn check_background() {
+    let ctx = GcContext::with_config(GcConfig::default().max_sweep_batch(100));
+    let dropped = Arc::new(AtomicUsize::new(0));
+    for _ in 0..10 {
+        drop(chain(&ctx, GARBAGE, &dropped));
+    }
+    ctx.wait_for_collection();
+    assert_eq!(dropped.load(Ordering::SeqCst), 10 * GARBAGE);
+}
+
+pub fn check_all() {
+    check_steps();
+    check_force_collect();
+    check_background();
+}
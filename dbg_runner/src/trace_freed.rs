@@ -0,0 +1,93 @@
+//! Checks that the collector panics when tracing reaches a `Gc<_>` whose object was already freed,
+//! instead of marking freed memory
+//!
+//! Only debug builds keep track of which objects are alive
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, PoisonError,
+    },
+    time::Duration,
+};
+
+use gc::{Gc, GcAble, GcConfig, GcContext};
+
+static SKIP_MARK: AtomicBool = AtomicBool::new(false);
+
+/// Doesn't mark its child while `SKIP_MARK` is set, breaking the `GcAble` contract so that the
+/// child is freed while still pointed to
+struct Holder(Mutex<Option<Gc<u32>>>);
+
+unsafe impl GcAble for Holder {
+    unsafe fn mark(&self) {
+        if !SKIP_MARK.load(Ordering::SeqCst) {
+            if let Some(child) = &*self.0.lock().unwrap() {
+                unsafe { child.mark() }
+            }
+        }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        if let Some(child) = &*self.0.lock().unwrap() {
+            unsafe { child.inc_root_count() }
+        }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        if let Some(child) = &*self.0.lock().unwrap() {
+            unsafe { child.dec_root_count() }
+        }
+    }
+
+    unsafe fn set_not_root(&self) {
+        if let Some(child) = &*self.0.lock().unwrap() {
+            unsafe { child.set_not_root() }
+        }
+    }
+}
+
+pub fn check_all() {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    // Only collected by the check itself
+    let ctx = GcContext::with_config(
+        GcConfig::default()
+            .alloc_watermark(usize::MAX)
+            .max_interval(Duration::from_secs(3600)),
+    );
+    let holder = ctx.alloc(Holder(Mutex::new(Some(ctx.alloc(5u32)))));
+    ctx.force_collect();
+    assert_eq!(ctx.stats().live_allocations, 2);
+
+    SKIP_MARK.store(true, Ordering::SeqCst);
+    ctx.force_collect();
+    SKIP_MARK.store(false, Ordering::SeqCst);
+    assert_eq!(ctx.stats().live_allocations, 1, "the child wasn't freed");
+
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let collected = panic::catch_unwind(AssertUnwindSafe(|| ctx.force_collect()));
+    panic::set_hook(hook);
+    let payload = collected.expect_err("tracing a freed object didn't panic");
+    let msg = payload.downcast_ref::<String>().unwrap();
+    assert!(
+        msg.starts_with("the collector reached a `Gc<_>` pointing to "),
+        "{msg}"
+    );
+    assert!(msg.ends_with(", which isn't a live object"), "{msg}");
+
+    // The panic poisoned the lock, and dropping the child would touch the freed object too
+    let mut child = holder.0.lock().unwrap_or_else(PoisonError::into_inner);
+    std::mem::forget(child.take());
+    drop(child);
+    holder.0.clear_poison();
+    ctx.force_collect();
+    assert_eq!(ctx.stats().live_allocations, 1);
+    drop(holder);
+    ctx.force_collect();
+    ctx.consistency_check();
+    ctx.assert_no_leaks();
+}
@@ -0,0 +1,83 @@
+//! Checks `GcContext::request_urgent_collection`: it returns right away after waking the driver,
+//! whose next step then finishes the collection in progress in one go, even inside a `GcBatch`,
+//! and a context without a collector collects on the calling thread instead
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, OnceLock,
+};
+
+use gc::{Collector, CollectorDriver, Gc, GcConfig, GcContext};
+
+/// Counts its wakes, leaving collecting to the caller
+#[derive(Clone, Default)]
+struct Counting {
+    collector: Arc<OnceLock<Collector>>,
+    wakes: Arc<AtomicUsize>,
+}
+
+impl CollectorDriver for Counting {
+    fn start(&self, collector: Collector) {
+        let _ = self.collector.set(collector);
+    }
+
+    fn wake(&self) {
+        self.wakes.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Allocates `live` objects which are kept and as many which aren't
+fn heap(ctx: &GcContext, live: u32) -> Vec<Gc<u32>> {
+    let kept = (0..live).map(|i| ctx.alloc(i)).collect();
+    for i in 0..live {
+        ctx.alloc(i);
+    }
+    kept
+}
+
+fn check_driven() {
+    let driver = Counting::default();
+    let ctx = GcContext::with_config(
+        GcConfig::default()
+            .alloc_watermark(usize::MAX)
+            .incremental(10)
+            .major_interval(1)
+            .driver(driver.clone()),
+    );
+    let collector = driver.collector.get().unwrap();
+    let kept = heap(&ctx, 1000);
+
+    // Partway through marking
+    assert!(!ctx.collect_step());
+    assert!(collector.is_requested());
+    let batch = ctx.batch();
+    let wakes = driver.wakes.load(Ordering::SeqCst);
+    ctx.request_urgent_collection();
+    assert_eq!(driver.wakes.load(Ordering::SeqCst), wakes + 1);
+    // Only the driver collects
+    assert_eq!(ctx.stats().live_allocations, 2000);
+
+    assert_eq!(collector.collect_step(), Some(true));
+    assert_eq!(ctx.stats().live_allocations, kept.len());
+    drop(batch);
+
+    drop(kept);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
+
+/// A deterministic context never collects on its collector's thread, so there's nothing to wake
+fn check_undriven() {
+    let ctx = GcContext::with_config(GcConfig::default().deterministic(true).incremental(10));
+    let kept = heap(&ctx, 1000);
+    ctx.request_urgent_collection();
+    assert_eq!(ctx.stats().live_allocations, kept.len());
+    drop(kept);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
+
+pub fn check_all() {
+    check_driven();
+    check_undriven();
+}
@@ -0,0 +1,33 @@
+//! Checks that a `Gc<_>` whose object was freed panics when it's used, instead of reading freed
+//! memory
+//!
+//! Only debug builds keep track of which objects are alive
+
+use std::panic::{self, AssertUnwindSafe};
+
+use gc::{GcConfig, GcContext};
+
+pub fn check_all() {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    let ctx = GcContext::with_config(GcConfig::default().deterministic(true));
+    let owner = ctx.alloc(5u32);
+    let dangling = owner.clone();
+    // Breaks the `GcAble` contract, since `dangling` isn't stored in any value, so nothing keeps
+    // the object alive once `owner` is dropped
+    unsafe { dangling.set_not_root() };
+    assert!(dangling.is_alive());
+    drop(owner);
+    ctx.force_collect();
+    assert!(!dangling.is_alive(), "the object wasn't freed");
+    assert!(dangling.as_ref_option().is_none());
+
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let read = panic::catch_unwind(AssertUnwindSafe(|| *dangling));
+    panic::set_hook(hook);
+    assert!(read.is_err(), "reading a freed object didn't panic");
+    // Dropping it would touch the freed object too
+    std::mem::forget(dangling);
+}
@@ -0,0 +1,106 @@
+//! Checks that `GcConfig::verify_roots` catches a hand-written `GcAble` whose `set_not_root`
+//! misses a field, which would otherwise keep that field's object alive forever
+//!
+//! Only checked in debug builds, which are the only ones to verify
+
+use std::panic::{self, AssertUnwindSafe};
+
+use gc::{Gc, GcAble, GcConfig, GcContext};
+
+struct Pair {
+    first: Gc<u32>,
+    second: Gc<u32>,
+}
+
+/// Forgets `second` in `set_not_root`
+struct Buggy(Pair);
+
+// SAFETY: it isn't, which is what's being checked
+unsafe impl GcAble for Buggy {
+    unsafe fn mark(&self) {
+        unsafe {
+            self.0.first.mark();
+            self.0.second.mark();
+        }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe {
+            self.0.first.inc_root_count();
+            self.0.second.inc_root_count();
+        }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe {
+            self.0.first.dec_root_count();
+            self.0.second.dec_root_count();
+        }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { self.0.first.set_not_root() }
+    }
+}
+
+// SAFETY: every method forwards to `first` and `second`, the only `Gc<_>`s in a `Pair`
+unsafe impl GcAble for Pair {
+    unsafe fn mark(&self) {
+        unsafe {
+            self.first.mark();
+            self.second.mark();
+        }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe {
+            self.first.inc_root_count();
+            self.second.inc_root_count();
+        }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe {
+            self.first.dec_root_count();
+            self.second.dec_root_count();
+        }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe {
+            self.first.set_not_root();
+            self.second.set_not_root();
+        }
+    }
+}
+
+pub fn check_all() {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    let ctx = GcContext::with_config(GcConfig::default().verify_roots(true));
+    let pair = |ctx: &GcContext| Pair {
+        first: ctx.alloc(1),
+        second: ctx.alloc(2),
+    };
+
+    let correct = ctx.alloc(pair(&ctx));
+    drop(correct);
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+
+    let buggy = pair(&ctx);
+    let allocated = panic::catch_unwind(AssertUnwindSafe(|| drop(ctx.alloc(Buggy(buggy)))));
+    let payload = allocated.expect_err("a missed `set_not_root` wasn't caught");
+    let msg = payload.downcast_ref::<String>().unwrap();
+    assert!(
+        msg.contains(
+            "`<dbg_runner::verify_roots::Buggy as GcAble>::set_not_root` missed a `Gc<u32>`"
+        ),
+        "{msg}"
+    );
+
+    // Nothing is left behind by the object which failed to allocate
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
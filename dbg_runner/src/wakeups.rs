@@ -0,0 +1,86 @@
+//! Checks that the collector thread sleeps while nothing is allocated, and is woken once enough
+//! is
+//!
+//! It used to collect every millisecond whether or not there was anything to collect
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use gc::{GcAble, GcConfig, GcContext};
+
+/// Sets its flag once dropped, which only a collection does
+struct Garbage(Arc<AtomicBool>);
+
+// SAFETY: there are no `Gc<_>`s in a `Garbage`
+unsafe impl GcAble for Garbage {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+impl Drop for Garbage {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Waits for up to 10 seconds for `dropped` to be set by a collection
+fn wait_for_collection(dropped: &AtomicBool, what: &str) {
+    let start = Instant::now();
+    while !dropped.load(Ordering::SeqCst) {
+        assert!(start.elapsed() < Duration::from_secs(10), "{what}");
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// An idle collector still runs once every `max_interval`, but not before
+fn check_idle() {
+    let ctx = GcContext::with_config(GcConfig::default().max_interval(Duration::from_millis(500)));
+    let dropped = Arc::new(AtomicBool::new(false));
+    drop(ctx.alloc(Garbage(Arc::clone(&dropped))));
+    // Collected within a millisecond or so by the old polling loop
+    thread::sleep(Duration::from_millis(20));
+    assert!(
+        !dropped.load(Ordering::SeqCst),
+        "the collector ran without being woken"
+    );
+    wait_for_collection(&dropped, "an idle collector never ran");
+}
+
+/// Crossing the watermark wakes the collector long before `max_interval` is up
+fn check_watermark() {
+    let ctx = GcContext::with_config(
+        GcConfig::default()
+            .alloc_watermark(100)
+            .max_interval(Duration::from_secs(3600)),
+    );
+    let dropped = Arc::new(AtomicBool::new(false));
+    drop(ctx.alloc(Garbage(Arc::clone(&dropped))));
+    thread::sleep(Duration::from_millis(50));
+    assert!(
+        !dropped.load(Ordering::SeqCst),
+        "the collector ran without being woken"
+    );
+
+    for i in 0..100u32 {
+        ctx.alloc(i);
+    }
+    wait_for_collection(&dropped, "crossing the watermark didn't wake the collector");
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
+
+pub fn check_all() {
+    check_idle();
+    check_watermark();
+}
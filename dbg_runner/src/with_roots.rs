@@ -0,0 +1,31 @@
+//! Checks `gc::with_roots`, which roots objects by the `Gc<_>`s given to it
+
+use gc::{Gc, GcCell, GcConfig};
+
+pub fn check_all() {
+    let ctx = crate::basics::context(GcConfig::default());
+
+    // A `Gc<_>` which isn't a root, like one left behind in an object which was freed
+    let gc = ctx.alloc(1u32);
+    let weak = Gc::downgrade(&gc);
+    unsafe { gc.set_not_root() };
+    gc::with_roots(&[&gc], || {
+        ctx.force_collect();
+        assert_eq!(*gc, 1);
+    });
+    ctx.force_collect();
+    assert!(weak.upgrade().is_none(), "the root outlived `with_roots`");
+    // Dropping it would touch the freed object
+    std::mem::forget(gc);
+
+    // Changing what the `Gc<_>`s given are stored in doesn't change what's rooted
+    let (old, new) = (ctx.alloc(2u32), ctx.alloc(3u32));
+    let node = ctx.alloc(GcCell::new(Some(old.clone())));
+    gc::with_roots(&[&node, &old], || node.set(Some(new.clone())));
+    drop(node);
+    ctx.force_collect();
+    assert_eq!((*old, *new), (2, 3));
+    drop((old, new));
+    ctx.force_collect();
+    ctx.assert_no_leaks();
+}
@@ -0,0 +1,36 @@
+//! Times allocating and then dropping a million `Gc<_>`s, with and without a `GcBatch` around it
+//!
+//! `cargo bench -p gc --bench batch`
+//!
+//! Without a batch the collector keeps collecting partway through, each time tracing everything
+//! allocated so far. With one it collects once, after everything was dropped.
+
+use std::time::{Duration, Instant};
+
+use gc::{GcConfig, GcContext};
+
+const GCS: usize = 1_000_000;
+const RUNS: usize = 5;
+
+/// Returns how long a run took, and how many collections finished during it
+fn run(batched: bool) -> (Duration, u64) {
+    let ctx = GcContext::with_config(GcConfig::default());
+    let before = ctx.runtime_metrics().collections;
+    let start = Instant::now();
+    let batch = batched.then(|| ctx.batch());
+    let gcs = (0..GCS).map(|i| ctx.alloc(i as u64)).collect::<Vec<_>>();
+    drop(gcs);
+    drop(batch);
+    let elapsed = start.elapsed();
+    let collections = ctx.runtime_metrics().collections - before;
+    (elapsed, collections)
+}
+
+fn main() {
+    for (name, batched) in [("unbatched", false), ("batched", true)] {
+        let mut runs = (0..RUNS).map(|_| run(batched)).collect::<Vec<_>>();
+        runs.sort();
+        let (median, collections) = runs[RUNS / 2];
+        println!("{name:>10}: {median:>10.2?} per {GCS} Gcs, {collections} collections");
+    }
+}
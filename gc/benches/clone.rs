@@ -0,0 +1,58 @@
+//! Times cloning and dropping a `Gc<_>`, on one thread and on several at once
+//!
+//! `cargo bench -p gc --bench clone`
+//!
+//! Neither takes the context's lock, so threads only contend on the object's root count, and a
+//! collection running meanwhile doesn't hold them up.
+
+use std::{
+    hint::black_box,
+    thread,
+    time::{Duration, Instant},
+};
+
+use gc::{GcConfig, GcContext};
+
+const CLONES: u32 = 10_000_000;
+const RUNS: usize = 5;
+
+/// Returns the median time per clone and drop on each of `threads` threads
+fn per_clone(ctx: &GcContext, threads: u32) -> Duration {
+    let gc = ctx.alloc(0u64);
+    let mut runs = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            thread::scope(|s| {
+                for _ in 0..threads {
+                    s.spawn(|| {
+                        for _ in 0..CLONES / threads {
+                            drop(black_box(gc.clone()));
+                        }
+                    });
+                }
+            });
+            start.elapsed() / (CLONES / threads)
+        })
+        .collect::<Vec<_>>();
+    runs.sort();
+    runs[RUNS / 2]
+}
+
+fn main() {
+    let ctx = GcContext::with_config(GcConfig::default().deterministic(true));
+    for threads in [1, 4] {
+        let idle = per_clone(&ctx, threads);
+        println!("{threads} thread(s), idle: {idle:>8.2?} per clone");
+    }
+
+    // The collector keeps tracing a large heap meanwhile
+    let ctx = GcContext::with_config(
+        GcConfig::default()
+            .alloc_watermark(1000)
+            .max_interval(Duration::from_millis(1)),
+    );
+    let heap = (0..100_000u64).map(|i| ctx.alloc(i)).collect::<Vec<_>>();
+    let collecting = per_clone(&ctx, 4);
+    println!("4 thread(s), collecting: {collecting:>8.2?} per clone");
+    drop(heap);
+}
@@ -0,0 +1,39 @@
+//! Times allocating on the thread's local heap against allocating in the global context
+//!
+//! `cargo bench -p gc --bench local`
+//!
+//! Both run on one thread alone, so the difference is what the global context's lock and atomics
+//! cost an embedder which never shares anything.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use gc::{force_collect_local, Gc, LocalGc};
+
+const ALLOCS: u64 = 100_000;
+const RUNS: usize = 5;
+
+/// Returns the median time to allocate `ALLOCS` values with `alloc`, keeping every tenth
+fn run<G>(alloc: impl Fn(u64) -> G, collect: impl Fn()) -> Duration {
+    let mut runs = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            let kept: Vec<G> = (0..ALLOCS).map(&alloc).step_by(10).collect();
+            let elapsed = start.elapsed();
+            drop(black_box(kept));
+            collect();
+            elapsed
+        })
+        .collect::<Vec<_>>();
+    runs.sort();
+    runs[RUNS / 2]
+}
+
+fn main() {
+    let local = run(LocalGc::new, force_collect_local);
+    let global = run(Gc::new, gc::force_collect);
+    println!("{ALLOCS} allocations on one thread: {local:>10.2?} as LocalGc");
+    println!("{ALLOCS} allocations on one thread: {global:>10.2?} as Gc");
+}
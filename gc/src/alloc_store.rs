@@ -1 +1,62 @@
+use alloc::vec::Vec;
+use core::{alloc::Layout, mem, ptr::NonNull};
 
+use crate::sys::collections::HashMap;
+
+/// Boxes freed by the collector, kept to be reused by later allocations of the same layout instead
+/// of going back to the allocator, see [`crate::GcConfig::free_list_capacity`]
+///
+/// The memory is handed out as is, since every allocation writes its whole header and value before
+/// anything reads them.
+pub(crate) struct FreeList {
+    /// The most boxes kept at once, across every layout
+    capacity: usize,
+    len: usize,
+    /// Keyed by size and alignment, since `Layout` isn't `Ord` for the map used without `std`
+    boxes: HashMap<(usize, usize), Vec<NonNull<u8>>>,
+}
+
+// Safety: the boxes are unused memory, owned by the list
+unsafe impl Send for FreeList {}
+
+impl FreeList {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            len: 0,
+            boxes: HashMap::new(),
+        }
+    }
+
+    /// Takes a box which was allocated with `layout`
+    pub fn pop(&mut self, layout: Layout) -> Option<NonNull<u8>> {
+        let ptr = self.boxes.get_mut(&key(layout))?.pop()?;
+        self.len -= 1;
+        Some(ptr)
+    }
+
+    /// Keeps `ptr` for reuse, or gives it back if the list is full
+    pub fn push(&mut self, ptr: NonNull<u8>, layout: Layout) -> Result<(), NonNull<u8>> {
+        if self.len >= self.capacity {
+            return Err(ptr);
+        }
+        self.boxes.entry(key(layout)).or_default().push(ptr);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes every box, which the caller has to deallocate
+    pub fn drain(&mut self) -> impl Iterator<Item = (NonNull<u8>, Layout)> + '_ {
+        self.len = 0;
+        mem::take(&mut self.boxes)
+            .into_iter()
+            .flat_map(|((size, align), boxes)| {
+                let layout = Layout::from_size_align(size, align).unwrap();
+                boxes.into_iter().map(move |ptr| (ptr, layout))
+            })
+    }
+}
+
+fn key(layout: Layout) -> (usize, usize) {
+    (layout.size(), layout.align())
+}
@@ -0,0 +1,212 @@
+use alloc::sync::Arc;
+use core::{
+    fmt::Debug,
+    mem::ManuallyDrop,
+    ptr::NonNull,
+    sync::atomic::{AtomicBool, AtomicPtr, Ordering},
+};
+
+use crate::{
+    context::ContextInner,
+    sys::sync::{Mutex, MutexGuard, PoisonError},
+    Gc, GcAble, GcBox,
+};
+
+/// A `Gc<T>` which can be swapped out through a shared reference, for concurrent data structures
+///
+/// This is a root like any other `Gc<_>` until it's stored inside a `GcAble` value. Every operation
+/// briefly takes the lock of the context the value belongs to, which the collector holds while it
+/// traces and frees objects. That's what makes it safe to root a value which another thread may be
+/// swapping out at the same time, since the value can't be freed in between.
+///
+/// Every value stored in one `AtomicGc<_>` has to belong to the same context.
+pub struct AtomicGc<T: GcAble> {
+    gcbox: AtomicPtr<GcBox<T>>,
+    /// Whether the `Gc<_>` in the slot counts as a root, which it stops doing once this is stored
+    /// in a `Gc<_>`. Only changed while holding the context's lock.
+    is_root: AtomicBool,
+    /// The number of `GcAble::inc_root_count`s not yet undone by `GcAble::dec_root_count`, each of
+    /// which roots whatever is in the slot, so every swap moves them from the old value to the new
+    ///
+    /// Locked by swaps while they hold the context's lock, and on its own by the two methods
+    held: Mutex<u32>,
+    context: Arc<ContextInner>,
+}
+
+impl<T: GcAble> AtomicGc<T> {
+    pub fn new(gc: Gc<T>) -> Self {
+        let context = Arc::clone(&gc.gcb().header.context);
+        let (is_root, gcbox) = Gc::into_parts(gc);
+        Self {
+            gcbox: AtomicPtr::new(gcbox.as_ptr()),
+            is_root: AtomicBool::new(is_root),
+            held: Mutex::new(0),
+            context,
+        }
+    }
+
+    /// Returns a new root `Gc<T>` to the value currently stored
+    pub fn load(&self) -> Gc<T> {
+        // Holding the lock makes sure the value isn't swapped out and collected while it's being
+        // rooted
+        let _gc = self.context.lock();
+        unsafe { Gc::from_gcbox(self.current()) }
+    }
+
+    /// Stores `gc`, dropping the value previously stored
+    pub fn store(&self, gc: Gc<T>) {
+        drop(self.swap(gc))
+    }
+
+    /// Stores `gc`, returning the value previously stored as a root `Gc<T>`
+    pub fn swap(&self, gc: Gc<T>) -> Gc<T> {
+        self.check_context(&gc);
+        let mut lock = self.context.lock();
+        unsafe { self.swap_locked(&mut lock, gc) }
+    }
+
+    /// Stores `new` if the value currently stored is the same object as `current`, see
+    /// [`Gc::ptr_eq`]
+    ///
+    /// Returns the value previously stored if it was replaced, and gives `new` back otherwise.
+    pub fn compare_exchange(&self, current: &Gc<T>, new: Gc<T>) -> Result<Gc<T>, Gc<T>> {
+        self.check_context(&new);
+        let mut lock = self.context.lock();
+        if self.current() != current.gcbox {
+            return Err(new);
+        }
+        Ok(unsafe { self.swap_locked(&mut lock, new) })
+    }
+
+    /// Consumes this, returning the value stored
+    pub fn into_inner(self) -> Gc<T> {
+        let this = ManuallyDrop::new(self);
+        let gc = Gc {
+            is_root: AtomicBool::new(this.is_root.load(Ordering::Acquire)),
+            gcbox: this.current(),
+        };
+        drop(unsafe { core::ptr::read(&this.context) });
+        gc
+    }
+
+    fn current(&self) -> NonNull<GcBox<T>> {
+        NonNull::new(self.gcbox.load(Ordering::Acquire)).unwrap()
+    }
+
+    fn check_context(&self, gc: &Gc<T>) {
+        let context = &gc.gcb().header.context;
+        assert!(
+            Arc::ptr_eq(context, &self.context),
+            "tried to store a `Gc<_>` from another context in an `AtomicGc<_>`",
+        );
+    }
+
+    /// # Safety
+    /// `gc` must be the context's `GcAlloc`, locked by the caller
+    unsafe fn swap_locked(&self, gc: &mut crate::GcAlloc, new: Gc<T>) -> Gc<T> {
+        let held = self.held();
+        let is_root = self.is_root.load(Ordering::Acquire);
+        let (new_is_root, new) = Gc::into_parts(new);
+        let new_gc = ManuallyDrop::new(Gc {
+            is_root: AtomicBool::new(new_is_root),
+            gcbox: new,
+        });
+        for _ in 0..*held {
+            unsafe { new_gc.inc_root_count() };
+        }
+        if is_root {
+            if !new_is_root {
+                unsafe { new_gc.inc_root_count() };
+            }
+        } else {
+            // The object this is stored in may already have been scanned, or may be old
+            gc.shade((unsafe { new.as_ref() }.header.erase)(new.cast()));
+            unsafe { new_gc.set_not_root() };
+        }
+
+        let old = NonNull::new(self.gcbox.swap(new.as_ptr(), Ordering::AcqRel)).unwrap();
+        let old = Gc {
+            is_root: AtomicBool::new(is_root),
+            gcbox: old,
+        };
+        // Only reachable through the containing object until now, which the lock keeps from
+        // being collected before it's a root
+        if !is_root {
+            unsafe { old.inc_root_count() };
+            old.is_root.store(true, Ordering::Release);
+        }
+        for _ in 0..*held {
+            unsafe { old.dec_root_count() };
+        }
+        old
+    }
+
+    fn held(&self) -> MutexGuard<'_, u32> {
+        self.held.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The `Gc<_>` in the slot, which must not be dropped
+    fn view(&self) -> ManuallyDrop<Gc<T>> {
+        ManuallyDrop::new(Gc {
+            is_root: AtomicBool::new(self.is_root.load(Ordering::Acquire)),
+            gcbox: self.current(),
+        })
+    }
+
+    /// Calls `f` on the `Gc<_>` in the slot, then keeps whether `f` made it a root or not
+    ///
+    /// Only called while the context's lock is held, like every other change to `is_root`
+    fn update(&self, f: impl FnOnce(&Gc<T>)) {
+        let gc = self.view();
+        f(&gc);
+        self.is_root
+            .store(gc.is_root.load(Ordering::Acquire), Ordering::Release);
+    }
+}
+
+impl<T: GcAble> From<Gc<T>> for AtomicGc<T> {
+    fn from(gc: Gc<T>) -> Self {
+        Self::new(gc)
+    }
+}
+
+impl<T: GcAble + Debug> Debug for AtomicGc<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("AtomicGc").field(&*self.load()).finish()
+    }
+}
+
+impl<T: GcAble> Drop for AtomicGc<T> {
+    fn drop(&mut self) {
+        drop(Gc {
+            is_root: AtomicBool::new(*self.is_root.get_mut()),
+            gcbox: self.current(),
+        })
+    }
+}
+
+/// [`GcAble::inc_root_count`] roots whatever value is in the slot until the matching
+/// [`GcAble::dec_root_count`], so a swap in between moves the root from the old value to the new one
+unsafe impl<T: GcAble> GcAble for AtomicGc<T> {
+    unsafe fn mark(&self) {
+        // Makes the `Gc<_>` a root again if this is being moved out of its `Gc<_>`
+        self.update(|gc| unsafe { gc.mark() })
+    }
+
+    unsafe fn inc_root_count(&self) {
+        // Keeps the value from being swapped out until it's counted
+        let mut held = self.held();
+        unsafe { self.view().inc_root_count() };
+        *held += 1;
+    }
+
+    unsafe fn dec_root_count(&self) {
+        let mut held = self.held();
+        unsafe { self.view().dec_root_count() };
+        *held -= 1;
+    }
+
+    unsafe fn set_not_root(&self) {
+        self.update(|gc| unsafe { gc.set_not_root() })
+    }
+}
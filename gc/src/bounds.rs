@@ -0,0 +1,92 @@
+//! Compile-time checks of the thread safety the crate's `unsafe impl`s promise, so a change which
+//! breaks them fails to build instead of silently becoming unsound
+//!
+//! The examples below check the other side, that misuse still fails to compile. Handles into the
+//! shared heap can go to other threads:
+//!
+//! ```
+//! use gc::{Gc, WeakGc};
+//!
+//! let gc = Gc::new(1u32);
+//! let weak: WeakGc<u32> = Gc::downgrade(&gc);
+//! std::thread::spawn(move || assert_eq!(*gc + *weak.upgrade().unwrap(), 2))
+//!     .join()
+//!     .unwrap();
+//! ```
+//!
+//! But a `LocalGc<_>` can't be sent to another thread:
+//!
+//! ```compile_fail,E0277
+//! let local = gc::LocalGc::new(1u32);
+//! std::thread::spawn(move || *local);
+//! ```
+//!
+//! Nor shared with one:
+//!
+//! ```compile_fail,E0277
+//! fn assert_sync<T: Sync>(_: &T) {}
+//! assert_sync(&gc::LocalGc::new(1u32));
+//! ```
+//!
+//! Nor smuggled into the shared heap, which only holds `GcAble` values:
+//!
+//! ```compile_fail,E0277
+//! gc::Gc::new(gc::LocalGc::new(1u32));
+//! ```
+//!
+//! The shared heap can't hold anything else which isn't `GcAble` either:
+//!
+//! ```compile_fail,E0277
+//! gc::Gc::new(std::rc::Rc::new(1u32));
+//! ```
+//!
+//! And a type which isn't `Send + Sync` can't be made `GcAble` by hand:
+//!
+//! ```compile_fail,E0277
+//! struct Counter(std::cell::Cell<u32>);
+//!
+//! unsafe impl gc::GcAble for Counter {
+//!     unsafe fn mark(&self) {}
+//!     unsafe fn inc_root_count(&self) {}
+//!     unsafe fn dec_root_count(&self) {}
+//!     unsafe fn set_not_root(&self) {}
+//! }
+//! ```
+
+use crate::{Gc, GcAble, GcCell, GcMut, GcRef, LocalGc, WeakGc};
+
+/// Only implemented for a type once, unless `T: Send`, in which case naming the trait's item for it
+/// is ambiguous and fails to compile
+trait AmbiguousIfSend<A> {
+    fn check() {}
+}
+impl<T: ?Sized> AmbiguousIfSend<()> for T {}
+impl<T: ?Sized + Send> AmbiguousIfSend<u8> for T {}
+
+/// Same as `AmbiguousIfSend`, but for `Sync`
+trait AmbiguousIfSync<A> {
+    fn check() {}
+}
+impl<T: ?Sized> AmbiguousIfSync<()> for T {}
+impl<T: ?Sized + Sync> AmbiguousIfSync<u8> for T {}
+
+const fn assert_send_sync<T: ?Sized + Send + Sync>() {}
+
+/// Every handle into a shared heap can be sent to and shared with other threads, which `GcAble`'s
+/// `Send + Sync` bound makes sound
+const _: () = {
+    assert_send_sync::<Gc<u32>>();
+    assert_send_sync::<Gc<[Gc<u32>]>>();
+    assert_send_sync::<Gc<dyn GcAble>>();
+    assert_send_sync::<WeakGc<u32>>();
+    assert_send_sync::<GcRef<Gc<u32>, u32>>();
+    assert_send_sync::<GcCell<Gc<u32>>>();
+    assert_send_sync::<GcMut<u32>>();
+};
+
+/// A `LocalGc<_>` points into its thread's heap, which other threads must never touch
+#[allow(dead_code)]
+fn assert_local_gc_not_send_or_sync() {
+    let _ = <LocalGc<u32> as AmbiguousIfSend<_>>::check;
+    let _ = <LocalGc<u32> as AmbiguousIfSync<_>>::check;
+}
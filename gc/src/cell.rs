@@ -0,0 +1,474 @@
+use alloc::vec::Vec;
+use core::{
+    cell::RefCell,
+    fmt::{Debug, Display},
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{
+    sys::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError},
+    tracer, Gc, GcAble,
+};
+
+thread_local! {
+    /// The address of every `GcCell` borrowed on this thread, and whether the borrow is mutable
+    static BORROWED: RefCell<Vec<(usize, bool)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Records a borrow of a `GcCell` on the current thread until dropped
+///
+/// Another thread's borrows only make a borrow wait, like any `RwLock`, but waiting on the current
+/// thread's own borrows would never end, so those are errors instead
+struct BorrowFlag {
+    addr: usize,
+    mutable: bool,
+}
+
+impl BorrowFlag {
+    /// Fails if the borrow would conflict with one held by the current thread
+    fn new<T: GcAble>(cell: &GcCell<T>, mutable: bool) -> Option<Self> {
+        let addr = cell as *const GcCell<T> as usize;
+        BORROWED.with_borrow_mut(|borrowed| {
+            let conflicts = borrowed
+                .iter()
+                .any(|&(other, other_mutable)| other == addr && (mutable || other_mutable));
+            if conflicts {
+                return None;
+            }
+            borrowed.push((addr, mutable));
+            Some(Self { addr, mutable })
+        })
+    }
+}
+
+impl Drop for BorrowFlag {
+    fn drop(&mut self) {
+        // The thread local may already be gone if this is dropped during thread teardown
+        let _ = BORROWED.try_with(|borrowed| {
+            let mut borrowed = borrowed.borrow_mut();
+            if let Some(i) = borrowed
+                .iter()
+                .rposition(|&b| b == (self.addr, self.mutable))
+            {
+                borrowed.swap_remove(i);
+            }
+        });
+    }
+}
+
+/// The error returned by [`GcCell::try_borrow`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorrowError;
+
+impl Display for BorrowError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "already mutably borrowed")
+    }
+}
+
+impl core::error::Error for BorrowError {}
+
+/// The error returned by [`GcMut::try_borrow_mut`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorrowMutError;
+
+impl Display for BorrowMutError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "already borrowed")
+    }
+}
+
+impl core::error::Error for BorrowMutError {}
+
+/// Like `RwLock::try_read` or `RwLock::try_write`, but `None` only if the lock would block
+fn try_lock<G>(res: Result<G, TryLockError<G>>) -> Option<G> {
+    match res {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::WouldBlock) => None,
+        Err(TryLockError::Poisoned(err)) => panic!("{err}"),
+    }
+}
+
+/// A mutable memory location which can be stored inside a `GcAble` value
+///
+/// Replacing the contents keeps the root counts of the `Gc<_>`s going in and coming out correct,
+/// and tells the collector about the new references
+///
+/// Borrows are checked at runtime like a `RefCell`'s, except that the contents may be shared
+/// between threads like an `RwLock`'s. A borrow which conflicts with one held by another thread
+/// waits for it to end, while one which conflicts with a borrow held by the current thread panics,
+/// since it would wait forever. The `try_` methods fail instead of waiting or panicking.
+///
+/// This takes the place of `RefCell` in values stored in the Gc, which can't hold a `RefCell`
+/// since every [`GcAble`] type has to be `Sync`:
+///
+/// ```compile_fail
+/// use std::cell::RefCell;
+/// use gc::Gc;
+///
+/// #[derive(gc::GcAble)]
+/// struct Node {
+///     next: RefCell<Option<Gc<Node>>>,
+/// }
+/// ```
+///
+/// `borrow`, `get`, `set` and `replace` work the same. The contents can only be replaced as a
+/// whole, so that the Gc sees every `Gc<_>` going in, so a `borrow_mut` becomes a `replace`, or a
+/// [`GcMut`] when the value is mutated in place:
+///
+/// ```
+/// use gc::{Gc, GcCell};
+///
+/// #[derive(gc::GcAble)]
+/// struct Node {
+///     id: u32,
+///     next: GcCell<Option<Gc<Node>>>,
+/// }
+///
+/// let a = Gc::new(Node { id: 1, next: GcCell::new(None) });
+/// let b = Gc::new(Node { id: 2, next: GcCell::new(Some(a.clone())) });
+/// // A cycle, which the collector frees once both are dropped
+/// a.next.set(Some(b.clone()));
+///
+/// gc::force_collect();
+/// assert_eq!(a.next.borrow().as_ref().unwrap().id, 2);
+/// assert_eq!(b.next.replace(None).unwrap().id, 1);
+/// ```
+///
+/// A value can't hold a `Mutex` or an `RwLock` instead, since `Gc<_>`s can be moved in and out
+/// through their guards without the Gc seeing it, which would leave a `Gc<_>` moved out unrooted
+/// and one moved in rooted forever:
+///
+/// ```compile_fail
+/// use std::sync::Mutex;
+/// use gc::Gc;
+///
+/// let shared = Gc::new(Mutex::new(Gc::new(1u32)));
+/// ```
+pub struct GcCell<T: GcAble> {
+    value: RwLock<T>,
+    /// Set once this is stored in a `Gc<_>`, after which the `Gc<_>`s in `value` aren't roots
+    managed: AtomicBool,
+}
+
+impl<T: GcAble> GcCell<T> {
+    pub fn new(val: T) -> Self {
+        Self {
+            value: RwLock::new(val),
+            managed: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns a clone of the contents
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        self.borrow().clone()
+    }
+
+    /// Immutably borrows the contents, blocking while they're being replaced or mutably borrowed
+    /// by another thread
+    ///
+    /// # Panics
+    /// If the current thread mutably borrows the contents, see [`GcCell::try_borrow`]
+    pub fn borrow(&self) -> GcCellRef<'_, T> {
+        let Some(flag) = BorrowFlag::new(self, false) else {
+            panic!("{BorrowError}");
+        };
+        GcCellRef {
+            value: self.value.read().unwrap(),
+            _flag: flag,
+        }
+    }
+
+    /// Immutably borrows the contents, or fails if they're mutably borrowed anywhere right now
+    pub fn try_borrow(&self) -> Result<GcCellRef<'_, T>, BorrowError> {
+        let flag = BorrowFlag::new(self, false).ok_or(BorrowError)?;
+        let value = try_lock(self.value.try_read()).ok_or(BorrowError)?;
+        Ok(GcCellRef { value, _flag: flag })
+    }
+
+    /// Replaces the contents, dropping the old value
+    pub fn set(&self, val: T) {
+        drop(self.replace(val))
+    }
+
+    /// Replaces the contents, returning the old value
+    ///
+    /// The `Gc<_>`s in the returned value are roots again, so it may be kept around freely
+    ///
+    /// # Panics
+    /// If the current thread borrows the contents
+    pub fn replace(&self, val: T) -> T {
+        let Some(_flag) = BorrowFlag::new(self, true) else {
+            panic!("{BorrowMutError}");
+        };
+        if !self.managed.load(Ordering::Acquire) {
+            return core::mem::replace(&mut *self.value.write().unwrap(), val);
+        }
+
+        // Before `val`'s `Gc<_>`s stop being roots, the collector needs to know this object points
+        // to them, since this object may already have been scanned or may be old
+        for child in unsafe { tracer::record_children(&val) } {
+            let header = &unsafe { child.as_ref() }.header;
+            header.context.lock().shade(child);
+        }
+        // The collector can't trace this while the values are changing hands
+        let mut value = self.value.write().unwrap();
+        let old = core::mem::replace(&mut *value, val);
+        unsafe {
+            value.set_not_root();
+            tracer::root_children(&old);
+        }
+        old
+    }
+}
+
+impl<T: GcAble + Default> Default for GcCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: GcAble + Debug> Debug for GcCell<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut d = f.debug_struct("GcCell");
+        match self.try_borrow() {
+            Ok(value) => d.field("value", &*value),
+            Err(_) => d.field("value", &format_args!("<borrowed>")),
+        };
+        d.finish()
+    }
+}
+
+/// A shared borrow of a `GcCell<_>`'s contents, see [`GcCell::borrow`]
+pub struct GcCellRef<'a, T: GcAble> {
+    value: RwLockReadGuard<'a, T>,
+    _flag: BorrowFlag,
+}
+
+impl<T: GcAble> Deref for GcCellRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T: GcAble + Debug> Debug for GcCellRef<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+unsafe impl<T: GcAble> GcAble for GcCell<T> {
+    unsafe fn mark(&self) {
+        // This is being moved out of its `Gc<_>` along with its containing value
+        if tracer::rooting() {
+            self.managed.store(false, Ordering::Release);
+        }
+        unsafe { self.value.read().unwrap().mark() }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { self.value.read().unwrap().inc_root_count() }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self.value.read().unwrap().dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        self.managed.store(true, Ordering::Release);
+        unsafe { self.value.read().unwrap().set_not_root() }
+    }
+}
+
+/// A shared mutable value in the global context, which is a `Gc<GcCell<T>>` that can also be
+/// borrowed mutably
+///
+/// Cloning this gives another handle to the same value, so a change made through one handle is
+/// seen through every other
+///
+/// ```
+/// use gc::{Gc, GcAble, GcMut};
+///
+/// #[derive(Default)]
+/// struct List(Vec<Gc<u32>>);
+/// # unsafe impl GcAble for List {
+/// #     unsafe fn mark(&self) {
+/// #         self.0.iter().for_each(|gc| unsafe { gc.mark() })
+/// #     }
+/// #     unsafe fn inc_root_count(&self) {
+/// #         self.0.iter().for_each(|gc| unsafe { gc.inc_root_count() })
+/// #     }
+/// #     unsafe fn dec_root_count(&self) {
+/// #         self.0.iter().for_each(|gc| unsafe { gc.dec_root_count() })
+/// #     }
+/// #     unsafe fn set_not_root(&self) {
+/// #         self.0.iter().for_each(|gc| unsafe { gc.set_not_root() })
+/// #     }
+/// # }
+///
+/// let list: GcMut<List> = GcMut::default();
+/// let other = list.clone();
+/// list.borrow_mut().0.push(Gc::new(1));
+/// other.borrow_mut().0.push(Gc::new(2));
+///
+/// gc::force_collect();
+/// let values: Vec<u32> = list.borrow().0.iter().map(|gc| **gc).collect();
+/// assert_eq!(values, [1, 2]);
+/// assert_eq!(other.borrow().0.len(), 2);
+/// assert_eq!(gc::stats().live_allocations, 3);
+///
+/// drop((list, other));
+/// gc::force_collect();
+/// assert_eq!(gc::stats().live_allocations, 0);
+/// ```
+pub struct GcMut<T: GcAble>(Gc<GcCell<T>>);
+
+impl<T: GcAble> GcMut<T> {
+    /// Moves `val` into the global context
+    pub fn new(val: T) -> Self {
+        Self(Gc::new(GcCell::new(val)))
+    }
+
+    /// Immutably borrows the value, see [`GcCell::borrow`]
+    pub fn borrow(&self) -> GcCellRef<'_, T> {
+        self.0.borrow()
+    }
+
+    /// Mutably borrows the value, blocking while it's borrowed by another thread
+    ///
+    /// `Gc<_>`s may be freely moved into and out of the value through the returned guard. Collection
+    /// is skipped in the value's context for as long as it's alive.
+    ///
+    /// # Panics
+    /// If the current thread borrows the value, see [`GcMut::try_borrow_mut`]
+    pub fn borrow_mut(&self) -> GcMutRef<'_, T> {
+        let gcb = self.0.gcb();
+        let Some(flag) = BorrowFlag::new(&gcb.val, true) else {
+            panic!("{BorrowMutError}");
+        };
+        // Taking the lock waits out any collection which may be reading the value right now
+        gcb.header.context.lock().exclusive_borrows += 1;
+        let value = gcb.val.value.write().unwrap();
+        unsafe { self.borrowed_mut(value, flag) }
+    }
+
+    /// Mutably borrows the value, or fails if it's borrowed anywhere right now
+    pub fn try_borrow_mut(&self) -> Result<GcMutRef<'_, T>, BorrowMutError> {
+        let gcb = self.0.gcb();
+        let flag = BorrowFlag::new(&gcb.val, true).ok_or(BorrowMutError)?;
+        let mut gc = gcb.header.context.lock();
+        let value = try_lock(gcb.val.value.try_write()).ok_or(BorrowMutError)?;
+        gc.exclusive_borrows += 1;
+        drop(gc);
+        Ok(unsafe { self.borrowed_mut(value, flag) })
+    }
+
+    /// # Safety
+    /// `exclusive_borrows` must have been incremented for the returned guard
+    unsafe fn borrowed_mut<'a>(
+        &'a self,
+        value: RwLockWriteGuard<'a, T>,
+        flag: BorrowFlag,
+    ) -> GcMutRef<'a, T> {
+        // Anything moved out of the value has to be a root by the time it is
+        unsafe { tracer::root_children(&*value) };
+        GcMutRef {
+            gc: &self.0,
+            value,
+            _flag: flag,
+        }
+    }
+
+    /// The `Gc<_>` this wraps
+    pub fn as_gc(this: &Self) -> &Gc<GcCell<T>> {
+        &this.0
+    }
+}
+
+impl<T: GcAble> From<Gc<GcCell<T>>> for GcMut<T> {
+    fn from(gc: Gc<GcCell<T>>) -> Self {
+        Self(gc)
+    }
+}
+
+impl<T: GcAble> Deref for GcMut<T> {
+    type Target = GcCell<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: GcAble> Clone for GcMut<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: GcAble + Default> Default for GcMut<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: GcAble + Debug> Debug for GcMut<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+unsafe impl<T: GcAble> GcAble for GcMut<T> {
+    unsafe fn mark(&self) {
+        unsafe { self.0.mark() }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { self.0.inc_root_count() }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self.0.dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { self.0.set_not_root() }
+    }
+}
+
+/// A unique mutable borrow of a `GcMut<_>`'s value, see [`GcMut::borrow_mut`]
+pub struct GcMutRef<'a, T: GcAble> {
+    gc: &'a Gc<GcCell<T>>,
+    value: RwLockWriteGuard<'a, T>,
+    _flag: BorrowFlag,
+}
+
+impl<T: GcAble> Deref for GcMutRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T: GcAble> DerefMut for GcMutRef<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<T: GcAble> Drop for GcMutRef<'_, T> {
+    fn drop(&mut self) {
+        let gcb = self.gc.gcb();
+        let mut gc = gcb.header.context.lock();
+        // Every `Gc<_>` in the value is now reachable through it
+        unsafe { self.value.set_not_root() };
+        gc.write_barrier(self.gc.erased());
+        gc.exclusive_borrows -= 1;
+    }
+}
@@ -0,0 +1,450 @@
+use alloc::{string::String, sync::Arc};
+use core::{
+    alloc::GlobalAlloc,
+    fmt::{Debug, Display},
+    time::Duration,
+};
+
+use crate::CollectorDriver;
+
+/// The fewest bytes `GcConfig::growth_factor` measures growth from, so that a small heap isn't
+/// collected after every allocation
+pub(crate) const MIN_GROWTH_BASE: usize = 64 * 1024;
+
+/// Settings for a `GcContext`'s collector, see [`crate::GcContext::with_config`]
+#[derive(Clone)]
+pub struct GcConfig {
+    pub(crate) alloc_watermark: usize,
+    pub(crate) max_interval: Duration,
+    pub(crate) promotion_threshold: u32,
+    pub(crate) major_interval: u64,
+    pub(crate) step_budget: Option<usize>,
+    pub(crate) max_sweep_batch: Option<usize>,
+    pub(crate) growth_factor: Option<f64>,
+    pub(crate) allocator: Option<Arc<dyn GlobalAlloc + Send + Sync>>,
+    pub(crate) free_list_capacity: usize,
+    pub(crate) capacity: usize,
+    pub(crate) verify_roots: bool,
+    pub(crate) deterministic: bool,
+    pub(crate) defer_finalizers: bool,
+    pub(crate) thread_name: String,
+    pub(crate) low_priority: bool,
+    pub(crate) driver: Option<Arc<dyn CollectorDriver>>,
+}
+
+impl GcConfig {
+    /// The collector is woken once this many objects have been allocated since the last collection
+    pub fn alloc_watermark(mut self, allocs: usize) -> Self {
+        self.alloc_watermark = allocs;
+        self
+    }
+
+    /// The longest the collector will sleep between collections, regardless of allocations
+    pub fn max_interval(mut self, interval: Duration) -> Self {
+        self.max_interval = interval;
+        self
+    }
+
+    /// Objects are moved to the old generation once they've survived this many collections
+    pub fn promotion_threshold(mut self, collections: u32) -> Self {
+        self.promotion_threshold = collections;
+        self
+    }
+
+    /// Every `collections`th background collection is a major collection, and the rest are minor
+    ///
+    /// Minor collections only look at the young generation, treating every old object as alive.
+    /// This is `1` by default, meaning every collection is major.
+    ///
+    /// An old object which has a `Gc<_>` stored in it by anything other than a `GcRefMut` must be
+    /// passed to a write barrier, or a minor collection may free objects reachable only through it.
+    /// `force_collect` is always major.
+    pub fn major_interval(mut self, collections: u64) -> Self {
+        self.major_interval = collections;
+        self
+    }
+
+    /// Marks collections incrementally, scanning at most `step_budget` objects each time the
+    /// collector takes the lock
+    ///
+    /// This bounds the time the collector holds the lock while marking, though the final slice also
+    /// rescans the roots and sweeps.
+    pub fn incremental(mut self, step_budget: usize) -> Self {
+        self.step_budget = Some(step_budget);
+        self
+    }
+
+    /// Reclaims at most `objects` unreachable objects each time the collector takes the lock,
+    /// releasing it in between so other threads can allocate
+    ///
+    /// Every unreachable object is finalized, then dropped, then deallocated, and each of these
+    /// counts towards `objects`. No new collection starts until the last one's objects are all
+    /// reclaimed. Explicit collections like [`crate::GcContext::force_collect`] always reclaim
+    /// everything before returning. Unset by default, which reclaims everything at once.
+    pub fn max_sweep_batch(mut self, objects: usize) -> Self {
+        self.max_sweep_batch = Some(objects);
+        self
+    }
+
+    /// The collector is also woken once the heap's size in bytes is `factor` times what it was
+    /// right after the last collection
+    ///
+    /// This lets collections happen less often while most of the heap survives them, and more
+    /// often while most of it doesn't. Growth is measured from at least 64 KiB. Unset by default.
+    pub fn growth_factor(mut self, factor: f64) -> Self {
+        self.growth_factor = Some(factor);
+        self
+    }
+
+    /// Allocates and frees every object with `allocator` instead of the global allocator
+    ///
+    /// ```
+    /// use std::{
+    ///     alloc::{GlobalAlloc, Layout, System},
+    ///     sync::atomic::{AtomicUsize, Ordering},
+    /// };
+    ///
+    /// use gc::{GcConfig, GcContext};
+    ///
+    /// static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+    /// static DEALLOCS: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// struct Counting;
+    ///
+    /// unsafe impl GlobalAlloc for Counting {
+    ///     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    ///         ALLOCS.fetch_add(1, Ordering::SeqCst);
+    ///         unsafe { System.alloc(layout) }
+    ///     }
+    ///
+    ///     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    ///         DEALLOCS.fetch_add(1, Ordering::SeqCst);
+    ///         unsafe { System.dealloc(ptr, layout) }
+    ///     }
+    /// }
+    ///
+    /// let config = GcConfig::default().allocator(Counting);
+    /// let ctx = GcContext::with_config(config);
+    /// let mut objects: Vec<_> = (0..10u32).map(|i| ctx.alloc(i)).collect();
+    /// assert_eq!(ALLOCS.load(Ordering::SeqCst), 10);
+    ///
+    /// objects.truncate(4);
+    /// ctx.force_collect();
+    /// assert_eq!(DEALLOCS.load(Ordering::SeqCst), 6);
+    ///
+    /// drop(objects);
+    /// ctx.force_collect();
+    /// assert_eq!(DEALLOCS.load(Ordering::SeqCst), 10);
+    /// assert_eq!(ALLOCS.load(Ordering::SeqCst), 10);
+    /// ```
+    pub fn allocator(mut self, allocator: impl GlobalAlloc + Send + Sync + 'static) -> Self {
+        self.allocator = Some(Arc::new(allocator));
+        self
+    }
+
+    /// Keeps up to `boxes` freed objects around, to be reused by new objects of the same size and
+    /// alignment instead of calling the allocator
+    ///
+    /// This saves an allocation and deallocation for every object when many short-lived objects of
+    /// the same few types are allocated, at the cost of the memory in the list not being given back
+    /// until the context is dropped or [`crate::GcContext::trim`] is called. `0` by default, which
+    /// disables the list.
+    pub fn free_list_capacity(mut self, boxes: usize) -> Self {
+        self.free_list_capacity = boxes;
+        self
+    }
+
+    /// Sets aside room for `objects` live objects in the context's bookkeeping up front, so it
+    /// doesn't have to grow while the heap does
+    ///
+    /// Only the lists of objects in each generation and the list of objects left to trace are
+    /// presized, which take a few dozen bytes per object, while each object's memory is still
+    /// allocated as it's created. Without `std`, where the lists are trees, only the last one is
+    /// presized. `0` by default.
+    pub fn capacity(mut self, objects: usize) -> Self {
+        self.capacity = objects;
+        self
+    }
+
+    /// Checks every new object's `Gc<_>`s right after it's allocated, panicking with the type's name
+    /// if any of them still counts as a root
+    ///
+    /// This catches a hand-written [`crate::GcAble::set_not_root`] which misses a field, which
+    /// would otherwise keep that field's object alive forever. Only checked in debug builds, and
+    /// `false` by default.
+    pub fn verify_roots(mut self, verify: bool) -> Self {
+        self.verify_roots = verify;
+        self
+    }
+
+    /// Makes collections reproducible from run to run, for tests and debugging
+    ///
+    /// The collector only collects when explicitly asked to, such as by
+    /// [`crate::GcContext::force_collect`] or [`crate::GcContext::wait_for_collection`], never
+    /// because of allocations or `max_interval`. Objects are visited in the order they were
+    /// allocated in, so finalizers and `Drop`s run in that order, and [`crate::dump_dot`] names
+    /// objects by that order instead of by address.
+    ///
+    /// Every collection runs on the thread which asked for it, so no collector thread is started,
+    /// and a [`GcConfig::driver`] is never asked to continue a collection partway done. `false` by
+    /// default.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Runs finalizers without holding the context's lock, after the collection which found their
+    /// objects unreachable, instead of while it sweeps
+    ///
+    /// The collection queues the finalizers, keeping each queued object alive along with everything
+    /// reachable from it. The queue is run by the collector's thread or [`crate::Collector`] right
+    /// after it releases the lock, or by [`crate::GcContext::flush_finalizers`]. Since the lock
+    /// isn't held, a queued finalizer may allocate and otherwise call into the Gc. Each object is
+    /// freed by a later collection which finds it unreachable, without being finalized again.
+    /// `false` by default.
+    pub fn defer_finalizers(mut self, defer: bool) -> Self {
+        self.defer_finalizers = defer;
+        self
+    }
+
+    /// The name of the collector's thread, as shown by debuggers and profilers
+    ///
+    /// Only used by [`crate::ThreadDriver`]. `"wasm_gc-collector"` by default
+    pub fn thread_name(mut self, name: impl Into<String>) -> Self {
+        self.thread_name = name.into();
+        self
+    }
+
+    /// Runs the collector's thread at a lower priority than the threads using the Gc, so collection
+    /// doesn't take time away from them while the machine is busy
+    ///
+    /// Only used by [`crate::ThreadDriver`], and only supported on Linux, where the thread's nice
+    /// value is raised to 10. `false` by default.
+    pub fn low_priority(mut self, low_priority: bool) -> Self {
+        self.low_priority = low_priority;
+        self
+    }
+
+    /// Runs the context's collections with `driver` instead of the default [`crate::ThreadDriver`]
+    ///
+    /// Without the `background-thread` feature there's no default driver, so a context is only
+    /// collected when asked to unless this is set.
+    pub fn driver(mut self, driver: impl CollectorDriver) -> Self {
+        self.driver = Some(Arc::new(driver));
+        self
+    }
+}
+
+impl Debug for GcConfig {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("GcConfig")
+            .field("alloc_watermark", &self.alloc_watermark)
+            .field("max_interval", &self.max_interval)
+            .field("promotion_threshold", &self.promotion_threshold)
+            .field("major_interval", &self.major_interval)
+            .field("step_budget", &self.step_budget)
+            .field("max_sweep_batch", &self.max_sweep_batch)
+            .field("growth_factor", &self.growth_factor)
+            .field("custom_allocator", &self.allocator.is_some())
+            .field("free_list_capacity", &self.free_list_capacity)
+            .field("capacity", &self.capacity)
+            .field("verify_roots", &self.verify_roots)
+            .field("deterministic", &self.deterministic)
+            .field("defer_finalizers", &self.defer_finalizers)
+            .field("thread_name", &self.thread_name)
+            .field("low_priority", &self.low_priority)
+            .field("custom_driver", &self.driver.is_some())
+            .finish()
+    }
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        Self {
+            alloc_watermark: 1024,
+            max_interval: Duration::from_millis(100),
+            promotion_threshold: 2,
+            major_interval: 1,
+            step_budget: None,
+            max_sweep_batch: None,
+            growth_factor: None,
+            allocator: None,
+            free_list_capacity: 0,
+            capacity: 0,
+            verify_roots: false,
+            deterministic: false,
+            defer_finalizers: false,
+            thread_name: String::from("wasm_gc-collector"),
+            low_priority: false,
+            driver: None,
+        }
+    }
+}
+
+/// Builds a [`GcConfig`], checking that its settings make sense together
+///
+/// Every setting is the same as the [`GcConfig`] method of the same name
+#[derive(Debug, Clone, Default)]
+pub struct GcBuilder {
+    config: GcConfig,
+}
+
+impl GcBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn alloc_watermark(mut self, allocs: usize) -> Self {
+        self.config = self.config.alloc_watermark(allocs);
+        self
+    }
+
+    pub fn max_interval(mut self, interval: Duration) -> Self {
+        self.config = self.config.max_interval(interval);
+        self
+    }
+
+    pub fn promotion_threshold(mut self, collections: u32) -> Self {
+        self.config = self.config.promotion_threshold(collections);
+        self
+    }
+
+    pub fn major_interval(mut self, collections: u64) -> Self {
+        self.config = self.config.major_interval(collections);
+        self
+    }
+
+    pub fn incremental(mut self, step_budget: usize) -> Self {
+        self.config = self.config.incremental(step_budget);
+        self
+    }
+
+    pub fn max_sweep_batch(mut self, objects: usize) -> Self {
+        self.config = self.config.max_sweep_batch(objects);
+        self
+    }
+
+    pub fn growth_factor(mut self, factor: f64) -> Self {
+        self.config = self.config.growth_factor(factor);
+        self
+    }
+
+    pub fn allocator(mut self, allocator: impl GlobalAlloc + Send + Sync + 'static) -> Self {
+        self.config = self.config.allocator(allocator);
+        self
+    }
+
+    pub fn free_list_capacity(mut self, boxes: usize) -> Self {
+        self.config = self.config.free_list_capacity(boxes);
+        self
+    }
+
+    pub fn capacity(mut self, objects: usize) -> Self {
+        self.config = self.config.capacity(objects);
+        self
+    }
+
+    pub fn verify_roots(mut self, verify: bool) -> Self {
+        self.config = self.config.verify_roots(verify);
+        self
+    }
+
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.config = self.config.deterministic(deterministic);
+        self
+    }
+
+    pub fn defer_finalizers(mut self, defer: bool) -> Self {
+        self.config = self.config.defer_finalizers(defer);
+        self
+    }
+
+    pub fn thread_name(mut self, name: impl Into<String>) -> Self {
+        self.config = self.config.thread_name(name);
+        self
+    }
+
+    pub fn low_priority(mut self, low_priority: bool) -> Self {
+        self.config = self.config.low_priority(low_priority);
+        self
+    }
+
+    pub fn driver(mut self, driver: impl CollectorDriver) -> Self {
+        self.config = self.config.driver(driver);
+        self
+    }
+
+    /// Returns the config, or the first setting which doesn't make sense
+    pub fn build(self) -> Result<GcConfig, GcConfigError> {
+        let config = self.config;
+        if config.max_interval.is_zero() {
+            return Err(GcConfigError::ZeroInterval);
+        }
+        if config.alloc_watermark == 0 {
+            return Err(GcConfigError::ZeroWatermark);
+        }
+        if config.major_interval == 0 {
+            return Err(GcConfigError::ZeroMajorInterval);
+        }
+        if config.step_budget == Some(0) {
+            return Err(GcConfigError::ZeroStepBudget);
+        }
+        if config.max_sweep_batch == Some(0) {
+            return Err(GcConfigError::ZeroSweepBatch);
+        }
+        if let Some(factor) = config.growth_factor {
+            if factor.is_nan() || factor <= 1.0 {
+                return Err(GcConfigError::GrowthFactor(factor));
+            }
+        }
+        if config.thread_name.contains('\0') {
+            return Err(GcConfigError::ThreadName);
+        }
+        Ok(config)
+    }
+}
+
+/// Why a [`GcBuilder`] or [`crate::init_gc_with`] was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum GcConfigError {
+    /// `max_interval` was zero, which would keep the collector from ever sleeping
+    ZeroInterval,
+    /// `alloc_watermark` was zero, which would request a collection on every allocation
+    ZeroWatermark,
+    /// `major_interval` was zero, which would make every collection minor
+    ZeroMajorInterval,
+    /// `incremental` was given a step budget of zero, which would never finish marking
+    ZeroStepBudget,
+    /// `max_sweep_batch` was zero, which would never reclaim anything
+    ZeroSweepBatch,
+    /// `growth_factor` wasn't more than `1.0`, which would request a collection on every allocation
+    GrowthFactor(f64),
+    /// `thread_name` contained a null byte, which thread names can't
+    ThreadName,
+    /// The global context was already initialized, so its config can't be set anymore
+    AlreadyInitialized,
+}
+
+impl Display for GcConfigError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::ZeroInterval => write!(f, "the collector's max interval must not be zero"),
+            Self::ZeroWatermark => write!(f, "the allocation watermark must not be zero"),
+            Self::ZeroMajorInterval => write!(f, "the major collection interval must not be zero"),
+            Self::ZeroStepBudget => write!(f, "the incremental step budget must not be zero"),
+            Self::ZeroSweepBatch => write!(f, "the maximum sweep batch must not be zero"),
+            Self::GrowthFactor(factor) => {
+                write!(
+                    f,
+                    "the growth factor must be more than 1.0, but was {factor}"
+                )
+            }
+            Self::ThreadName => {
+                write!(f, "the collector's thread name must not contain null bytes")
+            }
+            Self::AlreadyInitialized => write!(f, "the global Gc was already initialized"),
+        }
+    }
+}
+
+impl core::error::Error for GcConfigError {}
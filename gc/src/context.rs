@@ -0,0 +1,895 @@
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    cell::RefCell,
+    fmt::Display,
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "background-thread")]
+use std::sync::Condvar;
+
+#[cfg(feature = "background-thread")]
+use crate::ThreadDriver;
+use crate::{
+    alloc_store::FreeList,
+    sys::{
+        panic::{self, AssertUnwindSafe},
+        sync::{Mutex, MutexGuard, OnceLock, PoisonError},
+    },
+    AllocSummary, Collector, CollectorDriver, Finalize, Gc, GcAble, GcAlloc, GcConfig, GcStats,
+    HeapSnapshot, LiveObject, RootSet, RuntimeMetrics, WeakGc,
+};
+
+/// An independent heap with its own collector
+///
+/// Every `Gc<_>` belongs to exactly one context, and a collection in one context never touches
+/// the objects of another. A context stays alive for as long as it has live objects, even if every
+/// `GcContext` pointing to it has been dropped.
+///
+/// The free functions and `Gc::new` all use the default context, see [`GcContext::global`]
+#[derive(Clone)]
+pub struct GcContext {
+    pub(crate) inner: Arc<ContextInner>,
+}
+
+/// Uniquely identifies a `GcContext` for the lifetime of the process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct ContextId(u64);
+
+impl ContextId {
+    fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+pub(crate) struct ContextInner {
+    pub id: ContextId,
+    gc: Mutex<GcAlloc>,
+    /// Notified when `GcAlloc::collection_requested` is set
+    #[cfg(feature = "background-thread")]
+    pub wake: Condvar,
+    /// Notified by the collector every time it's done collecting
+    #[cfg(feature = "background-thread")]
+    pub collected: Condvar,
+    /// Every object is allocated with this, or the global allocator if `None`
+    allocator: Option<Arc<dyn GlobalAlloc + Send + Sync>>,
+    /// Freed boxes waiting to be reused, or `None` if `GcConfig::free_list_capacity` is zero
+    free_list: Option<Mutex<FreeList>>,
+    /// Runs the collections, or nothing if `None`
+    driver: Option<Arc<dyn CollectorDriver>>,
+    /// Set by [`GcContext::shutdown`], after which nothing can be allocated
+    shut_down: AtomicBool,
+    /// The number of times [`ContextInner::force_collect`] was called
+    force_collects: AtomicU64,
+}
+
+impl ContextInner {
+    /// Locks the context, ignoring poisoning
+    ///
+    /// The collector keeps `GcAlloc` consistent when user code panics during a collection, see
+    /// [`crate::force_collect`]
+    ///
+    /// Panics if this thread already holds the lock, which happens when user code run by the
+    /// collector, such as a finalizer, calls back into the Gc. Waiting would deadlock instead.
+    pub fn lock(&self) -> GcLock<'_> {
+        let reentrant = HELD
+            .try_with(|held| held.borrow().contains(&self.id))
+            .unwrap_or(false);
+        if reentrant {
+            panic!(
+                "Gc operation attempted from within a Gc callback: this thread is already \
+                 collecting or inspecting the same context, so it must not allocate, collect or \
+                 otherwise lock the context from a finalizer, `Drop` or `GcAble` method"
+            );
+        }
+        let guard = self.gc.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = HELD.try_with(|held| held.borrow_mut().push(self.id));
+        GcLock {
+            guard,
+            _held: Held(self.id),
+        }
+    }
+
+    /// Tells the collector a collection was requested
+    pub fn wake_collector(&self) {
+        #[cfg(feature = "background-thread")]
+        self.wake.notify_one();
+        if let Some(driver) = &self.driver {
+            driver.wake();
+        }
+    }
+
+    /// Runs a full collection, unless one was started after this was called
+    ///
+    /// Threads which call this at the same time all wait for the lock, after which the first one
+    /// collects for all of them instead of each running a full collection in turn.
+    pub fn force_collect(&self) {
+        let request = self.force_collects.fetch_add(1, Ordering::AcqRel) + 1;
+        let mut gc = self.lock();
+        if gc.force_collected >= request {
+            return;
+        }
+        // Every request made so far was made before this collection starts
+        let requests = self.force_collects.load(Ordering::Acquire);
+        if gc.mark_sweep() {
+            gc.force_collected = requests;
+        }
+    }
+
+    /// Runs every queued finalizer without holding the lock, returning how many ran, see
+    /// [`GcConfig::defer_finalizers`]
+    ///
+    /// If a finalizer panics, the rest still run before the first panic is resumed
+    pub fn flush_finalizers(&self) -> usize {
+        let queue = core::mem::take(&mut self.lock().finalize_queue);
+        let mut panicked = None;
+        for nn in &queue {
+            let header = &unsafe { nn.as_ref() }.header;
+            let finalizer = header.finalizer.unwrap();
+            let res = panic::catch_unwind(AssertUnwindSafe(|| unsafe { finalizer(*nn) }));
+            panicked = panicked.or(res.err());
+            // Drops the queue's root, after which the object is collected like any other
+            header.root_count.fetch_sub(1, Ordering::SeqCst);
+        }
+        if let Some(payload) = panicked {
+            panic::resume_unwind(payload)
+        }
+        queue.len()
+    }
+
+    pub fn is_shut_down(&self) -> bool {
+        self.shut_down.load(Ordering::Acquire)
+    }
+
+    /// # Safety
+    /// See [`GlobalAlloc::alloc`]
+    ///
+    /// Panics if the context was shut down
+    pub unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if self.is_shut_down() {
+            panic!("tried to allocate in a `GcContext` which was shut down, see `Gc::try_new`");
+        }
+        if let Some(free_list) = &self.free_list {
+            let reused = free_list
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .pop(layout);
+            if let Some(ptr) = reused {
+                return ptr.as_ptr();
+            }
+        }
+        match &self.allocator {
+            Some(allocator) => unsafe { allocator.alloc(layout) },
+            None => unsafe { alloc::alloc::alloc(layout) },
+        }
+    }
+
+    /// Keeps the memory in the free list if there's room, and deallocates it otherwise
+    ///
+    /// # Safety
+    /// See [`GlobalAlloc::dealloc`], `ptr` must have been allocated by [`ContextInner::alloc`]
+    pub unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if let (Some(free_list), Some(nn)) = (&self.free_list, NonNull::new(ptr)) {
+            let kept = free_list
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(nn, layout);
+            if kept.is_ok() {
+                return;
+            }
+        }
+        unsafe { self.release(ptr, layout) }
+    }
+
+    /// Deallocates every box in the free list, returning the number of bytes released
+    pub fn trim(&self) -> usize {
+        let Some(free_list) = &self.free_list else {
+            return 0;
+        };
+        let mut free_list = free_list.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut released = 0;
+        for (ptr, layout) in free_list.drain() {
+            unsafe { self.release(ptr.as_ptr(), layout) };
+            released += layout.size();
+        }
+        released
+    }
+
+    /// Gives memory back to the allocator, skipping the free list
+    ///
+    /// # Safety
+    /// See [`ContextInner::dealloc`]
+    unsafe fn release(&self, ptr: *mut u8, layout: Layout) {
+        match &self.allocator {
+            Some(allocator) => unsafe { allocator.dealloc(ptr, layout) },
+            None => unsafe { alloc::alloc::dealloc(ptr, layout) },
+        }
+    }
+}
+
+impl Drop for ContextInner {
+    fn drop(&mut self) {
+        self.trim();
+    }
+}
+
+thread_local! {
+    /// The contexts whose lock is held by this thread
+    static HELD: RefCell<Vec<ContextId>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A context's locked `GcAlloc`, see [`ContextInner::lock`]
+pub(crate) struct GcLock<'a> {
+    guard: MutexGuard<'a, GcAlloc>,
+    _held: Held,
+}
+
+/// Records that this thread holds a context's lock until dropped
+struct Held(ContextId);
+
+impl Drop for Held {
+    fn drop(&mut self) {
+        let _ = HELD.try_with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some(i) = held.iter().rposition(|id| *id == self.0) {
+                held.swap_remove(i);
+            }
+        });
+    }
+}
+
+impl GcLock<'_> {
+    /// Like [`Condvar::wait_while`], the lock is only released while waiting
+    #[cfg(feature = "background-thread")]
+    pub fn wait_while(
+        self,
+        condvar: &Condvar,
+        condition: impl FnMut(&mut GcAlloc) -> bool,
+    ) -> Self {
+        let Self { guard, _held } = self;
+        let guard = condvar
+            .wait_while(guard, condition)
+            .unwrap_or_else(PoisonError::into_inner);
+        Self { guard, _held }
+    }
+
+    /// Like [`Condvar::wait_timeout_while`], the lock is only released while waiting
+    #[cfg(feature = "background-thread")]
+    pub fn wait_timeout_while(
+        self,
+        condvar: &Condvar,
+        timeout: core::time::Duration,
+        condition: impl FnMut(&mut GcAlloc) -> bool,
+    ) -> Self {
+        let Self { guard, _held } = self;
+        let (guard, _) = condvar
+            .wait_timeout_while(guard, timeout, condition)
+            .unwrap_or_else(PoisonError::into_inner);
+        Self { guard, _held }
+    }
+}
+
+impl Deref for GcLock<'_> {
+    type Target = GcAlloc;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl DerefMut for GcLock<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl GcContext {
+    /// Creates a new context with the default config and starts its collector
+    pub fn new() -> Self {
+        Self::with_config(GcConfig::default())
+    }
+
+    /// Creates a new context and starts its collector, see [`GcConfig::driver`]
+    ///
+    /// Without the `background-thread` feature there is no collector to start unless a driver is
+    /// given, so the context is only collected when asked to, such as by
+    /// [`GcContext::force_collect`]:
+    ///
+    /// ```
+    /// use std::{thread, time::Duration};
+    ///
+    /// use gc::{GcConfig, GcContext};
+    ///
+    /// let ctx = GcContext::with_config(GcConfig::default().alloc_watermark(10));
+    /// for i in 0..100 {
+    ///     ctx.alloc(i);
+    /// }
+    /// if cfg!(feature = "background-thread") {
+    ///     // Woken by the watermark
+    ///     while ctx.stats().live_allocations > 0 {
+    ///         thread::sleep(Duration::from_millis(1));
+    ///     }
+    /// } else {
+    ///     thread::sleep(Duration::from_millis(200));
+    ///     assert_eq!(ctx.stats().live_allocations, 100);
+    ///     ctx.force_collect();
+    ///     assert_eq!(ctx.stats().live_allocations, 0);
+    /// }
+    /// ```
+    pub fn with_config(config: GcConfig) -> Self {
+        let id = ContextId::next();
+        #[cfg(feature = "background-thread")]
+        let default_driver = || Some(Arc::new(ThreadDriver) as Arc<dyn CollectorDriver>);
+        #[cfg(not(feature = "background-thread"))]
+        let default_driver = || None;
+        let inner = Arc::new(ContextInner {
+            id,
+            allocator: config.allocator.clone(),
+            free_list: (config.free_list_capacity > 0)
+                .then(|| Mutex::new(FreeList::new(config.free_list_capacity))),
+            // A deterministic context only collects on the threads which ask it to
+            driver: match config.deterministic {
+                true => config.driver.clone(),
+                false => config.driver.clone().or_else(default_driver),
+            },
+            gc: Mutex::new(GcAlloc::new(id, config)),
+            #[cfg(feature = "background-thread")]
+            wake: Condvar::new(),
+            shut_down: AtomicBool::new(false),
+            force_collects: AtomicU64::new(0),
+            #[cfg(feature = "background-thread")]
+            collected: Condvar::new(),
+        });
+
+        if let Some(driver) = &inner.driver {
+            driver.start(Collector {
+                ctx: Arc::downgrade(&inner),
+            });
+        }
+
+        Self { inner }
+    }
+
+    /// The default context, which is initialized on first use
+    pub fn global() -> &'static GcContext {
+        crate::global_gc::context()
+    }
+
+    /// Moves `val` into this context's heap
+    ///
+    /// Panics if this context was shut down, see [`GcContext::try_alloc`]
+    pub fn alloc<T: GcAble>(&self, val: T) -> Gc<T> {
+        Gc::new_with_finalizer(&self.inner, val, None)
+    }
+
+    /// Like [`GcContext::alloc`], but fails instead of panicking if this context was shut down, or
+    /// instead of aborting if the allocator is out of memory
+    ///
+    /// When the allocator fails, everything unreachable is collected on the calling thread as if
+    /// by [`GcContext::request_urgent_collection`], then the allocation is tried once more.
+    /// `val` is dropped if that fails too.
+    pub fn try_alloc<T: GcAble>(&self, val: T) -> Result<Gc<T>, GcError> {
+        if self.inner.is_shut_down() {
+            return Err(GcError::ShutDown);
+        }
+        let gcbox = match Gc::<T>::try_alloc_uninit(&self.inner) {
+            Some(gcbox) => gcbox,
+            None => {
+                let mut gc = self.lock();
+                gc.urgent = true;
+                gc.collect();
+                drop(gc);
+                Gc::<T>::try_alloc_uninit(&self.inner).ok_or(GcError::OutOfMemory)?
+            }
+        };
+        Ok(unsafe { Gc::init_gcbox(&self.inner, gcbox, val, None, OnceLock::new()) })
+    }
+
+    /// Like [`GcContext::alloc`], but [`Finalize::finalize`] will be called on the value before it's collected
+    pub fn alloc_finalized<T: Finalize>(&self, val: T) -> Gc<T> {
+        Gc::new_with_finalizer(&self.inner, val, Some(crate::finalize_gcbox::<T>))
+    }
+
+    /// Copies `vals` into a single allocation in this context's heap
+    ///
+    /// Every element is traced along with the others, and they're all collected together
+    pub fn alloc_slice<T: GcAble + Clone>(&self, vals: &[T]) -> Gc<[T]> {
+        Gc::from_vec_in(&self.inner, vals.to_vec())
+    }
+
+    /// Stops all collection in this context until [`GcContext::enable`] is called as many times as
+    /// this has been
+    ///
+    /// Unlike [`GcContext::batch`], explicit collections like [`GcContext::force_collect`] are
+    /// skipped too, and instead run once collection is enabled again.
+    pub fn disable(&self) {
+        self.lock().disabled += 1;
+    }
+
+    /// Undoes a call to [`GcContext::disable`]
+    ///
+    /// Panics if collection isn't disabled
+    pub fn enable(&self) {
+        let mut gc = self.lock();
+        gc.disabled = gc
+            .disabled
+            .checked_sub(1)
+            .expect("`GcContext::enable` was called more times than `GcContext::disable`");
+        if gc.disabled == 0 && gc.collection_requested {
+            self.inner.wake_collector();
+        }
+    }
+
+    /// Runs `f` with collection in this context disabled, then enables it again, even if `f`
+    /// panics
+    ///
+    /// For building a large graph one object at a time, such as in a tight loop, without any
+    /// collection running partway through, see [`GcContext::disable`]. Every `Gc<_>` is a root
+    /// until it's stored in another object, so a partially built graph is never collected either
+    /// way, but the collector doesn't spend time tracing it over and over while it grows.
+    ///
+    /// ```
+    /// # use gc::{Gc, GcAble, GcContext};
+    /// #[derive(GcAble)]
+    /// struct Node {
+    ///     value: u32,
+    ///     next: Option<Gc<Node>>,
+    /// }
+    ///
+    /// let ctx = GcContext::new();
+    /// let list = ctx.build(|ctx| {
+    ///     let mut head = None;
+    ///     for value in 0..1000 {
+    ///         head = Some(ctx.alloc(Node { value, next: head }));
+    ///     }
+    ///     head.unwrap()
+    /// });
+    /// assert_eq!(list.value, 999);
+    /// ```
+    pub fn build<R>(&self, f: impl FnOnce(&GcContext) -> R) -> R {
+        struct Enable<'a>(&'a GcContext);
+
+        impl Drop for Enable<'_> {
+            fn drop(&mut self) {
+                self.0.enable()
+            }
+        }
+
+        self.disable();
+        let _enable = Enable(self);
+        f(self)
+    }
+
+    /// Keeps the collector from collecting by itself until the returned guard is dropped, which is
+    /// useful around a bulk operation such as dropping or building a large collection of `Gc<_>`s
+    ///
+    /// Collections requested in the meantime are run once the last `GcBatch` is dropped, instead
+    /// of repeatedly partway through. Explicit collections like [`GcContext::force_collect`] still
+    /// run.
+    pub fn batch(&self) -> GcBatch {
+        self.lock().batches += 1;
+        GcBatch {
+            context: Arc::clone(&self.inner),
+        }
+    }
+
+    /// Permanently stops this context's collector, and any allocation in this context from then on
+    ///
+    /// Everything which is unreachable is collected first. Objects which are still reachable stay
+    /// alive, so every `Gc<_>` pointing into this context stays valid, and they can still be
+    /// collected by [`GcContext::force_collect`] once they're unreachable. Only allocating fails,
+    /// see [`GcContext::try_alloc`]. A `WeakGc<_>` to a collected object upgrades to `None`, the
+    /// same as before shutting down.
+    ///
+    /// Waits for the collector to stop if it's collecting. Shutting down a context more than once
+    /// does nothing.
+    pub fn shutdown(&self) {
+        let mut gc = self.lock();
+        if self.inner.shut_down.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        gc.mark_sweep();
+        #[cfg(feature = "background-thread")]
+        {
+            let handle = gc.collection_handle.take();
+            drop(gc);
+            self.inner.wake.notify_all();
+            if let Some(handle) = handle {
+                // A panic in the collector was already reported by the panic hook
+                let _ = handle.join();
+            }
+        }
+    }
+
+    /// Creates an empty [`RootSet`] in this context
+    pub fn root_set<T: ?Sized + GcAble>(&self) -> RootSet<T> {
+        RootSet::new_in(&self.inner)
+    }
+
+    /// Allocates a box for a `T` in this context's heap without initializing it
+    ///
+    /// The box is registered right away, but its value isn't traced or dropped until it's
+    /// initialized and passed to [`Gc::assume_init`]. A box which is collected before then is
+    /// freed without dropping anything.
+    pub fn alloc_uninit<T: GcAble>(&self) -> Gc<MaybeUninit<T>> {
+        Gc::new_uninit_in(&self.inner)
+    }
+
+    /// Like [`Gc::new_cyclic`], but in this context
+    pub fn alloc_cyclic<T: GcAble>(&self, f: impl FnOnce(&WeakGc<T>) -> T) -> Gc<T> {
+        Gc::new_cyclic_in(&self.inner, f)
+    }
+
+    /// Makes sure all memory in this context that can be freed at the moment is freed
+    ///
+    /// If other threads call this at the same time, a single collection is run for all of them,
+    /// since one which starts after a call frees everything that call would have.
+    pub fn force_collect(&self) {
+        self.inner.force_collect()
+    }
+
+    /// Runs full collections until one doesn't free anything, returning the number of objects
+    /// freed in total
+    ///
+    /// A single collection frees everything that's unreachable when it starts, but a finalizer or
+    /// `Drop` may release the last root to other objects while it runs, such as by removing it
+    /// from a global table. Those are only freed by the next collection.
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use gc::{Gc, GcAble, GcConfig, GcContext};
+    ///
+    /// type Table = Arc<Mutex<Vec<Option<Gc<Link>>>>>;
+    ///
+    /// /// Releases the next link from the table when dropped
+    /// struct Link {
+    ///     id: usize,
+    ///     table: Table,
+    /// }
+    /// # unsafe impl GcAble for Link {
+    /// #     unsafe fn mark(&self) {}
+    /// #     unsafe fn inc_root_count(&self) {}
+    /// #     unsafe fn dec_root_count(&self) {}
+    /// #     unsafe fn set_not_root(&self) {}
+    /// # }
+    ///
+    /// impl Drop for Link {
+    ///     fn drop(&mut self) {
+    ///         let next = self.table.lock().unwrap().get_mut(self.id + 1).and_then(Option::take);
+    ///         drop(next);
+    ///     }
+    /// }
+    ///
+    /// let ctx = GcContext::with_config(GcConfig::default().deterministic(true));
+    /// let table = Table::default();
+    /// for id in 0..5 {
+    ///     let link = ctx.alloc(Link { id, table: Arc::clone(&table) });
+    ///     table.lock().unwrap().push(Some(link));
+    /// }
+    ///
+    /// table.lock().unwrap()[0] = None;
+    /// ctx.force_collect();
+    /// assert_eq!(ctx.stats().live_allocations, 4);
+    /// assert_eq!(ctx.force_collect_until_stable(), 4);
+    /// assert_eq!(ctx.stats().live_allocations, 0);
+    /// ```
+    pub fn force_collect_until_stable(&self) -> usize {
+        self.lock().mark_sweep_until_stable()
+    }
+
+    /// Runs the finalizers queued by collections on the calling thread, returning how many ran, see
+    /// [`GcConfig::defer_finalizers`]
+    ///
+    /// The context's lock isn't held while they run, so they may allocate and collect. The
+    /// collector's thread runs the queue by itself after each collection, so this is only needed
+    /// for a deterministic context, or to have finalizers run at a point of the caller's choosing.
+    pub fn flush_finalizers(&self) -> usize {
+        self.inner.flush_finalizers()
+    }
+
+    /// Gives every freed object kept for reuse back to the allocator, returning the number of bytes
+    /// released, see [`GcConfig::free_list_capacity`]
+    ///
+    /// Like `malloc_trim`, this is meant for when a burst of allocations is over, so that the
+    /// memory the burst left in the free list isn't held on to while the program is idle. The list
+    /// fills up again as objects are collected.
+    pub fn trim(&self) -> usize {
+        self.inner.trim()
+    }
+
+    /// Asks the collector to collect everything it can as soon as possible, such as when the host
+    /// is low on memory, without waiting for it to
+    ///
+    /// The collector is woken right away, and its next step finishes any major collection in
+    /// progress in one go, or runs a whole major collection otherwise. Unlike background
+    /// collections, this isn't held off by a [`GcBatch`], only by [`GcContext::disable`].
+    ///
+    /// If there's no collector, which is only the case without the `background-thread` feature
+    /// or a [`GcConfig::driver`], or the context is [`GcConfig::deterministic`], this collects on
+    /// the calling thread instead.
+    pub fn request_urgent_collection(&self) {
+        let mut gc = self.lock();
+        gc.urgent = true;
+        if self.inner.driver.is_none() || gc.config.deterministic {
+            gc.collect();
+            return;
+        }
+        gc.collection_requested = true;
+        self.inner.wake_collector();
+    }
+
+    /// Does one bounded unit of collection work on the calling thread, which is what the collector
+    /// does every time it wakes up
+    ///
+    /// This is a single slice of marking if [`GcConfig::incremental`] is set, and a whole
+    /// collection otherwise. Returns `true` if this finished a collection, so a collection can be
+    /// driven from an event loop by calling this until it does.
+    ///
+    /// A collection run a step at a time frees the same objects as [`GcContext::force_collect`]:
+    ///
+    /// ```
+    /// use std::{
+    ///     sync::{Arc, Mutex},
+    ///     time::Duration,
+    /// };
+    ///
+    /// use gc::{Gc, GcAble, GcCell, GcConfig, GcContext};
+    ///
+    /// #[derive(Clone)]
+    /// struct Link(Option<Gc<Node>>);
+    /// # unsafe impl GcAble for Link {
+    /// #     unsafe fn mark(&self) {
+    /// #         self.0.iter().for_each(|gc| unsafe { gc.mark() })
+    /// #     }
+    /// #     unsafe fn inc_root_count(&self) {
+    /// #         self.0.iter().for_each(|gc| unsafe { gc.inc_root_count() })
+    /// #     }
+    /// #     unsafe fn dec_root_count(&self) {
+    /// #         self.0.iter().for_each(|gc| unsafe { gc.dec_root_count() })
+    /// #     }
+    /// #     unsafe fn set_not_root(&self) {
+    /// #         self.0.iter().for_each(|gc| unsafe { gc.set_not_root() })
+    /// #     }
+    /// # }
+    ///
+    /// struct Node {
+    ///     id: u32,
+    ///     dropped: Arc<Mutex<Vec<u32>>>,
+    ///     next: GcCell<Link>,
+    /// }
+    /// # unsafe impl GcAble for Node {
+    /// #     unsafe fn mark(&self) {
+    /// #         unsafe { self.next.mark() }
+    /// #     }
+    /// #     unsafe fn inc_root_count(&self) {
+    /// #         unsafe { self.next.inc_root_count() }
+    /// #     }
+    /// #     unsafe fn dec_root_count(&self) {
+    /// #         unsafe { self.next.dec_root_count() }
+    /// #     }
+    /// #     unsafe fn set_not_root(&self) {
+    /// #         unsafe { self.next.set_not_root() }
+    /// #     }
+    /// # }
+    ///
+    /// impl Drop for Node {
+    ///     fn drop(&mut self) {
+    ///         self.dropped.lock().unwrap().push(self.id);
+    ///     }
+    /// }
+    ///
+    /// /// Every third node is kept, and the rest are chained into garbage
+    /// fn dropped(config: GcConfig, collect: impl FnOnce(&GcContext)) -> Vec<u32> {
+    ///     // Only collected by `collect`
+    ///     let config = config
+    ///         .alloc_watermark(usize::MAX)
+    ///         .max_interval(Duration::from_secs(3600));
+    ///     let ctx = GcContext::with_config(config);
+    ///     let dropped = Arc::new(Mutex::new(Vec::new()));
+    ///     let mut kept = Vec::new();
+    ///     let mut last = None;
+    ///     for id in 0..100 {
+    ///         let next = GcCell::new(Link(if id % 3 == 0 { None } else { last.take() }));
+    ///         let node = ctx.alloc(Node { id, dropped: Arc::clone(&dropped), next });
+    ///         match id % 3 {
+    ///             0 => kept.push(node),
+    ///             _ => last = Some(node),
+    ///         }
+    ///     }
+    ///     drop(last);
+    ///     collect(&ctx);
+    ///     let mut dropped = dropped.lock().unwrap().clone();
+    ///     dropped.sort();
+    ///     dropped
+    /// }
+    ///
+    /// let forced = dropped(GcConfig::default(), GcContext::force_collect);
+    /// let mut steps = 0;
+    /// let stepped = dropped(GcConfig::default().incremental(4), |ctx| {
+    ///     steps += 1;
+    ///     while !ctx.collect_step() {
+    ///         steps += 1;
+    ///     }
+    /// });
+    /// assert!(steps > 1);
+    /// assert_eq!(forced.len(), 66);
+    /// assert_eq!(stepped, forced);
+    /// ```
+    pub fn collect_step(&self) -> bool {
+        self.lock().collect()
+    }
+
+    /// Blocks until this context's collector finishes a collection which started after this was
+    /// called, waking the collector up if it's asleep
+    ///
+    /// A collection which is in progress when this is called doesn't count. Collections run by
+    /// [`GcContext::force_collect`] in the meantime do. Never returns while a `GcRefMut` or
+    /// `DeepCloner` in this context is alive, or while collection is disabled, since collection is
+    /// skipped until then.
+    ///
+    /// Without the `background-thread` feature, or for a [`GcConfig::deterministic`] context, this
+    /// runs a full collection on the calling thread instead.
+    #[cfg(feature = "background-thread")]
+    pub fn wait_for_collection(&self) {
+        let mut gc = self.lock();
+        // There's no collector to wait for
+        if self.inner.is_shut_down() || self.inner.driver.is_none() || gc.config.deterministic {
+            drop(gc);
+            return self.force_collect();
+        }
+        let started = gc.collections;
+        gc.collection_requested = true;
+        self.inner.wake_collector();
+        let _gc = gc.wait_while(&self.inner.collected, |gc| gc.last_finished <= started);
+    }
+
+    /// Runs a full collection on the calling thread, since there's no collector to wait for
+    #[cfg(not(feature = "background-thread"))]
+    pub fn wait_for_collection(&self) {
+        self.force_collect()
+    }
+
+    /// Like [`crate::dump_dot`], but for this context
+    ///
+    /// ```
+    /// use gc::{Gc, GcAble, GcContext};
+    ///
+    /// struct Node {
+    ///     next: Option<Gc<Node>>,
+    /// }
+    ///
+    /// // SAFETY: every method forwards to `next`, the only `Gc<_>` in a `Node`
+    /// unsafe impl GcAble for Node {
+    ///     unsafe fn mark(&self) {
+    ///         self.next.iter().for_each(|next| unsafe { next.mark() })
+    ///     }
+    ///
+    ///     unsafe fn inc_root_count(&self) {
+    ///         self.next.iter().for_each(|next| unsafe { next.inc_root_count() })
+    ///     }
+    ///
+    ///     unsafe fn dec_root_count(&self) {
+    ///         self.next.iter().for_each(|next| unsafe { next.dec_root_count() })
+    ///     }
+    ///
+    ///     unsafe fn set_not_root(&self) {
+    ///         self.next.iter().for_each(|next| unsafe { next.set_not_root() })
+    ///     }
+    /// }
+    ///
+    /// let ctx = GcContext::new();
+    /// let b = ctx.alloc(Node { next: None });
+    /// let _a = ctx.alloc(Node { next: Some(b) });
+    /// ctx.force_collect();
+    ///
+    /// let dot = ctx.dump_dot();
+    /// assert!(dot.starts_with("digraph gc {\n"));
+    /// // Nodes are named by address, so they're found by their root counts
+    /// let name = |root_count: &str| {
+    ///     let node = dot.lines().find(|line| line.contains(root_count)).unwrap();
+    ///     node.split('"').nth(1).unwrap().to_string()
+    /// };
+    /// let (a_name, b_name) = (name("root_count: 1"), name("root_count: 0"));
+    /// assert!(dot.contains(&format!("\"{a_name}\" -> \"{b_name}\";")));
+    /// assert_eq!(dot.matches("->").count(), 1);
+    /// ```
+    pub fn dump_dot(&self) -> String {
+        self.lock().dump_dot()
+    }
+
+    /// Like [`crate::heap_snapshot`], but for this context
+    pub fn heap_snapshot(&self) -> HeapSnapshot {
+        self.lock().heap_snapshot()
+    }
+
+    /// Like [`crate::stats`], but for this context
+    pub fn stats(&self) -> GcStats {
+        self.lock().stats()
+    }
+
+    /// Like [`crate::runtime_metrics`], but for this context
+    pub fn runtime_metrics(&self) -> RuntimeMetrics {
+        self.lock().runtime_metrics()
+    }
+
+    /// Like [`crate::for_each_live`], but for this context
+    pub fn for_each_live(&self, f: impl FnMut(LiveObject)) {
+        let live = self.lock().live_objects();
+        live.into_iter().for_each(f)
+    }
+
+    /// Like [`crate::histogram_by_type`], but for this context
+    #[cfg(feature = "std")]
+    pub fn histogram_by_type(&self) -> HashMap<&'static str, usize> {
+        self.lock().histogram_by_type()
+    }
+
+    /// Like [`crate::consistency_check`], but for this context
+    pub fn consistency_check(&self) {
+        self.lock().consistency_check()
+    }
+
+    /// Like [`crate::live_allocations`], but for this context
+    pub fn live_allocations(&self) -> Vec<AllocSummary> {
+        let mut gc = self.lock();
+        gc.mark_sweep();
+        gc.live_allocations()
+    }
+
+    /// Like [`crate::assert_no_leaks`], but for this context
+    pub fn assert_no_leaks(&self) {
+        crate::inspect::assert_no_leaks(self.live_allocations())
+    }
+
+    pub(crate) fn lock(&self) -> GcLock<'_> {
+        self.inner.lock()
+    }
+}
+
+impl Default for GcContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The reason an allocation failed, see [`crate::Gc::try_new`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GcError {
+    /// The context was shut down by [`GcContext::shutdown`]
+    ShutDown,
+    /// The allocator couldn't allocate the value, even after collecting
+    OutOfMemory,
+}
+
+impl Display for GcError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::ShutDown => write!(f, "the Gc context was shut down"),
+            Self::OutOfMemory => write!(f, "out of memory"),
+        }
+    }
+}
+
+impl core::error::Error for GcError {}
+
+/// Defers background collection in a context while alive, see [`GcContext::batch`]
+pub struct GcBatch {
+    context: Arc<ContextInner>,
+}
+
+impl Drop for GcBatch {
+    fn drop(&mut self) {
+        let mut gc = self.context.lock();
+        gc.batches -= 1;
+        if gc.batches == 0 && gc.collection_requested {
+            self.context.wake_collector();
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use alloc::sync::Arc;
+use core::{
+    ptr::{addr_of_mut, NonNull},
+    sync::atomic::AtomicBool,
+};
+
+use crate::{
+    context::ContextInner,
+    sys::{collections::HashMap, sync::OnceLock},
+    tracer, AllocAddr, Gc, GcAble, GcBox, GcContext,
+};
+
+/// A value which can be copied along with every `Gc<_>` it references, see [`Gc::clone_deep`]
+///
+/// Values without any `Gc<_>`s can implement this by cloning themselves
+pub trait DeepClone: GcAble + Sized {
+    /// Clones this, using [`DeepCloner::clone_gc`] on every `Gc<_>` directly contained in it
+    fn clone_deep(&self, cloner: &mut DeepCloner) -> Self;
+}
+
+/// Copies the objects of a graph while remembering which have already been copied, so objects
+/// which are shared in the original graph are shared in the copy as well
+///
+/// Collection is skipped in the context being copied into for as long as this is alive, since the
+/// copies of objects which are still being cloned aren't initialized yet
+pub struct DeepCloner {
+    context: Arc<ContextInner>,
+    /// The copy of every object copied so far, by the address of its original
+    copies: HashMap<AllocAddr, NonNull<GcBox<dyn GcAble>>>,
+}
+
+impl DeepCloner {
+    fn new(context: &Arc<ContextInner>) -> Self {
+        context.lock().exclusive_borrows += 1;
+        Self {
+            context: Arc::clone(context),
+            copies: HashMap::new(),
+        }
+    }
+
+    /// Returns the copy of `gc`'s value, copying it first if this is the first time it's reached
+    pub fn clone_gc<T: DeepClone>(&mut self, gc: &Gc<T>) -> Gc<T> {
+        let addr = AllocAddr::from(gc.gcbox.as_ptr());
+        if let Some(copy) = self.copies.get(&addr) {
+            return unsafe { Gc::from_gcbox(copy.cast()) };
+        }
+
+        // The header is written before the value is cloned, so that cycles back to this object can
+        // already point to its copy
+        let gcbox = Gc::<T>::alloc_uninit(&self.context);
+        unsafe {
+            addr_of_mut!((*gcbox.as_ptr()).header).write(Gc::<T>::new_header(
+                &self.context,
+                None,
+                OnceLock::new(),
+            ))
+        };
+        let copy = Gc {
+            is_root: AtomicBool::new(true),
+            gcbox,
+        };
+        self.copies.insert(addr, gcbox);
+        // Cycles back to the copy point to it before it's registered, which debug builds would
+        // otherwise take for a use after free
+        tracer::registered(AllocAddr::from(gcbox.as_ptr()));
+
+        let val = T::clone_deep(gc, self);
+        let mut alloc = self.context.lock();
+        unsafe {
+            val.set_not_root();
+            addr_of_mut!((*gcbox.as_ptr()).val).write(val);
+        }
+        let verify = alloc.config.verify_roots;
+        Gc::register(&mut alloc, &self.context, gcbox);
+        drop(alloc);
+        if verify {
+            copy.verify_not_root();
+        }
+        copy
+    }
+}
+
+impl Drop for DeepCloner {
+    fn drop(&mut self) {
+        self.context.lock().exclusive_borrows -= 1;
+    }
+}
+
+impl<T: DeepClone> Gc<T> {
+    /// Copies the value, and everything reachable from it, into new allocations
+    ///
+    /// Unlike [`Clone::clone`], which gives another handle to the same value, the returned `Gc<T>`
+    /// shares nothing with this one. Objects reachable through several paths, including cycles, are
+    /// copied once.
+    pub fn clone_deep(&self) -> Gc<T> {
+        let gcb = self.gcb();
+        DeepCloner::new(&gcb.header.context).clone_gc(self)
+    }
+
+    /// Like [`Gc::clone_deep`], but makes the copies in `ctx` instead of in this value's context
+    ///
+    /// This is how data moves between contexts, such as when something built in a short-lived
+    /// context has to outlive it. The copy is collected by `ctx` alone, and shares nothing with the
+    /// original, which is still collected by its own context.
+    ///
+    /// ```
+    /// use gc::{DeepClone, DeepCloner, Gc, GcCell, GcContext};
+    ///
+    /// #[derive(gc::GcAble)]
+    /// struct Node {
+    ///     id: u32,
+    ///     next: GcCell<Option<Gc<Node>>>,
+    /// }
+    ///
+    /// impl DeepClone for Node {
+    ///     fn clone_deep(&self, cloner: &mut DeepCloner) -> Self {
+    ///         let next = self.next.get().map(|next| cloner.clone_gc(&next));
+    ///         Node { id: self.id, next: GcCell::new(next) }
+    ///     }
+    /// }
+    ///
+    /// let (scratch, long_lived) = (GcContext::new(), GcContext::new());
+    /// let a = scratch.alloc(Node { id: 1, next: GcCell::new(None) });
+    /// a.next.set(Some(scratch.alloc(Node { id: 2, next: GcCell::new(Some(a.clone())) })));
+    ///
+    /// let copy = a.clone_into_context(&long_lived);
+    /// assert_eq!(long_lived.stats().live_allocations, 2);
+    /// drop(a);
+    /// scratch.force_collect();
+    /// assert_eq!(scratch.stats().live_allocations, 0);
+    ///
+    /// // The cycle was copied as a cycle
+    /// let next = copy.next.get().unwrap();
+    /// assert_eq!(next.id, 2);
+    /// assert!(Gc::ptr_eq(&next.next.get().unwrap(), &copy));
+    /// drop((copy, next));
+    /// long_lived.force_collect();
+    /// assert_eq!(long_lived.stats().live_allocations, 0);
+    /// ```
+    pub fn clone_into_context(&self, ctx: &GcContext) -> Gc<T> {
+        DeepCloner::new(&ctx.inner).clone_gc(self)
+    }
+}
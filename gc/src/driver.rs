@@ -0,0 +1,120 @@
+use alloc::sync::Weak;
+use core::time::Duration;
+
+use crate::context::ContextInner;
+
+/// Decides when and where a context's collections run, see [`crate::GcConfig::driver`]
+///
+/// Every context gets a [`ThreadDriver`] by default, which collects on a thread of its own. A
+/// custom driver can run collections on an async runtime or a timer instead, which is needed where
+/// blocking a thread is wrong.
+pub trait CollectorDriver: Send + Sync + 'static {
+    /// Called once while the context is being created, after which `collector` runs its
+    /// collections
+    fn start(&self, collector: Collector);
+
+    /// Called whenever the context wants to be collected, such as once enough has been allocated
+    /// since the last collection
+    ///
+    /// This runs while the context's lock is held, so it must not call into the Gc, only arrange
+    /// for [`Collector::collect_step`] to be called soon.
+    fn wake(&self) {}
+}
+
+/// Runs a context's collections for its [`CollectorDriver`], without keeping the context alive
+#[derive(Clone)]
+pub struct Collector {
+    pub(crate) ctx: Weak<ContextInner>,
+}
+
+impl Collector {
+    /// Does one bounded unit of collection work, see [`crate::GcContext::collect_step`], then runs
+    /// any finalizers queued by [`crate::GcConfig::defer_finalizers`]
+    ///
+    /// Does nothing while the context is batched or disabled. Returns `None` once the context has
+    /// been dropped or shut down, after which there's nothing left to drive.
+    pub fn collect_step(&self) -> Option<bool> {
+        let ctx = self.ctx.upgrade()?;
+        if ctx.is_shut_down() {
+            return None;
+        }
+        let mut gc = ctx.lock();
+        if gc.collection_paused() {
+            return Some(false);
+        }
+        let done = gc.collect();
+        drop(gc);
+        ctx.flush_finalizers();
+        #[cfg(feature = "background-thread")]
+        ctx.collected.notify_all();
+        Some(done)
+    }
+
+    /// Whether a collection was requested or is partway done, meaning
+    /// [`Collector::collect_step`] has work to do right away
+    pub fn is_requested(&self) -> bool {
+        let Some(ctx) = self.ctx.upgrade() else {
+            return false;
+        };
+        let gc = ctx.lock();
+        gc.has_work()
+    }
+
+    /// The longest the driver should go without calling [`Collector::collect_step`], see
+    /// [`crate::GcConfig::max_interval`]
+    ///
+    /// `None` if the context should only be collected when requested, such as when
+    /// [`crate::GcConfig::deterministic`] is set, or if it's gone
+    pub fn max_interval(&self) -> Option<Duration> {
+        let ctx = self.ctx.upgrade()?;
+        let gc = ctx.lock();
+        (!gc.config.deterministic).then_some(gc.config.max_interval)
+    }
+}
+
+/// Collects on a thread of its own, which sleeps until a collection is requested or for at most
+/// [`crate::GcConfig::max_interval`]
+///
+/// The thread is named by [`crate::GcConfig::thread_name`], and its priority is set by
+/// [`crate::GcConfig::low_priority`]
+#[cfg(feature = "background-thread")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadDriver;
+
+#[cfg(feature = "background-thread")]
+impl CollectorDriver for ThreadDriver {
+    fn start(&self, collector: Collector) {
+        let Some(ctx) = collector.ctx.upgrade() else {
+            return;
+        };
+        let (thread_name, low_priority) = {
+            let gc = ctx.lock();
+            (gc.config.thread_name.clone(), gc.config.low_priority)
+        };
+        let handle = std::thread::Builder::new()
+            .name(thread_name)
+            .spawn(move || {
+                if low_priority {
+                    lower_thread_priority();
+                }
+                crate::GcAlloc::collection_loop(collector.ctx)
+            })
+            .expect("failed to spawn the collector's thread");
+        ctx.lock().collection_handle = Some(handle);
+    }
+}
+
+/// Raises the calling thread's nice value, see [`crate::GcConfig::low_priority`]
+#[cfg(all(feature = "background-thread", target_os = "linux"))]
+fn lower_thread_priority() {
+    extern "C" {
+        fn setpriority(which: i32, who: u32, prio: i32) -> i32;
+    }
+    const PRIO_PROCESS: i32 = 0;
+    // On Linux every thread has its own nice value, and `0` means the calling thread. Failing just
+    // leaves the priority as it was.
+    unsafe { setpriority(PRIO_PROCESS, 0, 10) };
+}
+
+#[cfg(all(feature = "background-thread", not(target_os = "linux")))]
+fn lower_thread_priority() {}
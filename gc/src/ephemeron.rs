@@ -0,0 +1,109 @@
+use core::fmt::Debug;
+
+use crate::{tracer, Gc, GcAble, WeakGc};
+
+/// A key and a value, where the value is kept alive by this only for as long as the key is reachable
+/// some other way
+///
+/// This is the building block of a `WeakMap`, whose entries are meant to go away once their key
+/// is unreachable, even when the value points back to the key. A value held by a `Gc<_>` would keep
+/// its key alive in that case, while one held by a `WeakGc<_>` would be collected while the key is
+/// still in use. Neither the key nor the value is kept alive by the ephemeron itself.
+///
+/// The collector only marks the value of an ephemeron it reaches once it has marked the key,
+/// repeating until no more keys are found to be reachable. Like a `Gc<_>`, an ephemeron only does
+/// this once it's stored in an object, since the collector doesn't know about one anywhere else.
+///
+/// ```
+/// use gc::{Ephemeron, Gc, GcMut};
+///
+/// #[derive(gc::GcAble)]
+/// struct Key(u32);
+///
+/// #[derive(gc::GcAble)]
+/// struct Value {
+///     // Pointing back to the key doesn't keep it alive
+///     key: Gc<Key>,
+/// }
+///
+/// let table = GcMut::new(Vec::new());
+/// let (alive, dead) = (Gc::new(Key(1)), Gc::new(Key(2)));
+/// for key in [&alive, &dead] {
+///     let value = Gc::new(Value { key: key.clone() });
+///     table.borrow_mut().push(Ephemeron::new(key, &value));
+/// }
+/// drop(dead);
+///
+/// gc::force_collect();
+/// let table = table.borrow();
+/// assert_eq!(table[0].value().unwrap().key.0, 1);
+/// assert!(table[1].key().is_none() && table[1].value().is_none());
+/// ```
+pub struct Ephemeron<K: GcAble, V: GcAble> {
+    key: WeakGc<K>,
+    value: WeakGc<V>,
+}
+
+impl<K: GcAble, V: GcAble> Ephemeron<K, V> {
+    /// Panics if `key` and `value` belong to different contexts
+    pub fn new(key: &Gc<K>, value: &Gc<V>) -> Self {
+        assert!(
+            key.gcb().header.context.id == value.gcb().header.context.id,
+            "the key and value of an `Ephemeron` must belong to the same `GcContext`"
+        );
+        Self {
+            key: Gc::downgrade(key),
+            value: Gc::downgrade(value),
+        }
+    }
+
+    /// Returns the key if it hasn't been collected
+    pub fn key(&self) -> Option<Gc<K>> {
+        self.key.upgrade()
+    }
+
+    /// Returns the value if the key hasn't been collected
+    ///
+    /// The value may outlive the key if something else points to it, but it's no longer
+    /// associated with it once the key is gone
+    pub fn value(&self) -> Option<Gc<V>> {
+        self.get().map(|(_, value)| value)
+    }
+
+    /// Returns both the key and the value, if the key hasn't been collected
+    pub fn get(&self) -> Option<(Gc<K>, Gc<V>)> {
+        let key = self.key.upgrade()?;
+        Some((key, self.value.upgrade()?))
+    }
+}
+
+impl<K: GcAble, V: GcAble> Clone for Ephemeron<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl<K: GcAble, V: GcAble> Debug for Ephemeron<K, V> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "(Ephemeron)")
+    }
+}
+
+/// Only `mark` does anything, since an ephemeron holds no roots
+unsafe impl<K: GcAble, V: GcAble> GcAble for Ephemeron<K, V> {
+    unsafe fn mark(&self) {
+        // Tracers run while holding the lock, and one which was collected is never traced again
+        if let (Some(key), Some(value)) = (self.key.erased(), self.value.erased()) {
+            unsafe { tracer::visit_ephemeron(key, value) }
+        }
+    }
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
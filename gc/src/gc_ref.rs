@@ -0,0 +1,133 @@
+use core::{fmt::Debug, ops::Deref, ptr::NonNull};
+
+use crate::{Gc, GcAble};
+
+/// A `Gc<T>` projected onto a part of its value, see [`Gc::map`]
+///
+/// Keeps the whole `T` alive for as long as this is, and traces through to it when stored inside
+/// another `GcAble` value
+pub struct GcRef<T: GcAble, U: ?Sized> {
+    gc: Gc<T>,
+    projected: NonNull<U>,
+}
+
+// SAFETY: This only gives out `&U`, and keeping `gc` around is thread safe
+unsafe impl<T: GcAble, U: ?Sized + Sync> Send for GcRef<T, U> {}
+// SAFETY: See `Send`
+unsafe impl<T: GcAble, U: ?Sized + Sync> Sync for GcRef<T, U> {}
+
+impl<T: GcAble> Gc<T> {
+    /// Projects this onto a part of its value, such as a field, without cloning it
+    ///
+    /// The projection keeps the whole object alive, whether it's a root or stored in another
+    /// object:
+    ///
+    /// ```
+    /// use gc::{Gc, GcAble, GcContext, GcRef};
+    ///
+    /// struct Person {
+    ///     name: String,
+    ///     age: u32,
+    /// }
+    /// # // SAFETY: there are no `Gc<_>`s in a `Person`
+    /// # unsafe impl GcAble for Person {
+    /// #     unsafe fn mark(&self) {}
+    /// #     unsafe fn inc_root_count(&self) {}
+    /// #     unsafe fn dec_root_count(&self) {}
+    /// #     unsafe fn set_not_root(&self) {}
+    /// # }
+    ///
+    /// struct Label {
+    ///     text: GcRef<Person, str>,
+    /// }
+    /// # // SAFETY: every method forwards to `text`
+    /// # unsafe impl GcAble for Label {
+    /// #     unsafe fn mark(&self) { unsafe { self.text.mark() } }
+    /// #     unsafe fn inc_root_count(&self) { unsafe { self.text.inc_root_count() } }
+    /// #     unsafe fn dec_root_count(&self) { unsafe { self.text.dec_root_count() } }
+    /// #     unsafe fn set_not_root(&self) { unsafe { self.text.set_not_root() } }
+    /// # }
+    ///
+    /// let ctx = GcContext::new();
+    /// let person = ctx.alloc(Person { name: "Ada".into(), age: 36 });
+    /// let weak = Gc::downgrade(&person);
+    /// let name = Gc::map(person, |person| person.name.as_str());
+    ///
+    /// ctx.force_collect();
+    /// assert_eq!(&*name, "Ada");
+    /// assert_eq!(weak.upgrade().unwrap().age, 36);
+    ///
+    /// let label = ctx.alloc(Label { text: name });
+    /// ctx.force_collect();
+    /// assert_eq!(&*label.text, "Ada");
+    ///
+    /// drop(label);
+    /// ctx.force_collect();
+    /// assert!(weak.upgrade().is_none());
+    /// ```
+    pub fn map<U: ?Sized>(this: Self, f: impl FnOnce(&T) -> &U) -> GcRef<T, U> {
+        let projected = NonNull::from(f(&this));
+        GcRef {
+            gc: this,
+            projected,
+        }
+    }
+}
+
+impl<T: GcAble, U: ?Sized> GcRef<T, U> {
+    /// Projects this further, see [`Gc::map`]
+    pub fn map<V: ?Sized>(this: Self, f: impl FnOnce(&U) -> &V) -> GcRef<T, V> {
+        let projected = NonNull::from(f(&this));
+        GcRef {
+            gc: this.gc,
+            projected,
+        }
+    }
+
+    /// The `Gc<_>` this was projected from
+    pub fn parent(this: &Self) -> &Gc<T> {
+        &this.gc
+    }
+}
+
+impl<T: GcAble, U: ?Sized> Deref for GcRef<T, U> {
+    type Target = U;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: `projected` points into `gc`'s value, which is alive and never moves
+        unsafe { self.projected.as_ref() }
+    }
+}
+
+impl<T: GcAble, U: ?Sized> Clone for GcRef<T, U> {
+    fn clone(&self) -> Self {
+        Self {
+            gc: self.gc.clone(),
+            projected: self.projected,
+        }
+    }
+}
+
+impl<T: GcAble, U: ?Sized + Debug> Debug for GcRef<T, U> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?}", &**self)
+    }
+}
+
+unsafe impl<T: GcAble, U: ?Sized + Sync + 'static> GcAble for GcRef<T, U> {
+    unsafe fn mark(&self) {
+        unsafe { self.gc.mark() }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { self.gc.inc_root_count() }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self.gc.dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { self.gc.set_not_root() }
+    }
+}
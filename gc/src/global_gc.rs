@@ -1,81 +1,31 @@
-use std::{
-    mem::MaybeUninit,
-    ops::{Deref, DerefMut},
-    sync::{Mutex, Once},
-};
+use core::ops::DerefMut;
 
-use crate::{GcAble, GcAlloc, GcBox};
+use crate::{sys::sync::OnceLock, GcAlloc, GcConfig, GcContext};
 
-static GC: GcAllock = GcAllock::new();
+static GC: OnceLock<GcContext> = OnceLock::new();
 
-struct GcAllock {
-    gc: Mutex<MaybeUninit<GcAlloc>>,
-    once: Once,
-}
-
-impl GcAllock {
-    const fn new() -> Self {
-        Self {
-            gc: Mutex::new(MaybeUninit::uninit()),
-            once: Once::new(),
-        }
-    }
-}
-
-/// A locked initialized Gc instance
-struct GcAllocked<M>
-where
-    M: DerefMut<Target = MaybeUninit<GcAlloc>>,
-{
-    lock: M,
-}
-
-impl<M> GcAllocked<M>
-where
-    M: DerefMut<Target = MaybeUninit<GcAlloc>>,
-{
-    pub unsafe fn assume_init(lock: M) -> Self {
-        Self { lock }
-    }
-}
-
-impl<M> Deref for GcAllocked<M>
-where
-    M: DerefMut<Target = MaybeUninit<GcAlloc>>,
-{
-    type Target = GcAlloc;
-
-    fn deref(&self) -> &Self::Target {
-        unsafe { self.lock.assume_init_ref() }
-    }
+/// Returns `true` iff the global Gc has been initialized
+pub fn is_init() -> bool {
+    GC.get().is_some()
 }
 
-impl<M> DerefMut for GcAllocked<M>
-where
-    M: DerefMut<Target = MaybeUninit<GcAlloc>>,
-{
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        unsafe { self.lock.assume_init_mut() }
-    }
+/// Returns the global Gc's context, initializing it if it isn't
+pub fn context() -> &'static GcContext {
+    GC.get_or_init(GcContext::new)
 }
 
-/// Returns `true` iff the global Gc has been initialized
-pub fn is_init() -> bool {
-    GC.once.is_completed()
+/// Initializes the global Gc with `config`, returns `false` if it was already initialized
+pub fn init_with(config: GcConfig) -> bool {
+    let mut initialized = false;
+    GC.get_or_init(|| {
+        initialized = true;
+        GcContext::with_config(config)
+    });
+    initialized
 }
 
 /// Locks the global Gc and makes sure it's init
 #[inline(always)]
 pub fn lock() -> impl DerefMut<Target = GcAlloc> {
-    GC.once.call_once(|| {
-        let mut gc = GC.gc.lock().unwrap();
-        gc.write(GcAlloc::new());
-    });
-    unsafe { lock_assume_init() }
-}
-
-/// Locks the global Gc without making sure it's init
-#[inline(always)]
-pub unsafe fn lock_assume_init() -> impl DerefMut<Target = GcAlloc> {
-    unsafe { GcAllocked::assume_init(GC.gc.lock().unwrap()) }
+    context().lock()
 }
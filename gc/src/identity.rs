@@ -0,0 +1,112 @@
+use core::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+
+use crate::{Gc, GcAble};
+
+impl<T: ?Sized + GcAble> Gc<T> {
+    /// Hashes the address of the object, the same way [`Gc::ptr_eq`] compares it
+    ///
+    /// `Gc<T>`'s own `Hash` impl hashes the value instead, see [`GcIdentityKey`] for using
+    /// identity as a key
+    pub fn ptr_hash<H: Hasher>(this: &Self, state: &mut H) {
+        this.gcbox.cast::<u8>().hash(state)
+    }
+}
+
+/// A `Gc<T>` which is hashed and compared by which object it points to, instead of by its value
+///
+/// Two objects with equal values are different keys, and an object stays the same key when its
+/// value changes. This is meant for memoizing per object, or for remembering which objects a
+/// traversal of a graph has already visited:
+///
+/// ```
+/// use std::collections::HashMap;
+/// use gc::{Gc, GcIdentityKey};
+///
+/// #[derive(gc::GcAble, PartialEq, Eq, Hash)]
+/// struct ExampleNum(u64);
+///
+/// fn square(num: &Gc<ExampleNum>, memo: &mut HashMap<GcIdentityKey<ExampleNum>, u64>) -> u64 {
+///     *memo
+///         .entry(GcIdentityKey(num.clone()))
+///         .or_insert_with(|| num.0 * num.0)
+/// }
+///
+/// let (a, b) = (Gc::new(ExampleNum(3)), Gc::new(ExampleNum(3)));
+/// let mut memo = HashMap::new();
+/// square(&a, &mut memo);
+/// square(&a.clone(), &mut memo);
+/// assert_eq!(memo.len(), 1);
+/// // Equal to `a`, but a different object
+/// assert!(a == b);
+/// square(&b, &mut memo);
+/// assert_eq!(memo.len(), 2);
+/// ```
+pub struct GcIdentityKey<T: ?Sized + GcAble>(pub Gc<T>);
+
+impl<T: ?Sized + GcAble> GcIdentityKey<T> {
+    pub fn into_inner(self) -> Gc<T> {
+        self.0
+    }
+}
+
+impl<T: ?Sized + GcAble> Hash for GcIdentityKey<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Gc::ptr_hash(&self.0, state)
+    }
+}
+
+impl<T: ?Sized + GcAble> PartialEq for GcIdentityKey<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Gc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<T: ?Sized + GcAble> Eq for GcIdentityKey<T> {}
+
+impl<T: ?Sized + GcAble> Clone for GcIdentityKey<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: ?Sized + GcAble> Deref for GcIdentityKey<T> {
+    type Target = Gc<T>;
+
+    fn deref(&self) -> &Gc<T> {
+        &self.0
+    }
+}
+
+impl<T: ?Sized + GcAble> From<Gc<T>> for GcIdentityKey<T> {
+    fn from(gc: Gc<T>) -> Self {
+        Self(gc)
+    }
+}
+
+impl<T: ?Sized + GcAble + Debug> Debug for GcIdentityKey<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("GcIdentityKey").field(&self.0).finish()
+    }
+}
+
+unsafe impl<T: ?Sized + GcAble> GcAble for GcIdentityKey<T> {
+    unsafe fn mark(&self) {
+        unsafe { self.0.mark() }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { self.0.inc_root_count() }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self.0.dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { self.0.set_not_root() }
+    }
+}
@@ -0,0 +1,449 @@
+use alloc::{
+    collections::VecDeque,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt::Write, ptr::NonNull, time::Duration};
+
+use crate::{
+    sys::{collections::HashMap, time::Instant},
+    tracer, AllocAddr, GcAble, GcAlloc, GcBox, SweepPhase,
+};
+
+/// A live allocation, see [`crate::live_allocations`]
+#[derive(Debug, Clone)]
+pub struct AllocSummary {
+    /// `std::any::type_name` of the allocated value
+    pub type_name: &'static str,
+    /// The number of root `Gc<_>`s pointing to the allocation
+    pub root_count: u32,
+}
+
+/// Statistics about a context's heap, see [`crate::stats`]
+#[derive(Debug, Clone)]
+pub struct GcStats {
+    /// The number of allocations which haven't been collected yet
+    pub live_allocations: usize,
+    /// The total size in bytes of every allocation which hasn't been collected yet
+    ///
+    /// This includes each allocation's bookkeeping, so a zero sized value still takes up space
+    pub total_bytes: usize,
+    /// How long the most recent collections paused the context for
+    pub pauses: PauseStats,
+}
+
+/// The distribution of the most recent collection pauses, see [`GcStats::pauses`]
+///
+/// A pause is the time the collector holds the context's lock to collect, so an incremental
+/// collection pauses once per slice. Every duration is zero if there hasn't been a pause yet.
+#[derive(Debug, Clone, Default)]
+pub struct PauseStats {
+    /// The number of pauses these statistics cover
+    pub count: usize,
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    /// The 99th percentile
+    pub p99: Duration,
+}
+
+/// How much time a context's collector has taken, see [`crate::runtime_metrics`]
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeMetrics {
+    /// The number of collections finished so far
+    pub collections: u64,
+    /// The total time spent collecting so far, including every slice of incremental collections
+    pub collection_time: Duration,
+    /// The number of collections finished per second over the last 10 seconds, or since the
+    /// context was created if that was more recent
+    pub collections_per_sec: f64,
+}
+
+/// Every object in a context and the references between them, see [`crate::heap_snapshot`]
+///
+/// This is plain data, so it can be turned into whatever format a tool reads
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeapSnapshot {
+    /// In the order the objects were allocated in if [`crate::GcConfig::deterministic`] is set
+    pub objects: Vec<HeapObject>,
+    /// Each `Gc<_>` stored in an object
+    pub edges: Vec<HeapEdge>,
+    /// `false` if a `GcRefMut` was alive, in which case the values couldn't be traced and `edges`
+    /// is empty
+    pub traced: bool,
+}
+
+/// An object in a [`HeapSnapshot`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeapObject {
+    /// The address of the object's value, which is what [`crate::Gc::as_ptr`] returns for a
+    /// `Gc<_>` pointing to it
+    pub addr: usize,
+    /// How many objects were allocated in the context before this one, which unlike `addr` is the
+    /// same from run to run
+    pub serial: u64,
+    /// `std::any::type_name` of the object's value
+    pub type_name: &'static str,
+    /// The number of root `Gc<_>`s pointing to the object
+    pub root_count: u32,
+    /// Whether the object was marked by the last collection
+    pub marked: bool,
+    /// Whether the object is in the old generation
+    pub old: bool,
+    /// The size in bytes of the object's allocation
+    pub size: usize,
+}
+
+/// A `Gc<_>` stored in an object of a [`HeapSnapshot`], pointing to another one
+///
+/// Both ends are indexes into [`HeapSnapshot::objects`]. An object holding several `Gc<_>`s to the
+/// same object has an edge for each of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HeapEdge {
+    pub from: usize,
+    pub to: usize,
+}
+
+/// The number of pauses `Pauses` remembers
+const PAUSE_WINDOW: usize = 256;
+
+/// How far back `RuntimeMetrics::collections_per_sec` looks
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// The current time, `None` where time isn't available
+fn now() -> Option<Instant> {
+    // `Instant::now` panics on `wasm32-unknown-unknown`, and there's no clock at all without `std`
+    if cfg!(any(
+        not(feature = "std"),
+        all(target_arch = "wasm32", target_os = "unknown")
+    )) {
+        return None;
+    }
+    Some(Instant::now())
+}
+
+/// When a pause started, `None` where time isn't available
+pub(crate) struct PauseStart(Option<Instant>);
+
+impl PauseStart {
+    pub fn now() -> Self {
+        Self(now())
+    }
+}
+
+/// The durations of the most recent `PAUSE_WINDOW` pauses
+#[derive(Default)]
+pub(crate) struct Pauses {
+    window: VecDeque<Duration>,
+    /// The sum of every pause so far
+    total: Duration,
+}
+
+impl Pauses {
+    /// Records a pause which started at `start` and ends now
+    pub fn record(&mut self, start: PauseStart) {
+        let Some(start) = start.0 else {
+            return;
+        };
+        if self.window.len() == PAUSE_WINDOW {
+            self.window.pop_front();
+        }
+        let pause = start.elapsed();
+        self.window.push_back(pause);
+        self.total += pause;
+    }
+
+    pub fn stats(&self) -> PauseStats {
+        if self.window.is_empty() {
+            return PauseStats::default();
+        }
+        let mut sorted = Vec::from(self.window.clone());
+        sorted.sort_unstable();
+        let count = sorted.len();
+        PauseStats {
+            count,
+            min: sorted[0],
+            max: sorted[count - 1],
+            mean: sorted.iter().sum::<Duration>() / count as u32,
+            p99: sorted[(count * 99).div_ceil(100) - 1],
+        }
+    }
+}
+
+/// When collections finished, for [`RuntimeMetrics`]
+pub(crate) struct Finishes {
+    total: u64,
+    /// The collections which finished within the last `RATE_WINDOW`
+    recent: VecDeque<Instant>,
+    created: Option<Instant>,
+}
+
+impl Finishes {
+    pub fn new() -> Self {
+        Self {
+            total: 0,
+            recent: VecDeque::new(),
+            created: now(),
+        }
+    }
+
+    /// Records a collection which finished just now
+    pub fn record(&mut self) {
+        self.total += 1;
+        if let Some(now) = now() {
+            self.recent.push_back(now);
+            self.forget_before(now);
+        }
+    }
+
+    fn forget_before(&mut self, now: Instant) {
+        while let Some(finished) = self.recent.front() {
+            if now - *finished <= RATE_WINDOW {
+                break;
+            }
+            self.recent.pop_front();
+        }
+    }
+
+    /// Collections per second over the last `RATE_WINDOW`, or `0.0` where time isn't available
+    pub fn rate(&mut self) -> f64 {
+        let (Some(now), Some(created)) = (now(), self.created) else {
+            return 0.0;
+        };
+        self.forget_before(now);
+        let span = (now - created).min(RATE_WINDOW).as_secs_f64();
+        match span > 0.0 {
+            true => self.recent.len() as f64 / span,
+            false => 0.0,
+        }
+    }
+}
+
+/// A snapshot of a live object, see [`crate::for_each_live`]
+#[derive(Debug, Clone)]
+pub struct LiveObject {
+    type_name: &'static str,
+    root_count: u32,
+    marked: bool,
+    size: usize,
+}
+
+impl LiveObject {
+    /// `std::any::type_name` of the object's value
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// The number of root `Gc<_>`s pointing to the object
+    pub fn root_count(&self) -> u32 {
+        self.root_count
+    }
+
+    /// Whether the object was marked by the last collection
+    pub fn marked(&self) -> bool {
+        self.marked
+    }
+
+    /// The size in bytes of the object's allocation
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+pub(crate) fn assert_no_leaks(live: Vec<AllocSummary>) {
+    if !live.is_empty() {
+        panic!("{} allocations are still live: {live:#?}", live.len());
+    }
+}
+
+impl GcAlloc {
+    /// See [`crate::stats`]
+    pub fn stats(&self) -> GcStats {
+        GcStats {
+            live_allocations: self.len(),
+            total_bytes: self.total_bytes,
+            pauses: self.pauses.stats(),
+        }
+    }
+
+    /// See [`crate::runtime_metrics`]
+    pub fn runtime_metrics(&mut self) -> RuntimeMetrics {
+        RuntimeMetrics {
+            collections: self.finishes.total,
+            collection_time: self.pauses.total,
+            collections_per_sec: self.finishes.rate(),
+        }
+    }
+
+    /// See [`crate::live_allocations`], but without collecting first
+    pub fn live_allocations(&self) -> Vec<AllocSummary> {
+        let mut live = Vec::new();
+        self.for_each_alloc(|nn| {
+            let gcb = unsafe { nn.as_ref() };
+            live.push(AllocSummary {
+                type_name: gcb.header.type_name,
+                root_count: gcb.header.root_count(),
+            });
+        });
+        live
+    }
+
+    /// Snapshots every live object, see [`crate::for_each_live`]
+    pub fn live_objects(&self) -> Vec<LiveObject> {
+        let mut live = Vec::with_capacity(self.len());
+        self.for_each_alloc(|nn| {
+            let header = &unsafe { nn.as_ref() }.header;
+            live.push(LiveObject {
+                type_name: header.type_name,
+                root_count: header.root_count(),
+                marked: header.marked(),
+                size: header.layout.size(),
+            });
+        });
+        live
+    }
+
+    /// See [`crate::histogram_by_type`]
+    #[cfg(feature = "std")]
+    pub fn histogram_by_type(&self) -> HashMap<&'static str, usize> {
+        let mut histogram = HashMap::new();
+        self.for_each_alloc(|nn| {
+            let header = &unsafe { nn.as_ref() }.header;
+            *histogram.entry(header.type_name).or_default() += 1;
+        });
+        histogram
+    }
+
+    /// See [`crate::consistency_check`]
+    pub fn consistency_check(&self) {
+        for (addr, nn) in self.old.iter().chain(self.young.iter()) {
+            let header = &unsafe { nn.as_ref() }.header;
+            let type_name = header.type_name;
+            assert_eq!(
+                *addr,
+                AllocAddr::from(nn.as_ptr()),
+                "the `GcBox<{type_name}>` at {addr} is listed under the wrong address",
+            );
+            assert_eq!(
+                header.context.id, self.id,
+                "the `GcBox<{type_name}>` at {addr} belongs to another context",
+            );
+            assert_eq!(
+                header.is_old(),
+                self.old.contains_key(addr),
+                "the `GcBox<{type_name}>` at {addr} is in the wrong generation",
+            );
+            assert!(
+                !(self.old.contains_key(addr) && self.young.contains_key(addr)),
+                "the `GcBox<{type_name}>` at {addr} is in both generations",
+            );
+            assert!(
+                header.root_count() <= i32::MAX as u32,
+                "the `GcBox<{type_name}>` at {addr} has a root count of {}, which has likely underflowed",
+                header.root_count(),
+            );
+        }
+        for addr in &self.remembered {
+            assert!(
+                self.old.contains_key(addr),
+                "{addr} is remembered, but isn't an old object",
+            );
+        }
+        for addr in &self.pinned {
+            assert!(
+                self.young.contains_key(addr),
+                "{addr} is pinned, but isn't a young object",
+            );
+        }
+
+        let mut total_bytes = 0;
+        self.for_each_alloc(|nn| total_bytes += unsafe { nn.as_ref() }.header.layout.size());
+        // Unreachable objects are only taken off the count once a batched sweep deallocates them
+        if let Some(sweep) = &self.sweeping {
+            let deallocated = match sweep.phase {
+                SweepPhase::Dealloc => sweep.next,
+                SweepPhase::Finalize | SweepPhase::Drop => 0,
+            };
+            for nn in &sweep.unreachable[deallocated..] {
+                total_bytes += unsafe { nn.as_ref() }.header.layout.size();
+            }
+        }
+        assert_eq!(
+            self.total_bytes, total_bytes,
+            "the byte count doesn't match the registered allocations",
+        );
+    }
+
+    /// See [`crate::heap_snapshot`]
+    pub fn heap_snapshot(&self) -> HeapSnapshot {
+        let mut objects = Vec::with_capacity(self.len());
+        let mut boxes = Vec::with_capacity(self.len());
+        self.for_each_alloc(|nn| {
+            let header = &unsafe { nn.as_ref() }.header;
+            objects.push(HeapObject {
+                addr: unsafe { GcBox::val(nn.as_ptr()) } as *const () as usize,
+                serial: header.serial(),
+                type_name: header.type_name,
+                root_count: header.root_count(),
+                marked: header.marked(),
+                old: header.is_old(),
+                size: header.layout.size(),
+            });
+            boxes.push(nn);
+        });
+
+        let traced = self.exclusive_borrows == 0;
+        let mut edges = Vec::new();
+        if traced {
+            let index: HashMap<AllocAddr, usize> = boxes
+                .iter()
+                .enumerate()
+                .map(|(i, nn)| (AllocAddr::from(nn.as_ptr()), i))
+                .collect();
+            for (from, nn) in boxes.iter().enumerate() {
+                let children = unsafe { tracer::record_children(&nn.as_ref().val) };
+                edges.extend(children.iter().map(|child| HeapEdge {
+                    from,
+                    to: index[&AllocAddr::from(child.as_ptr())],
+                }));
+            }
+        }
+        HeapSnapshot {
+            objects,
+            edges,
+            traced,
+        }
+    }
+
+    /// See [`crate::dump_dot`]
+    pub fn dump_dot(&self) -> String {
+        let mut dot = String::from("digraph gc {\n");
+        let trace_edges = self.exclusive_borrows == 0;
+        // Addresses change from run to run, but registration order doesn't
+        let node = |nn: NonNull<GcBox<dyn GcAble>>| match self.config.deterministic {
+            true => format!("#{}", unsafe { nn.as_ref() }.header.serial()),
+            false => AllocAddr::from(nn.as_ptr()).to_string(),
+        };
+        self.for_each_alloc(|nn| {
+            let gcb = unsafe { nn.as_ref() };
+            let addr = node(nn);
+            writeln!(
+                dot,
+                "    \"{addr}\" [label=\"{addr}\\nroot_count: {}\\nmarked: {}\"];",
+                gcb.header.root_count(),
+                gcb.header.marked(),
+            )
+            .unwrap();
+
+            if trace_edges {
+                for child in unsafe { tracer::record_children(&gcb.val) } {
+                    let child = node(child);
+                    writeln!(dot, "    \"{addr}\" -> \"{child}\";").unwrap();
+                }
+            }
+        });
+        dot.push_str("}\n");
+        dot
+    }
+}
@@ -1,36 +1,476 @@
+#![no_std]
 #![warn(unsafe_op_in_unsafe_fn)]
 
-use std::{
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
+use core::{
     alloc::Layout,
-    cell::{OnceCell, RefCell},
-    collections::{BTreeSet, HashMap, HashSet},
+    any::TypeId,
+    borrow::Borrow,
     fmt::Debug,
-    marker::PhantomData,
-    mem::MaybeUninit,
+    hash::{Hash, Hasher},
+    marker::{PhantomData, PhantomPinned},
+    mem::ManuallyDrop,
     num::NonZeroUsize,
     ops::{Deref, DerefMut},
-    ptr::{addr_of, NonNull},
-    sync::{atomic::AtomicBool, Mutex, Once, OnceLock},
-    thread::JoinHandle,
-    time::Duration,
+    pin::Pin,
+    ptr::{self, addr_of, addr_of_mut, NonNull},
+    sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+};
+
+use context::{ContextId, ContextInner};
+use sys::{
+    collections::{HashMap, HashSet},
+    panic::{self, AssertUnwindSafe},
+    sync::OnceLock,
 };
+use tracing_stats::Call;
+
+#[macro_use]
+mod sys;
 
 mod alloc_store;
+mod atomic;
+mod bounds;
+mod cell;
+mod config;
+mod context;
+mod deep_clone;
+mod driver;
+mod ephemeron;
+mod gc_ref;
 mod global_gc;
+mod identity;
+mod inspect;
+mod local;
+mod root_set;
+mod scope;
+#[cfg(feature = "serde")]
+mod serialize;
+mod slice;
+mod structural;
+mod tracer;
+mod tracing_stats;
+mod uninit;
+mod unsize;
+mod weak;
+#[cfg(feature = "std")]
+mod weak_table;
+
+pub use atomic::AtomicGc;
+pub use cell::{BorrowError, BorrowMutError, GcCell, GcCellRef, GcMut, GcMutRef};
+pub use config::{GcBuilder, GcConfig, GcConfigError};
+pub use context::{GcBatch, GcContext, GcError};
+pub use deep_clone::{DeepClone, DeepCloner};
+#[cfg(feature = "background-thread")]
+pub use driver::ThreadDriver;
+pub use driver::{Collector, CollectorDriver};
+pub use ephemeron::Ephemeron;
+/// Derives [`GcAble`] for a struct or enum, see its docs
+pub use gc_derive::GcAble;
+/// Derives [`Clone`] for a struct or enum, choosing between sharing and copying each `Gc<_>` field
+///
+/// Like `#[derive(Clone)]`, every field is cloned with [`Clone::clone`] by default, so a `Gc<_>`
+/// field of the clone points to the same object as the original's. A `Gc<_>` field marked
+/// `#[gc(clone = "deep")]` is cloned with [`Gc::clone_deep`] instead, giving the clone its own copy
+/// of the object and everything reachable from it:
+///
+/// ```
+/// use gc::{Gc, GcAble, GcClone};
+///
+/// #[derive(GcAble, GcClone)]
+/// struct Document {
+///     // Shared by every copy of the document
+///     style: Gc<String>,
+///     // Each copy gets its own
+///     #[gc(clone = "deep")]
+///     body: Gc<String>,
+/// }
+///
+/// let original = Document {
+///     style: Gc::new("serif".to_string()),
+///     body: Gc::new("Hello".to_string()),
+/// };
+/// let copy = original.clone();
+/// assert!(Gc::ptr_eq(&copy.style, &original.style));
+/// assert!(!Gc::ptr_eq(&copy.body, &original.body));
+/// assert_eq!(*copy.body, "Hello");
+/// ```
+///
+/// Each deep field is copied on its own, so an object reachable from two deep fields is copied
+/// twice. `#[gc(clone = "shared")]` spells out the default. The derived impl requires every type
+/// parameter to be `Clone`, which `#[gc(bound = "...")]` replaces the same way as for
+/// [`GcAble`], including for a derived `GcAble` impl of the same type.
+pub use gc_derive::GcClone;
+/// Derives [`LocalGcAble`] for a struct or enum, the same way as [`GcAble`](derive@GcAble)
+///
+/// ```
+/// use gc::{force_collect_local, LocalGc, LocalGcAble, LocalGcCell};
+///
+/// #[derive(LocalGcAble)]
+/// struct Node {
+///     id: u32,
+///     next: LocalGcCell<Option<LocalGc<Node>>>,
+/// }
+///
+/// let a = LocalGc::new(Node { id: 1, next: LocalGcCell::new(None) });
+/// let b = LocalGc::new(Node { id: 2, next: LocalGcCell::new(Some(a.clone())) });
+/// a.next.set(Some(b.clone()));
+/// drop(b);
+/// force_collect_local();
+/// assert_eq!(a.next.borrow().as_ref().unwrap().id, 2);
+/// ```
+pub use gc_derive::LocalGcAble;
+pub use gc_ref::GcRef;
+pub use identity::GcIdentityKey;
+pub use inspect::{
+    AllocSummary, GcStats, HeapEdge, HeapObject, HeapSnapshot, LiveObject, PauseStats,
+    RuntimeMetrics,
+};
+pub use local::{force_collect_local, LocalGc, LocalGcAble, LocalGcCell};
+pub use root_set::RootSet;
+pub use structural::{StructuralCmp, StructuralEq, StructuralHash, StructuralHasher};
+#[cfg(feature = "debug-tracing")]
+pub use tracing_stats::{assert_root_counts_balance, tracing_stats, TracingStats};
+pub use weak::WeakGc;
+#[cfg(feature = "std")]
+pub use weak_table::WeakTable;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::GcBox;
+
+    /// Overwrites the root count of the object `gc` points to, so that overflowing it can be
+    /// tested without creating billions of roots
+    ///
+    /// # Safety
+    /// The count must be put back to what it was before anything else changes it
+    pub unsafe fn set_root_count<T: ?Sized + crate::GcAble>(gc: &crate::Gc<T>, count: u32) {
+        let gcb = unsafe { gc.gcbox.as_ref() };
+        gcb.header
+            .root_count
+            .store(count, core::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Registers the object `gc` points to with its context again, so that the error for
+    /// registering a box twice can be tested
+    pub fn register_again<T: ?Sized + crate::GcAble>(gc: &crate::Gc<T>) {
+        let gcb = unsafe { gc.gcbox.as_ref() };
+        gcb.header.context.lock().register_gcbox(gc.erased())
+    }
+}
 
 /// Makes sure the global garbage collector is initialized, and initializes it if is isn't
 pub fn init_gc() {
     let _ = global_gc::lock();
 }
 
+/// Whether the global garbage collector has been initialized, either by [`init_gc`] or
+/// [`init_gc_with`], or by being used for the first time
+pub fn is_initialized() -> bool {
+    global_gc::is_init()
+}
+
+/// Panics if the global garbage collector hasn't been initialized yet, see [`is_initialized`]
+///
+/// For libraries which require the embedder to call [`init_gc`] or [`init_gc_with`] up front,
+/// rather than have the global Gc initialized with the default config by whatever uses it first
+///
+/// ```
+/// use std::panic;
+///
+/// assert!(!gc::is_initialized());
+/// let payload = panic::catch_unwind(gc::assert_initialized).unwrap_err();
+/// let msg = payload.downcast_ref::<&str>().unwrap();
+/// assert!(msg.contains("call `gc::init_gc` or `gc::init_gc_with` first"), "{msg}");
+///
+/// gc::init_gc();
+/// assert!(gc::is_initialized());
+/// gc::assert_initialized();
+/// // Initializing again changes nothing
+/// gc::init_gc();
+/// assert!(gc::is_initialized());
+/// ```
+#[track_caller]
+pub fn assert_initialized() {
+    assert!(
+        is_initialized(),
+        "the global Gc isn't initialized, call `gc::init_gc` or `gc::init_gc_with` first",
+    );
+}
+
+/// Initializes the global garbage collector with `config`, which is usually built by [`GcBuilder`]
+///
+/// Fails if the global Gc was already initialized, which also happens the first time it's used
+pub fn init_gc_with(config: GcConfig) -> Result<(), GcConfigError> {
+    match global_gc::init_with(config) {
+        true => Ok(()),
+        false => Err(GcConfigError::AlreadyInitialized),
+    }
+}
+
+/// Permanently shuts down the global context, see [`GcContext::shutdown`]
+///
+/// `Gc::new` panics from then on, while [`Gc::try_new`] returns an error. The global context isn't
+/// initialized again. Every `Gc<_>` can still be used and dropped, including ones which are only
+/// dropped as the program exits, such as by thread-local destructors.
+pub fn shutdown() {
+    GcContext::global().shutdown()
+}
+
 /// Makes sure all memory that can be freed at the moment is freed
+///
+/// If a collected object's [`Finalize::finalize`] or `Drop` panics, every other unreachable object
+/// is still reclaimed before the first such panic is resumed. If a [`GcAble::mark`] panics, the
+/// collection is abandoned and nothing is freed. Either way the Gc stays usable afterwards.
+///
+/// Threads calling this at the same time share a single collection, see
+/// [`GcContext::force_collect`].
 pub fn force_collect() {
-    global_gc::lock().mark_sweep()
+    GcContext::global().force_collect()
+}
+
+/// Runs full collections in the global context until one doesn't free anything, returning the
+/// number of objects freed in total, see [`GcContext::force_collect_until_stable`]
+pub fn force_collect_until_stable() -> usize {
+    GcContext::global().force_collect_until_stable()
+}
+
+/// Gives the global context's free list back to the allocator, returning the number of bytes
+/// released, see [`GcContext::trim`]
+pub fn trim() -> usize {
+    GcContext::global().trim()
+}
+
+/// Runs the global context's queued finalizers on the calling thread, returning how many ran, see
+/// [`GcContext::flush_finalizers`]
+pub fn flush_finalizers() -> usize {
+    GcContext::global().flush_finalizers()
+}
+
+/// Asks the global context's collector to collect everything it can as soon as possible, see
+/// [`GcContext::request_urgent_collection`]
+///
+/// This is a plain `fn()`, so it can be handed to a host directly as a memory pressure callback
+pub fn request_urgent_collection() {
+    GcContext::global().request_urgent_collection()
+}
+
+/// Does one bounded unit of collection work in the global context, see [`GcContext::collect_step`]
+pub fn collect_step() -> bool {
+    GcContext::global().collect_step()
+}
+
+/// Stops all collection in the global context until [`enable`] is called, see
+/// [`GcContext::disable`]
+pub fn disable() {
+    GcContext::global().disable()
+}
+
+/// Undoes a call to [`disable`], see [`GcContext::enable`]
+pub fn enable() {
+    GcContext::global().enable()
+}
+
+/// Runs `f` with collection in the global context disabled, see [`GcContext::build`]
+pub fn build<R>(f: impl FnOnce(&GcContext) -> R) -> R {
+    GcContext::global().build(f)
+}
+
+/// Runs `f` inside a [`GcBatch`] of the global context, see [`GcContext::batch`]
+pub fn with_batch<R>(f: impl FnOnce() -> R) -> R {
+    let _batch = GcContext::global().batch();
+    f()
+}
+
+/// Keeps the object of every `Gc<_>` in `gcs` alive while `f` runs, along with everything
+/// reachable from it
+///
+/// Like calling [`Gc::root`] on each of them, for `Gc<_>`s of different types which may not be
+/// roots, such as ones read out of other objects while walking a graph. A `&Gc<T>` of any `T`
+/// coerces to a `&dyn AsAnyGc`, so `gc::with_roots(&[&a, &b], || ..)` roots `a` and `b`. The roots
+/// are released even if `f` panics.
+///
+/// ```
+/// use gc::{Gc, GcCell};
+///
+/// let (a, b) = (Gc::new(1u32), Gc::new("b".to_string()));
+/// let cell = Gc::new(GcCell::new(Some(a.clone())));
+/// gc::with_roots(&[&a, &b], || {
+///     // Rooting is by object, so changing what the `Gc<_>`s are stored in doesn't affect it
+///     cell.set(None);
+///     gc::force_collect();
+/// });
+/// assert_eq!((*a, b.as_str()), (1, "b"));
+/// ```
+pub fn with_roots<R>(gcs: &[&dyn AsAnyGc], f: impl FnOnce() -> R) -> R {
+    let _roots: Vec<Gc<dyn GcAble>> = gcs.iter().map(|gc| gc.any_root()).collect();
+    f()
+}
+
+/// A `Gc<_>` of any type, so that [`with_roots`] can be given `Gc<_>`s of different types
+///
+/// Only implemented by `Gc<_>`
+pub trait AsAnyGc: as_any_gc::Sealed {}
+
+impl<T: ?Sized + GcAble> AsAnyGc for Gc<T> {}
+
+mod as_any_gc {
+    use crate::{Gc, GcAble};
+
+    pub trait Sealed {
+        /// A new root `Gc<_>` pointing to the same object
+        fn any_root(&self) -> Gc<dyn GcAble>;
+    }
+
+    impl<T: ?Sized + GcAble> Sealed for Gc<T> {
+        fn any_root(&self) -> Gc<dyn GcAble> {
+            unsafe { Gc::from_gcbox(self.erased()) }
+        }
+    }
+}
+
+/// Runs `f`, then frees every object this thread allocated in the global context while `f` ran,
+/// apart from those still reachable from a root, see [`GcContext::scope`]
+///
+/// # Safety
+/// See [`GcContext::scope`]
+pub unsafe fn scope<R>(f: impl FnOnce() -> R) -> R {
+    unsafe { GcContext::global().scope(f) }
+}
+
+/// Blocks until the global context's collector finishes a collection which started after this was
+/// called, see [`GcContext::wait_for_collection`]
+///
+/// ```
+/// use gc::Gc;
+///
+/// let gc = Gc::new(1u32);
+/// let weak = Gc::downgrade(&gc);
+/// drop(gc);
+/// gc::wait_for_collection();
+/// assert!(weak.upgrade().is_none());
+/// assert_eq!(gc::stats().live_allocations, 0);
+/// ```
+pub fn wait_for_collection() {
+    GcContext::global().wait_for_collection()
+}
+
+/// Returns the global context's heap graph in Graphviz DOT format
+///
+/// Each object is a node labeled with its address, root count and whether it was marked by the last
+/// collection, and each `Gc<_>` inside an object is an edge. Edges are left out if a `GcRefMut` is
+/// alive, since the values can't be traced then.
+pub fn dump_dot() -> String {
+    global_gc::lock().dump_dot()
+}
+
+/// Returns every object in the global context along with the `Gc<_>`s stored in each of them,
+/// for tools which inspect the heap
+///
+/// Like [`dump_dot`], but as data instead of text. Nothing is collected first, so unreachable
+/// objects which haven't been collected yet are included too.
+pub fn heap_snapshot() -> HeapSnapshot {
+    global_gc::lock().heap_snapshot()
+}
+
+/// Returns statistics about the global context's heap
+pub fn stats() -> GcStats {
+    global_gc::lock().stats()
+}
+
+/// Returns how much time the global context's collector has taken
+pub fn runtime_metrics() -> RuntimeMetrics {
+    global_gc::lock().runtime_metrics()
+}
+
+/// Calls `f` on a snapshot of every object in the global context
+///
+/// The snapshot is taken under the context's lock, which is released before `f` is called, so `f`
+/// may use the Gc freely
+///
+/// ```
+/// use gc::Gc;
+///
+/// let objects = (Gc::new(1u32), Gc::new(2u16), Gc::new(3u64));
+/// let (mut count, mut bytes, mut names, mut allocated) = (0, 0, Vec::new(), Vec::new());
+/// gc::for_each_live(|obj| {
+///     count += 1;
+///     bytes += obj.size();
+///     assert_eq!(obj.root_count(), 1);
+///     names.push(obj.type_name().to_string());
+///     // Allocating from the callback doesn't deadlock
+///     allocated.push(Gc::new(obj.size() as u64));
+/// });
+///
+/// let stats = gc::stats();
+/// assert_eq!(count, 3);
+/// assert_eq!(stats.live_allocations, count + allocated.len());
+/// assert!(bytes > 0 && bytes < stats.total_bytes);
+/// assert!(names.iter().any(|name| name.contains("u16")));
+/// ```
+pub fn for_each_live(f: impl FnMut(LiveObject)) {
+    let live = global_gc::lock().live_objects();
+    live.into_iter().for_each(f)
+}
+
+/// Counts the objects in the global context by the `std::any::type_name` of their values
+///
+/// Nothing is collected first, so unreachable objects which haven't been collected yet are counted
+/// too
+///
+/// ```
+/// use gc::Gc;
+///
+/// let nums: Vec<_> = (0..3u32).map(Gc::new).collect();
+/// let wide = [Gc::new(1u64), Gc::new(2u64)];
+/// let histogram = gc::histogram_by_type();
+/// assert_eq!(histogram["u32"], 3);
+/// assert_eq!(histogram["u64"], 2);
+/// assert_eq!(histogram.len(), 2);
+///
+/// drop(nums);
+/// gc::force_collect();
+/// assert_eq!(gc::histogram_by_type().get("u32"), None);
+/// # drop(wide);
+/// ```
+#[cfg(feature = "std")]
+pub fn histogram_by_type() -> HashMap<&'static str, usize> {
+    global_gc::lock().histogram_by_type()
+}
+
+/// Checks the global context's bookkeeping, panicking with a description of the first
+/// inconsistency found
+///
+/// Meant for tests, which can run this between operations to catch corruption close to its cause
+pub fn consistency_check() {
+    global_gc::lock().consistency_check()
+}
+
+/// Collects the global context, then returns a summary of every allocation that is still live
+pub fn live_allocations() -> Vec<AllocSummary> {
+    let mut gc = global_gc::lock();
+    gc.mark_sweep();
+    gc.live_allocations()
+}
+
+/// Collects the global context, then panics with a list of every allocation that is still live
+pub fn assert_no_leaks() {
+    inspect::assert_no_leaks(live_allocations())
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct AllocAddr(NonZeroUsize);
 
+impl core::fmt::Display for AllocAddr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
 impl<T: ?Sized + GcAble> From<*const GcBox<T>> for AllocAddr {
     fn from(value: *const GcBox<T>) -> Self {
         Self((value as *const () as usize).try_into().unwrap())
@@ -43,104 +483,875 @@ impl<T: ?Sized + GcAble> From<*mut GcBox<T>> for AllocAddr {
     }
 }
 
-struct AllocInfo {
-    marked: bool,
-}
-
 /// Stores all the information about the GC
 struct GcAlloc {
-    allocs: HashMap<AllocAddr, NonNull<GcBox<dyn GcAble>>>,
-    collection_handle: JoinHandle<()>,
+    id: ContextId,
+    /// Objects which have survived `GcConfig::promotion_threshold` collections
+    old: HashMap<AllocAddr, NonNull<GcBox<dyn GcAble>>>,
+    /// Objects which haven't been promoted to `old` yet
+    young: HashMap<AllocAddr, NonNull<GcBox<dyn GcAble>>>,
+    /// Old objects which may point to young objects, which are traced during minor collections
+    ///
+    /// Pruned after every collection
+    remembered: HashSet<AllocAddr>,
+    /// Young objects which may be pointed to by old objects that aren't in `remembered`, which are
+    /// roots during minor collections, see [`GcAlloc::shade`]
+    ///
+    /// Kept until they're promoted or collected
+    pinned: HashSet<AllocAddr>,
+    /// The number of collections started so far
+    collections: u64,
+    /// The value `collections` had when the last collection to finish was started
+    last_finished: u64,
+    /// The number of `ContextInner::force_collect` calls made before the last full collection they
+    /// ran started, which that collection already did the work of
+    force_collected: u64,
+    /// Set while a collection is being marked
+    marking: Option<Marking>,
+    /// Set while the unreachable objects of a finished collection are being reclaimed
+    sweeping: Option<Sweep>,
+    /// Objects which are marked but whose children may not be yet
+    grey: Vec<NonNull<GcBox<dyn GcAble>>>,
+    /// The ephemerons reached while marking whose keys haven't been marked yet, see [`Ephemeron`]
+    ephemerons: Vec<tracer::PendingEphemeron>,
+    /// Unreachable objects whose finalizer hasn't run yet, each rooted once by the queue, see
+    /// `GcConfig::defer_finalizers`
+    finalize_queue: Vec<NonNull<GcBox<dyn GcAble>>>,
+    /// The number of live `GcRefMut`s, `GcMutRef`s and `DeepCloner`s, collection is skipped while
+    /// there are any
+    exclusive_borrows: usize,
+    /// The number of live `GcBatch`es, the collector doesn't collect by itself while there are any
+    batches: usize,
+    /// The objects of every live `RootSet`, which are all roots
+    root_sets: Vec<Arc<root_set::RootList>>,
+    /// The number of `GcContext::disable`s which haven't been undone, nothing is collected while
+    /// there are any
+    disabled: usize,
+    /// The sum of the sizes of every registered allocation
+    total_bytes: usize,
+    /// How long the most recent collections held the lock for
+    pauses: inspect::Pauses,
+    /// When collections finished
+    finishes: inspect::Finishes,
+    /// The value of `total_bytes` right after the last collection
+    survived_bytes: usize,
+    /// The number of objects registered since the last collection
+    allocs_since_collection: usize,
+    /// The number of objects ever registered
+    registrations: u64,
+    /// Set once the collector should wake up and collect
+    collection_requested: bool,
+    /// Set by `GcContext::request_urgent_collection` until a major collection finishes
+    urgent: bool,
+    config: GcConfig,
+    #[cfg(feature = "background-thread")]
+    collection_handle: Option<std::thread::JoinHandle<()>>,
 }
 
 unsafe impl Send for GcAlloc {}
 
+/// A collection which is being marked, possibly incrementally
+struct Marking {
+    major: bool,
+}
+
+/// The objects a finished collection found unreachable, which are being reclaimed in batches, see
+/// `GcConfig::max_sweep_batch`
+///
+/// They're already gone from the allocation lists, and nothing reachable points to them, so the
+/// lock can be released between batches. No new collection starts until they're all reclaimed.
+struct Sweep {
+    unreachable: Vec<NonNull<GcBox<dyn GcAble>>>,
+    phase: SweepPhase,
+    /// The index of the next object to go through `phase`
+    next: usize,
+    /// The first panic from a finalizer or `Drop`, resumed once every object is reclaimed
+    panicked: Option<Box<dyn core::any::Any + Send>>,
+    /// Whether this finishes a collection, rather than freeing a [`GcContext::scope`]
+    collected: bool,
+}
+
+/// The phases of reclaiming unreachable objects, see `GcAlloc::finish_collection`
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SweepPhase {
+    Finalize,
+    Drop,
+    Dealloc,
+}
+
 impl GcAlloc {
-    fn collection_loop() -> ! {
+    /// Collects `ctx` until it's dropped
+    ///
+    /// Sleeps until a collection is requested, or for at most `GcConfig::max_interval`
+    #[cfg(feature = "background-thread")]
+    fn collection_loop(ctx: alloc::sync::Weak<ContextInner>) {
         loop {
-            std::thread::sleep(Duration::from_millis(1));
-            global_gc::lock().mark_sweep()
+            let Some(ctx) = ctx.upgrade() else {
+                return;
+            };
+            let gc = ctx.lock();
+            // Only an explicit request can wake a deterministic collector up
+            let interval = match gc.config.deterministic {
+                true => core::time::Duration::MAX,
+                false => gc.config.max_interval,
+            };
+            let mut gc = gc.wait_timeout_while(&ctx.wake, interval, |gc| {
+                !ctx.is_shut_down() && (gc.collection_paused() || !gc.has_work())
+            });
+            if ctx.is_shut_down() {
+                return;
+            }
+            if gc.collection_paused() {
+                continue;
+            }
+            // A panic from user code has already been reported by the panic hook, and shouldn't stop
+            // the collector
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| gc.collect()));
+            drop(gc);
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| ctx.flush_finalizers()));
+            ctx.collected.notify_all();
+            // Let other threads take the lock between incremental slices
+            std::thread::yield_now();
         }
     }
-    pub fn new() -> Self {
+    /// Creates an empty `GcAlloc`, its collector is started by its `GcContext`
+    pub fn new(id: ContextId, config: GcConfig) -> Self {
         GcAlloc {
-            allocs: HashMap::new(),
-            collection_handle: std::thread::spawn(|| Self::collection_loop()),
+            id,
+            old: sys::collections::map_with_capacity(config.capacity),
+            young: sys::collections::map_with_capacity(config.capacity),
+            remembered: HashSet::new(),
+            pinned: HashSet::new(),
+            collections: 0,
+            last_finished: 0,
+            force_collected: 0,
+            marking: None,
+            sweeping: None,
+            grey: Vec::with_capacity(config.capacity),
+            ephemerons: Vec::new(),
+            finalize_queue: Vec::new(),
+            exclusive_borrows: 0,
+            batches: 0,
+            root_sets: Vec::new(),
+            disabled: 0,
+            total_bytes: 0,
+            pauses: inspect::Pauses::default(),
+            finishes: inspect::Finishes::new(),
+            survived_bytes: 0,
+            allocs_since_collection: 0,
+            registrations: 0,
+            collection_requested: false,
+            urgent: false,
+            config,
+            #[cfg(feature = "background-thread")]
+            collection_handle: None,
         }
     }
 
     /// Gives this `GcAlloc` control over the given `GcBox`, which is needed for it to be collected
-    pub fn register_gcbox<T: Sized + GcAble>(&mut self, gcb: &mut GcBox<T>) {
-        let addr = AllocAddr::from(gcb as *mut _);
+    ///
+    /// Only reachable through the context's lock, which is what makes updating the allocation lists
+    /// exclusive
+    ///
+    /// Panics if `nn` is already registered, without changing anything
+    pub fn register_gcbox(&mut self, nn: NonNull<GcBox<dyn GcAble>>) {
+        let addr = AllocAddr::from(nn.as_ptr());
+        if self.old.contains_key(&addr) || self.young.contains_key(&addr) {
+            let type_name = unsafe { nn.as_ref() }.header.type_name;
+            panic!("the `GcBox<{type_name}>` at {addr} was registered twice");
+        }
+        unsafe { nn.as_ref() }
+            .header
+            .serial
+            .store(self.registrations, Ordering::Relaxed);
+        scope::registered(self.id, addr, self.registrations);
+        self.registrations += 1;
+        self.young.insert(addr, nn);
+        tracer::registered(addr);
+        // Its children may not have been marked yet
+        if self.marking.is_some() {
+            unsafe { nn.as_ref() }.header.mark();
+            self.grey.push(nn);
+        }
+        self.total_bytes += unsafe { nn.as_ref() }.header.layout.size();
+
+        self.allocs_since_collection += 1;
+        if self.config.deterministic {
+            return;
+        }
+        if self.allocs_since_collection >= self.config.alloc_watermark || self.outgrew_survivors() {
+            self.collection_requested = true;
+        }
     }
 
-    /// Mark then sweep
-    pub fn mark_sweep(&mut self) {
-        // Unmark all
-        for (_, nn) in &self.allocs {
-            let gcb = unsafe { nn.as_ref() };
-            gcb.header.unmark()
+    /// Forgets `nn` without freeing it, so that its value can be moved out
+    pub fn unregister_gcbox(&mut self, nn: NonNull<GcBox<dyn GcAble>>) {
+        let addr = AllocAddr::from(nn.as_ptr());
+        let removed = self.young.remove(&addr).or_else(|| self.old.remove(&addr));
+        assert!(removed.is_some(), "the `GcBox` at {addr} isn't registered");
+        self.remembered.remove(&addr);
+        self.pinned.remove(&addr);
+        self.grey
+            .retain(|grey| AllocAddr::from(grey.as_ptr()) != addr);
+        self.total_bytes -= unsafe { nn.as_ref() }.header.layout.size();
+        tracer::freed(addr);
+    }
+
+    /// Whether the collector shouldn't collect by itself right now
+    fn collection_paused(&self) -> bool {
+        (self.batches > 0 && !self.urgent) || self.disabled > 0
+    }
+
+    /// Whether the heap has grown by `GcConfig::growth_factor` since the last collection
+    fn outgrew_survivors(&self) -> bool {
+        let Some(factor) = self.config.growth_factor else {
+            return false;
+        };
+        let base = self.survived_bytes.max(config::MIN_GROWTH_BASE);
+        self.total_bytes as f64 > base as f64 * factor
+    }
+
+    /// Must be called after a `Gc<_>` is stored in the value of `gcb` after it was registered
+    ///
+    /// If `gcb` is old it may now point to young objects, which minor collections must know about
+    ///
+    /// If a collection is being marked incrementally, `gcb` may also have already been scanned
+    pub fn write_barrier(&mut self, gcb: NonNull<GcBox<dyn GcAble>>) {
+        if unsafe { gcb.as_ref() }.header.is_old() {
+            self.remembered.insert(AllocAddr::from(gcb.as_ptr()));
+        }
+        if self.marking.is_some() {
+            self.grey.push(gcb);
+        }
+    }
+
+    /// Must be called before a `Gc<_>` pointing to `gcb` is stored in a registered object which
+    /// isn't known, such as by [`crate::GcCell::set`]
+    pub fn shade(&mut self, gcb: NonNull<GcBox<dyn GcAble>>) {
+        let header = &unsafe { gcb.as_ref() }.header;
+        if !header.is_old() {
+            self.pinned.insert(AllocAddr::from(gcb.as_ptr()));
+        }
+        if self.marking.is_some() && !header.marked() {
+            header.mark();
+            self.grey.push(gcb);
+        }
+    }
+
+    /// The number of registered allocations
+    fn len(&self) -> usize {
+        self.old.len() + self.young.len()
+    }
+
+    /// Calls `f` on every registered allocation, in registration order if
+    /// `GcConfig::deterministic` is set
+    fn for_each_alloc(&self, f: impl FnMut(NonNull<GcBox<dyn GcAble>>)) {
+        let allocs = self.old.values().chain(self.young.values()).copied();
+        self.for_each_in_order(allocs, f)
+    }
+
+    /// Calls `f` on each of `allocs`, in registration order if `GcConfig::deterministic` is set
+    fn for_each_in_order(
+        &self,
+        allocs: impl Iterator<Item = NonNull<GcBox<dyn GcAble>>>,
+        f: impl FnMut(NonNull<GcBox<dyn GcAble>>),
+    ) {
+        if !self.config.deterministic {
+            return allocs.for_each(f);
+        }
+        let mut allocs: Vec<_> = allocs.collect();
+        allocs.sort_by_key(|nn| unsafe { nn.as_ref() }.header.serial());
+        allocs.into_iter().for_each(f)
+    }
+
+    /// Runs a minor or major collection, depending on `GcConfig::major_interval`
+    ///
+    /// If `GcConfig::incremental` is set, this only runs a single slice of the collection. If
+    /// `GcConfig::max_sweep_batch` is set and the last collection's unreachable objects haven't
+    /// all been reclaimed, this only reclaims the next batch of them.
+    ///
+    /// Returns `true` if this finished a collection
+    pub fn collect(&mut self) -> bool {
+        // An urgent collection reclaims the rest of the sweep before starting
+        if self.sweeping.is_some() && !self.urgent {
+            let pause = inspect::PauseStart::now();
+            let done = self.sweep_step(self.sweep_batch());
+            self.pauses.record(pause);
+            return done;
+        }
+        if self.urgent {
+            return self.collect_urgently();
+        }
+        match self.config.step_budget {
+            Some(budget) => self.collect_slice(budget),
+            None => self.collect_generations(self.next_is_major()) && self.sweeping.is_none(),
+        }
+    }
+
+    /// Whether a collection was requested or is partway done
+    fn has_work(&self) -> bool {
+        // A deterministic context's collections are driven by whoever started them, never by its
+        // driver
+        if self.config.deterministic {
+            return self.collection_requested;
+        }
+        self.collection_requested || self.marking.is_some() || self.sweeping.is_some()
+    }
+
+    /// The most unreachable objects the collector reclaims each time it takes the lock
+    fn sweep_batch(&self) -> usize {
+        match self.urgent {
+            true => usize::MAX,
+            false => self.config.max_sweep_batch.unwrap_or(usize::MAX),
+        }
+    }
+
+    /// Finishes the major collection in progress, or runs a whole new one
+    fn collect_urgently(&mut self) -> bool {
+        let done = match &self.marking {
+            Some(marking) if marking.major => self.collect_slice(usize::MAX),
+            _ => self.collect_generations(true),
+        };
+        if done {
+            self.urgent = false;
+        }
+        done
+    }
+
+    fn next_is_major(&self) -> bool {
+        self.collections.is_multiple_of(self.config.major_interval)
+    }
+
+    /// Mark then sweep every generation, reclaiming every unreachable object before returning
+    ///
+    /// Returns `false` if the collection was skipped
+    pub fn mark_sweep(&mut self) -> bool {
+        let collected = self.collect_generations(true);
+        self.sweep_step(usize::MAX);
+        collected
+    }
+
+    /// Runs `mark_sweep` until it doesn't free anything, returning the number of objects freed
+    pub fn mark_sweep_until_stable(&mut self) -> usize {
+        let mut freed = 0;
+        loop {
+            let before = self.len();
+            self.mark_sweep();
+            match before - self.len() {
+                0 => return freed,
+                n => freed += n,
+            }
+        }
+    }
+
+    /// Scans up to `budget` objects of the current collection, starting a new one if there isn't one
+    ///
+    /// Returns `true` if this finished the collection
+    pub fn collect_slice(&mut self, budget: usize) -> bool {
+        // A `GcRefMut` may be writing to a value the collector would read while tracing
+        if self.exclusive_borrows > 0 {
+            return false;
+        }
+        if self.disabled > 0 {
+            self.collection_requested = true;
+            return false;
+        }
+        let pause = inspect::PauseStart::now();
+        if self.marking.is_none() {
+            self.start_marking(self.next_is_major());
+        }
+        let mut done = self.mark_step(budget);
+        if done {
+            done = self.finish_collection();
+        }
+        self.pauses.record(pause);
+        done
+    }
+
+    /// Mark then sweep the young generation, and the old generation too if `major`
+    ///
+    /// Abandons any incremental collection which is in progress
+    ///
+    /// Returns `false` if the collection was skipped
+    fn collect_generations(&mut self, major: bool) -> bool {
+        // A `GcRefMut` may be writing to a value the collector would read while tracing
+        if self.exclusive_borrows > 0 {
+            return false;
+        }
+        if self.disabled > 0 {
+            self.collection_requested = true;
+            return false;
         }
+        let pause = inspect::PauseStart::now();
+        self.start_marking(major);
+        self.mark_step(usize::MAX);
+        self.finish_collection();
+        self.pauses.record(pause);
+        true
+    }
+
+    /// Calls `f` on every object in the generations being collected
+    fn for_each_collected(&self, major: bool, f: impl FnMut(NonNull<GcBox<dyn GcAble>>)) {
+        let old = major.then_some(&self.old);
+        let allocs = self
+            .young
+            .values()
+            .chain(old.into_iter().flat_map(|old| old.values()))
+            .copied();
+        self.for_each_in_order(allocs, f)
+    }
 
-        // Mark from stack
-        for (_, nn) in &self.allocs {
+    /// Marks every unmarked root being collected and makes it grey, which are the objects with a
+    /// root count and the objects in a `RootSet`
+    fn grey_roots(&self, major: bool, grey: &mut Vec<NonNull<GcBox<dyn GcAble>>>) {
+        // A `RootSet` roots an object before taking it out of its list and unroots it after putting
+        // it in, both while holding the list's lock, so holding every list's lock throughout means
+        // each of their objects is found one way or the other
+        let lists: Vec<_> = self.root_sets.iter().map(|list| list.lock()).collect();
+        self.for_each_collected(major, |nn| {
             let gcb = unsafe { nn.as_ref() };
-            if gcb.header.is_rooted() {
+            if gcb.header.is_rooted() && !gcb.header.marked() {
                 gcb.header.mark();
-                unsafe { gcb.val.mark() }
+                grey.push(nn);
+            }
+        });
+        for &nn in lists.iter().flat_map(|list| list.iter()) {
+            // Old objects are all assumed to be alive during minor collections
+            let collected = major || self.young.contains_key(&AllocAddr::from(nn.as_ptr()));
+            let header = &unsafe { nn.as_ref() }.header;
+            if collected && !header.marked() {
+                header.mark();
+                grey.push(nn);
+            }
+        }
+    }
+
+    /// Unmarks everything being collected, and makes the roots grey
+    ///
+    /// Reclaims what's left of the previous collection's unreachable objects first
+    fn start_marking(&mut self, major: bool) {
+        self.sweep_step(usize::MAX);
+        self.allocs_since_collection = 0;
+        self.collection_requested = false;
+        self.collections += 1;
+        self.marking = Some(Marking { major });
+
+        let mut grey = core::mem::take(&mut self.grey);
+        grey.clear();
+        self.ephemerons.clear();
+        self.for_each_collected(major, |nn| unsafe { nn.as_ref() }.header.unmark());
+        self.grey_roots(major, &mut grey);
+        // Old objects are all assumed to be alive, so only their references into the young
+        // generation matter
+        if !major {
+            grey.extend(self.remembered.iter().map(|addr| self.old[addr]));
+            for addr in &self.pinned {
+                let nn = self.young[addr];
+                let header = &unsafe { nn.as_ref() }.header;
+                if !header.marked() {
+                    header.mark();
+                    grey.push(nn);
+                }
+            }
+        }
+        self.grey = grey;
+    }
+
+    /// Scans up to `budget` grey objects, returns `true` once there are none left
+    ///
+    /// If a `GcAble::mark` panics, the collection is abandoned before the panic is resumed, since
+    /// reachable objects may not have been marked
+    fn mark_step(&mut self, budget: usize) -> bool {
+        let major = self.marking.as_ref().unwrap().major;
+        let (id, grey, ephemerons) = (self.id, &mut self.grey, &mut self.ephemerons);
+        match panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+            tracer::mark_in(id, !major, grey, ephemerons, budget)
+        })) {
+            Ok(done) => done,
+            Err(payload) => {
+                self.marking = None;
+                self.grey.clear();
+                self.ephemerons.clear();
+                panic::resume_unwind(payload)
             }
         }
+    }
+
+    /// Finishes marking, then sweeps
+    ///
+    /// Unreachable objects are reclaimed in three phases, each of which completes for every
+    /// unreachable object before the next one starts:
+    /// 1. [`Finalize::finalize`] is called on every unreachable object that has a finalizer
+    /// 2. Every unreachable object's value is dropped
+    /// 3. Every unreachable object's memory is deallocated
+    ///
+    /// Only `GcConfig::max_sweep_batch` of them are reclaimed before this returns, see
+    /// [`GcAlloc::sweep_step`]. Returns `true` if they all were.
+    fn finish_collection(&mut self) -> bool {
+        let major = self.marking.as_ref().unwrap().major;
+
+        // Objects may have been rooted since marking started
+        let mut grey = core::mem::take(&mut self.grey);
+        self.grey_roots(major, &mut grey);
+        self.grey = grey;
+        self.mark_step(usize::MAX);
+        if self.config.defer_finalizers {
+            self.queue_finalizers(major);
+        }
+        // The keys of the ephemerons left are unreachable, so their values weren't marked
+        self.ephemerons.clear();
+        self.marking = None;
 
-        // Deallocate & Drop unmarked
-        self.allocs.retain(|_, nn| {
-            let ptr = unsafe { nn.as_mut() };
-            let to_drop = !ptr.header.marked();
+        // Remove unmarked from the allocation lists
+        let mut unreachable = Vec::new();
+        let mut sweep = |_: &AllocAddr, nn: &mut NonNull<GcBox<dyn GcAble>>| {
+            let gcb = unsafe { nn.as_ref() };
+            let to_drop = !gcb.header.marked();
             if to_drop {
-                // Drop & deallocate
-                Box::leak(unsafe { Box::from_raw(ptr) });
-                *nn = NonNull::<GcBox<()>>::dangling();
+                if let Some(alive) = gcb.header.weak.get() {
+                    alive.store(false, Ordering::Release);
+                }
+                unreachable.push(*nn);
             }
 
             !to_drop
+        };
+        self.young.retain(&mut sweep);
+        if major {
+            self.old.retain(&mut sweep);
+        }
+
+        // Promote survivors
+        let threshold = self.config.promotion_threshold;
+        self.young.retain(|addr, nn| {
+            let gcb = unsafe { nn.as_ref() };
+            if gcb.header.survive() < threshold {
+                return true;
+            }
+            gcb.header.promote();
+            self.old.insert(*addr, *nn);
+            // It may point to objects which are still young
+            self.remembered.insert(*addr);
+            false
+        });
+
+        // Forget old objects which no longer point into the young generation
+        let (old, young) = (&self.old, &self.young);
+        self.remembered.retain(|addr| {
+            let Some(nn) = old.get(addr) else {
+                return false;
+            };
+            let children = unsafe { tracer::record_children(&nn.as_ref().val) };
+            children
+                .iter()
+                .any(|child| young.contains_key(&AllocAddr::from(child.as_ptr())))
+        });
+        self.pinned.retain(|addr| young.contains_key(addr));
+
+        if self.config.deterministic {
+            unreachable.sort_by_key(|nn| unsafe { nn.as_ref() }.header.serial());
+        }
+
+        self.sweeping = Some(Sweep {
+            unreachable,
+            phase: SweepPhase::Finalize,
+            next: 0,
+            panicked: None,
+            collected: true,
+        });
+        self.sweep_step(self.sweep_batch())
+    }
+
+    /// Queues the finalizer of every unreachable object whose finalizer hasn't been queued before,
+    /// then marks everything reachable from them so that it survives until they're finalized, see
+    /// `GcConfig::defer_finalizers`
+    fn queue_finalizers(&mut self, major: bool) {
+        let mut finalizable = Vec::new();
+        self.for_each_collected(major, |nn| {
+            let header = &unsafe { nn.as_ref() }.header;
+            if !header.marked() && header.needs_finalizing() {
+                finalizable.push(nn);
+            }
         });
+        for nn in finalizable {
+            self.queue_finalizer(nn);
+            unsafe { nn.as_ref() }.header.mark();
+            self.grey.push(nn);
+        }
+        self.mark_step(usize::MAX);
+    }
+
+    /// Roots `nn` until its finalizer is run by [`ContextInner::flush_finalizers`]
+    fn queue_finalizer(&mut self, nn: NonNull<GcBox<dyn GcAble>>) {
+        let header = &unsafe { nn.as_ref() }.header;
+        header.finalize_queued.store(true, Ordering::Relaxed);
+        header.root_count.fetch_add(1, Ordering::SeqCst);
+        self.finalize_queue.push(nn);
+    }
+
+    /// Takes up to `budget` unreachable objects through their next phase of being reclaimed,
+    /// moving on to the next phase if there's budget left, see [`GcAlloc::finish_collection`]
+    ///
+    /// Returns `true` once every unreachable object has been reclaimed, which is when the first
+    /// panic from a finalizer or `Drop` is resumed, if there was one
+    fn sweep_step(&mut self, mut budget: usize) -> bool {
+        let Some(mut sweep) = self.sweeping.take() else {
+            return true;
+        };
+        while budget > 0 {
+            let end = sweep
+                .unreachable
+                .len()
+                .min(sweep.next.saturating_add(budget));
+            let batch = &sweep.unreachable[sweep.next..end];
+            match sweep.phase {
+                SweepPhase::Finalize => {
+                    for nn in batch {
+                        let gcb = unsafe { nn.as_ref() };
+                        // A deferred finalizer has already run
+                        let queued = gcb.header.finalize_queued.load(Ordering::Relaxed);
+                        if let Some(finalizer) = gcb.header.finalizer.filter(|_| !queued) {
+                            let res =
+                                panic::catch_unwind(AssertUnwindSafe(|| unsafe { finalizer(*nn) }));
+                            sweep.panicked = sweep.panicked.or(res.err());
+                        }
+                        debug_assert!(
+                            !gcb.header.is_rooted(),
+                            "an object was resurrected by a finalizer"
+                        );
+                    }
+                }
+                SweepPhase::Drop => {
+                    for nn in batch {
+                        let res = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+                            ptr::drop_in_place(addr_of_mut!((*nn.as_ptr()).val))
+                        }));
+                        sweep.panicked = sweep.panicked.or(res.err());
+                    }
+                }
+                SweepPhase::Dealloc => {
+                    for nn in batch {
+                        tracer::freed(AllocAddr::from(nn.as_ptr()));
+                        let header = unsafe { ptr::read(addr_of!((*nn.as_ptr()).header)) };
+                        self.total_bytes -= header.layout.size();
+                        unsafe {
+                            header
+                                .context
+                                .dealloc(nn.as_ptr() as *mut u8, header.layout)
+                        };
+                    }
+                }
+            }
+            budget -= end - sweep.next;
+            sweep.next = end;
+            if sweep.next < sweep.unreachable.len() {
+                continue;
+            }
+            sweep.next = 0;
+            sweep.phase = match sweep.phase {
+                SweepPhase::Finalize => SweepPhase::Drop,
+                SweepPhase::Drop => SweepPhase::Dealloc,
+                SweepPhase::Dealloc => {
+                    if sweep.collected {
+                        self.survived_bytes = self.total_bytes;
+                        self.last_finished = self.collections;
+                        self.finishes.record();
+                    }
+                    if let Some(payload) = sweep.panicked {
+                        panic::resume_unwind(payload)
+                    }
+                    return true;
+                }
+            };
+        }
+        self.sweeping = Some(sweep);
+        false
     }
 }
 
+/// Bookkeeping for a single allocation
+///
+/// Every field is immutable, atomic or only set once, so the header has no lock of its own. The
+/// fields only the collector uses are accessed while holding the context's lock, which it holds for
+/// the entirety of a collection. Cloning and dropping a `Gc<_>` only change the counts, so they
+/// never wait for a collection to finish.
 pub(crate) struct GcBoxHeader {
-    /// `true` -> This is referenced (indirectly or not) by a stack `Gc<_>`
-    root_count: Mutex<u32>,
-    marked: Mutex<bool>,
+    /// The context this is registered in, which is kept alive for as long as this is
+    context: Arc<ContextInner>,
+    /// The number of root `Gc<_>`s pointing to this, plus every [`Gc::inc_root_count`] not yet
+    /// undone, which make this a root while there are any
+    root_count: AtomicU32,
+    /// The number of `Gc<_>`s pointing to this, whether they're roots or not
+    handle_count: AtomicU32,
+    /// Only accessed while holding the context's lock
+    marked: AtomicBool,
+    /// Whether this is in the old generation, only accessed while holding the context's lock
+    old: AtomicBool,
+    /// The number of collections this has survived while young, only accessed while holding the
+    /// context's lock
+    survivals: AtomicU32,
+    /// Called right before this is dropped by the collector, see [`Finalize`]
+    finalizer: Option<Finalizer>,
+    /// `std::any::type_name` of the value
+    type_name: &'static str,
+    /// The `TypeId` of the value, checked by [`Gc::downcast`]
+    type_id: TypeId,
+    /// Turns the address of this box back into a pointer the collector can trace through
+    erase: Erase,
+    /// The layout of the whole `GcBox<_>`, which it's deallocated with
+    layout: Layout,
+    /// The number of elements in the value if it's a slice, unused otherwise
+    slice_len: usize,
+    /// `false` until [`Gc::assume_init`] if this is from [`Gc::new_uninit`], `true` otherwise
+    initialized: AtomicBool,
+    /// Set once `finalizer` is queued by `GcConfig::defer_finalizers`, after which the collector
+    /// frees this without finalizing it, only accessed while holding the context's lock
+    finalize_queued: AtomicBool,
+    /// How many objects were registered in the context before this, set by
+    /// [`GcAlloc::register_gcbox`]
+    serial: AtomicU64,
+    /// Shared with every `WeakGc<_>` pointing to this, and `false` once this is collected
+    ///
+    /// Only created once this is first downgraded. This is freed along with the rest of the box, so
+    /// the flag lives in its own allocation, which the `WeakGc<_>`s keep alive for as long as they
+    /// need to check it. That also makes its reference count, minus this one, the weak count.
+    weak: OnceLock<Arc<AtomicBool>>,
 }
 
 impl GcBoxHeader {
+    /// The number of `WeakGc<_>`s pointing to this
+    pub fn weak_count(&self) -> usize {
+        self.weak
+            .get()
+            .map_or(0, |alive| Arc::strong_count(alive) - 1)
+    }
     /// Returns true if this has a root count of more than 0
     pub fn is_rooted(&self) -> bool {
-        *self.root_count.lock().unwrap() > 0
+        self.root_count() > 0
+    }
+    pub fn root_count(&self) -> u32 {
+        self.root_count.load(Ordering::SeqCst)
+    }
+    /// Whether this has a finalizer which hasn't been queued by `GcConfig::defer_finalizers`
+    pub fn needs_finalizing(&self) -> bool {
+        self.finalizer.is_some() && !self.finalize_queued.load(Ordering::Relaxed)
+    }
+    /// Adds one to the root count, returns `false` instead if it's at its maximum
+    pub fn try_inc_root_count(&self) -> bool {
+        self.root_count
+            .try_update(Ordering::SeqCst, Ordering::SeqCst, |rc| rc.checked_add(1))
+            .is_ok()
+    }
+    pub fn handle_count(&self) -> u32 {
+        self.handle_count.load(Ordering::SeqCst)
+    }
+    pub fn inc_handle_count(&self) {
+        self.handle_count.fetch_add(1, Ordering::SeqCst);
+    }
+    pub fn dec_handle_count(&self) {
+        self.handle_count.fetch_sub(1, Ordering::SeqCst);
     }
     pub fn marked(&self) -> bool {
-        *self.marked.lock().unwrap()
+        self.marked.load(Ordering::Relaxed)
     }
     pub fn unmark(&self) {
-        *self.marked.lock().unwrap() = false;
+        self.marked.store(false, Ordering::Relaxed);
     }
     pub fn mark(&self) {
-        *self.marked.lock().unwrap() = true;
+        self.marked.store(true, Ordering::Relaxed);
+    }
+    pub fn is_old(&self) -> bool {
+        self.old.load(Ordering::Relaxed)
+    }
+    pub fn promote(&self) {
+        self.old.store(true, Ordering::Relaxed);
+    }
+    pub fn serial(&self) -> u64 {
+        self.serial.load(Ordering::Relaxed)
+    }
+    /// Records surviving a collection, returning the number of collections survived
+    pub fn survive(&self) -> u32 {
+        self.survivals.fetch_add(1, Ordering::Relaxed) + 1
     }
 }
 
+/// Only public so [`gc_unsize!`] can name it
+#[doc(hidden)]
 #[repr(C)]
-pub(crate) struct GcBox<T: ?Sized + GcAble> {
+pub struct GcBox<T: ?Sized + GcAble> {
     header: GcBoxHeader,
     val: T,
 }
 
+// `#[repr(C)]` puts the header first whatever the value is, which is what lets a pointer to the
+// start of an allocation be cast to any `GcBox<_>`, and the header be read through a
+// `GcBox<dyn GcAble>` without knowing the value's type
+const _: () = assert!(core::mem::offset_of!(GcBox<()>, header) == 0);
+const _: () = assert!(core::mem::offset_of!(GcBox<u8>, header) == 0);
+const _: () = assert!(core::mem::offset_of!(GcBox<u128>, header) == 0);
+
 impl<T: ?Sized + GcAble> GcBox<T> {
-    pub fn header(this: *const Self) -> *const GcBoxHeader {
-        unsafe { addr_of!((*this).header) }
-    }
-    pub fn val(this: *const Self) -> *const T {
+    /// # Safety
+    /// `this` must point to a live allocation
+    pub unsafe fn val(this: *const Self) -> *const T {
         unsafe { addr_of!((*this).val) }
     }
+
+    /// Panics if `this` and `erased`, the same box as returned by [`GcBoxHeader::erase`], disagree
+    /// on where the header or the value is, or if the value doesn't fit in the box's layout
+    ///
+    /// Converting a `GcBox<T>` to a `GcBox<dyn GcAble>`, or to one of the erased values of
+    /// `Gc<[T]>` and `Gc::new_uninit`, and finding the header from the value's address all rely on
+    /// this
+    ///
+    /// # Safety
+    /// `this` must point to a live box whose header and value are initialized
+    unsafe fn assert_layout(this: NonNull<Self>, erased: NonNull<GcBox<dyn GcAble>>) {
+        let start = this.as_ptr() as *const u8 as usize;
+        let offset = |ptr: *const u8| ptr as usize - start;
+        let header = unsafe { &(*this.as_ptr()).header };
+        let type_name = header.type_name;
+        let layout = header.layout;
+        assert_eq!(
+            start % layout.align(),
+            0,
+            "a `GcBox<{type_name}>` is misaligned"
+        );
+
+        let erased_header = unsafe { addr_of!((*erased.as_ptr()).header) };
+        assert_eq!(offset(header as *const _ as *const u8), 0);
+        assert_eq!(offset(erased_header as *const u8), 0);
+
+        let val = unsafe { &*Self::val(this.as_ptr()) };
+        let erased_val = unsafe { GcBox::val(erased.as_ptr()) };
+        let val_offset = offset(val as *const T as *const u8);
+        assert_eq!(
+            val_offset,
+            offset(erased_val as *const u8),
+            "the value of a `GcBox<{type_name}>` moved when it was erased"
+        );
+        assert_eq!(val_offset % core::mem::align_of_val(val), 0);
+        assert!(
+            val_offset + core::mem::size_of_val(val) <= layout.size(),
+            "the value of a `GcBox<{type_name}>` doesn't fit in its layout"
+        );
+    }
+}
+
+/// A type-erased call to [`Finalize::finalize`]
+type Finalizer = unsafe fn(NonNull<GcBox<dyn GcAble>>);
+
+/// # Safety
+/// `gcbox` must point to a live `GcBox<T>`
+type Erase = fn(NonNull<u8>) -> NonNull<GcBox<dyn GcAble>>;
+
+fn erase_gcbox<T: GcAble>(gcbox: NonNull<u8>) -> NonNull<GcBox<dyn GcAble>> {
+    gcbox.cast::<GcBox<T>>()
+}
+
+unsafe fn finalize_gcbox<T: Finalize>(gcbox: NonNull<GcBox<dyn GcAble>>) {
+    let val = unsafe { &*(GcBox::val(gcbox.as_ptr()) as *const T) };
+    val.finalize()
 }
 
 trait IncOrDec: Copy + 'static {
@@ -163,139 +1374,1622 @@ struct NegOne;
 impl IncOrDec for NegOne {
     #[inline(always)]
     fn get() -> i32 {
-        1
+        -1
     }
 }
 
-pub struct Gc<T: GcAble> {
-    is_root: Mutex<bool>,
+pub struct Gc<T: ?Sized + GcAble> {
+    /// Whether this counts towards the root count, which is only `false` while this is stored
+    /// inside a `GcAble` value
+    is_root: AtomicBool,
     gcbox: NonNull<GcBox<T>>,
 }
 
 // SAFETY: All referenced values are managed between multiple threads
-unsafe impl<T: GcAble> Send for Gc<T> {}
+unsafe impl<T: ?Sized + GcAble> Send for Gc<T> {}
 // SAFETY: All referenced values are managed between multiple threads,
 // and any interior mutation is hidden behind syncronization primitives (in `GcBox<T>`)
-unsafe impl<T: GcAble> Sync for Gc<T> {}
+unsafe impl<T: ?Sized + GcAble> Sync for Gc<T> {}
 
 impl<T: GcAble> Gc<T> {
+    /// Moves `val` into the global context, see [`GcContext::alloc`]
+    ///
+    /// Panics if the global context was shut down, see [`Gc::try_new`]
     pub fn new(val: T) -> Gc<T> {
         Gc::from_box(Box::new(val))
     }
+
+    /// Like [`Gc::new`], but fails instead of panicking if the global context was shut down by
+    /// [`shutdown`], or instead of aborting if the allocator is out of memory
+    ///
+    /// If the first attempt to allocate fails, an urgent collection is run on the calling thread
+    /// before trying once more, see [`GcContext::try_alloc`]
+    ///
+    /// ```
+    /// use std::panic;
+    ///
+    /// use gc::{Gc, GcError};
+    ///
+    /// // The first allocation initializes the global context
+    /// let before = Gc::try_new(1u32).unwrap();
+    /// assert_eq!(*Gc::try_new(2u32).unwrap(), 2);
+    ///
+    /// gc::shutdown();
+    /// assert_eq!(Gc::try_new(3u32).err(), Some(GcError::ShutDown));
+    /// let payload = panic::catch_unwind(|| Gc::new(4u32)).unwrap_err();
+    /// let msg = payload.downcast_ref::<&str>().unwrap();
+    /// assert!(msg.contains("which was shut down, see `Gc::try_new`"), "{msg}");
+    /// // What was allocated before stays usable
+    /// assert_eq!(*before, 1);
+    /// ```
+    pub fn try_new(val: T) -> Result<Gc<T>, GcError> {
+        GcContext::global().try_alloc(val)
+    }
+    #[allow(clippy::boxed_local)]
     pub fn from_box(owned_ptr: Box<T>) -> Gc<T> {
-        let val = *owned_ptr;
-        unsafe { val.set_not_root() };
+        GcContext::global().alloc(*owned_ptr)
+    }
 
-        let gcbox = Box::leak(Box::new(GcBox {
-            header: GcBoxHeader {
-                marked: Mutex::new(false),
-                root_count: Mutex::new(1), // < `1` since we are creating the first Gc here
-            },
-            val,
-        }));
+    /// Like [`Gc::new`], but [`Finalize::finalize`] will be called on the value before it's collected
+    pub fn new_finalized(val: T) -> Gc<T>
+    where
+        T: Finalize,
+    {
+        GcContext::global().alloc_finalized(val)
+    }
 
-        global_gc::lock().register_gcbox(gcbox);
+    /// Constructs a `Gc<T>` while giving `f` a `WeakGc<T>` to it, which can be stored in the value
+    /// to build a self-referential graph
+    ///
+    /// Upgrading the weak handle before this returns gives `None`
+    pub fn new_cyclic(f: impl FnOnce(&WeakGc<T>) -> T) -> Gc<T> {
+        GcContext::global().alloc_cyclic(f)
+    }
 
-        Gc {
-            is_root: Mutex::new(true),
-            gcbox: NonNull::new(gcbox).unwrap(),
-        }
+    /// Like [`Gc::new`], but pinned
+    ///
+    /// A value stays at the same address from when it's allocated until it's dropped by the
+    /// collector, which only happens once nothing points to it, so the value of a `Gc<_>` never
+    /// moves. A `Pin<Gc<T>>` can't be turned back into a `Gc<T>`, so the value can't be moved out
+    /// or mutably borrowed through one either.
+    ///
+    /// ```
+    /// use std::{marker::PhantomPinned, pin::Pin};
+    ///
+    /// use gc::{Gc, GcAble};
+    ///
+    /// struct Unmovable {
+    ///     val: u64,
+    ///     _pinned: PhantomPinned,
+    /// }
+    /// # unsafe impl GcAble for Unmovable {
+    /// #     unsafe fn mark(&self) {}
+    /// #     unsafe fn inc_root_count(&self) {}
+    /// #     unsafe fn dec_root_count(&self) {}
+    /// #     unsafe fn set_not_root(&self) {}
+    /// # }
+    ///
+    /// struct Holder(Pin<Gc<Unmovable>>);
+    /// # unsafe impl GcAble for Holder {
+    /// #     unsafe fn mark(&self) {
+    /// #         unsafe { self.0.mark() }
+    /// #     }
+    /// #     unsafe fn inc_root_count(&self) {
+    /// #         unsafe { self.0.inc_root_count() }
+    /// #     }
+    /// #     unsafe fn dec_root_count(&self) {
+    /// #         unsafe { self.0.dec_root_count() }
+    /// #     }
+    /// #     unsafe fn set_not_root(&self) {
+    /// #         unsafe { self.0.set_not_root() }
+    /// #     }
+    /// # }
+    ///
+    /// let pinned = Gc::pin(Unmovable { val: 7, _pinned: PhantomPinned });
+    /// let addr: *const Unmovable = &*pinned;
+    /// let holder = Gc::new(Holder(pinned.clone()));
+    /// drop(pinned);
+    ///
+    /// // Other objects being allocated and collected around it doesn't move it
+    /// for i in 0..1000u64 {
+    ///     drop(Gc::new(i));
+    /// }
+    /// gc::force_collect();
+    /// assert!(std::ptr::eq(&*holder.0, addr));
+    /// assert_eq!(holder.0.val, 7);
+    /// ```
+    pub fn pin(val: T) -> Pin<Gc<T>> {
+        unsafe { Pin::new_unchecked(Gc::new(val)) }
     }
 
-    pub fn as_ptr(&self) -> *const T {
-        GcBox::val(self.gcbox.as_ptr())
+    fn new_with_finalizer(ctx: &Arc<ContextInner>, val: T, finalizer: Option<Finalizer>) -> Gc<T> {
+        let gcbox = Self::alloc_uninit(ctx);
+        unsafe { Self::init_gcbox(ctx, gcbox, val, finalizer, OnceLock::new()) }
     }
 
-    /// Recursively marks all pointed to values
-    ///
-    /// Ends recursion if this was already marked
-    pub unsafe fn mark(&self) {
-        let g = unsafe { self.gcbox.as_ref() };
-        let was_marked = g.header.marked();
-        if !was_marked {
-            g.header.mark();
-            unsafe { g.val.mark() };
-        }
+    fn new_cyclic_in(ctx: &Arc<ContextInner>, f: impl FnOnce(&WeakGc<T>) -> T) -> Gc<T> {
+        let gcbox = Self::alloc_uninit(ctx);
+        let alive = Arc::new(AtomicBool::new(false));
+        let weak = unsafe { WeakGc::new(gcbox, Arc::clone(&alive), Arc::clone(ctx)) };
+        let val = f(&weak);
+        unsafe { Self::init_gcbox(ctx, gcbox, val, None, OnceLock::from(alive)) }
     }
+
+    fn alloc_uninit(ctx: &ContextInner) -> NonNull<GcBox<T>> {
+        match Self::try_alloc_uninit(ctx) {
+            Some(gcbox) => gcbox,
+            None => alloc::alloc::handle_alloc_error(Layout::new::<GcBox<T>>()),
+        }
+    }
+
+    /// Like [`Gc::alloc_uninit`], but `None` if the allocator is out of memory
+    fn try_alloc_uninit(ctx: &ContextInner) -> Option<NonNull<GcBox<T>>> {
+        let gcbox = unsafe { ctx.alloc(Layout::new::<GcBox<T>>()) };
+        NonNull::new(gcbox).map(NonNull::cast)
+    }
+
+    /// The header of a new box, as pointed to by only the first root `Gc<T>`
+    fn new_header(
+        ctx: &Arc<ContextInner>,
+        finalizer: Option<Finalizer>,
+        weak: OnceLock<Arc<AtomicBool>>,
+    ) -> GcBoxHeader {
+        Gc::<T>::header_with(
+            ctx,
+            finalizer,
+            weak,
+            erase_gcbox::<T>,
+            Layout::new::<GcBox<T>>(),
+            0,
+        )
+    }
+
+    /// # Safety
+    /// `gcbox` must come from [`Gc::alloc_uninit`] or [`Gc::try_alloc_uninit`] and not be initialized yet
+    unsafe fn init_gcbox(
+        ctx: &Arc<ContextInner>,
+        gcbox: NonNull<GcBox<T>>,
+        val: T,
+        finalizer: Option<Finalizer>,
+        weak: OnceLock<Arc<AtomicBool>>,
+    ) -> Gc<T> {
+        // Hold the lock from the moment `val`'s children stop being roots until `val` is registered,
+        // otherwise a collection in between could free them
+        let mut gc = ctx.lock();
+        unsafe { val.set_not_root() };
+
+        if let Some(alive) = weak.get() {
+            alive.store(true, Ordering::Release);
+        }
+        unsafe {
+            gcbox.as_ptr().write(GcBox {
+                header: Self::new_header(ctx, finalizer, weak),
+                val,
+            })
+        };
+        let verify = gc.config.verify_roots;
+        Self::register(&mut gc, ctx, gcbox);
+        drop(gc);
+
+        let this = Gc {
+            is_root: AtomicBool::new(true),
+            gcbox,
+        };
+        if verify {
+            this.verify_not_root();
+        }
+        this
+    }
+
+    /// Creates a `WeakGc<T>` pointing to the same value as this
+    pub fn downgrade(this: &Self) -> WeakGc<T> {
+        let gcb = this.gcb();
+        let alive = gcb
+            .header
+            .weak
+            .get_or_init(|| Arc::new(AtomicBool::new(true)));
+        unsafe {
+            WeakGc::new(
+                this.gcbox,
+                Arc::clone(alive),
+                Arc::clone(&gcb.header.context),
+            )
+        }
+    }
+
+    /// The number of root `Gc<_>`s pointing to the value, including this one if it's a root
+    ///
+    /// `Gc<_>`s stored inside other values aren't counted
+    pub fn strong_count(this: &Self) -> u32 {
+        this.gcb().header.root_count()
+    }
+
+    /// Like `Gc::clone`, but fails instead of panicking if the object's root count is at its
+    /// maximum
+    ///
+    /// This is an associated function rather than a method, so that it doesn't shadow a
+    /// `try_clone` of the value, such as `File::try_clone`
+    pub fn try_clone(this: &Self) -> Result<Gc<T>, RootCountOverflow> {
+        let gcb = this.gcb();
+        if !gcb.header.try_inc_root_count() {
+            return Err(RootCountOverflow {
+                type_name: gcb.header.type_name,
+            });
+        }
+        tracing_stats::record(Call::IncRootCount);
+        tracing_stats::record_root_count(1);
+        gcb.header.inc_handle_count();
+        Ok(Gc {
+            is_root: AtomicBool::new(true),
+            gcbox: this.gcbox,
+        })
+    }
+
+    /// The number of `WeakGc<_>`s pointing to the value
+    pub fn weak_count(this: &Self) -> usize {
+        this.gcb().header.weak_count()
+    }
+
+    /// Returns a mutable reference into the value if this is the only `Gc<_>` pointing to it
+    ///
+    /// The `GcRefMut` derefs to a `&mut T` borrowed from this, and only exists so that the Gc knows
+    /// when the borrow ends. `Gc<_>`s may be freely moved into and out of the value through it, and
+    /// collection is skipped for as long as it's alive.
+    ///
+    /// ```
+    /// use gc::Gc;
+    ///
+    /// let mut list = Gc::new(vec![Gc::new(1u32)]);
+    /// list.get_mut().unwrap().push(Gc::new(2));
+    ///
+    /// let shared = list.clone();
+    /// assert!(list.get_mut().is_none());
+    /// drop(shared);
+    ///
+    /// // Moved out, so no longer kept alive by the list
+    /// let first = list.get_mut().unwrap().remove(0);
+    /// gc::force_collect();
+    /// assert_eq!((*first, *list[0]), (1, 2));
+    /// ```
+    pub fn get_mut(&mut self) -> Option<GcRefMut<'_, T>> {
+        let gcb = self.gcb();
+        if gcb.header.handle_count() != 1 {
+            return None;
+        }
+        if gcb.header.weak_count() > 0 {
+            return None;
+        }
+
+        // Taking the lock waits out any collection which may be reading the value right now
+        gcb.header.context.lock().exclusive_borrows += 1;
+        // Anything moved out of the value has to be a root by the time it is
+        unsafe { tracer::root_children(&gcb.val) };
+        Some(GcRefMut { gc: self })
+    }
+
+    /// Replaces the value in place if this is the only `Gc<_>` pointing to it, returning the old
+    /// value, otherwise gives `val` back
+    ///
+    /// Like [`GcCell::replace`], the `Gc<_>`s in `val` stop being roots and the ones in the returned
+    /// value become roots again. Every other `Gc<_>` stays pointing to the same object.
+    ///
+    /// ```
+    /// use gc::{Gc, GcAble, GcContext};
+    ///
+    /// struct Pair(Gc<u32>, Gc<u32>);
+    /// # unsafe impl GcAble for Pair {
+    /// #     unsafe fn mark(&self) {
+    /// #         unsafe { (self.0.mark(), self.1.mark()) };
+    /// #     }
+    /// #     unsafe fn inc_root_count(&self) {
+    /// #         unsafe { (self.0.inc_root_count(), self.1.inc_root_count()) };
+    /// #     }
+    /// #     unsafe fn dec_root_count(&self) {
+    /// #         unsafe { (self.0.dec_root_count(), self.1.dec_root_count()) };
+    /// #     }
+    /// #     unsafe fn set_not_root(&self) {
+    /// #         unsafe { (self.0.set_not_root(), self.1.set_not_root()) };
+    /// #     }
+    /// # }
+    ///
+    /// let ctx = GcContext::new();
+    /// let mut pair = ctx.alloc(Pair(ctx.alloc(1), ctx.alloc(2)));
+    /// let addr = Gc::as_ptr(&pair);
+    ///
+    /// // Neither is kept alive by anything but the value it's in
+    /// let old = pair.replace_in_place(Pair(ctx.alloc(3), ctx.alloc(4))).ok().unwrap();
+    /// ctx.force_collect();
+    /// assert_eq!((*old.0, *old.1, *pair.0, *pair.1), (1, 2, 3, 4));
+    /// assert_eq!(Gc::as_ptr(&pair), addr);
+    /// drop(old);
+    /// ctx.force_collect();
+    /// assert_eq!(ctx.stats().live_allocations, 3);
+    ///
+    /// // Not while another handle could be reading the value
+    /// let shared = pair.clone();
+    /// let refused = pair.replace_in_place(Pair(ctx.alloc(5), ctx.alloc(6))).err().unwrap();
+    /// drop((shared, refused));
+    /// let weak = Gc::downgrade(&pair);
+    /// assert!(pair.replace_in_place(Pair(ctx.alloc(7), ctx.alloc(8))).is_err());
+    /// drop(weak);
+    ///
+    /// drop(pair);
+    /// ctx.force_collect();
+    /// ctx.assert_no_leaks();
+    /// ```
+    pub fn replace_in_place(&mut self, val: T) -> Result<T, T> {
+        let gcb = self.gcb();
+        if gcb.header.handle_count() != 1 || gcb.header.weak_count() > 0 {
+            return Err(val);
+        }
+
+        // The collector can't be tracing the value while it's changing hands
+        let mut gc = gcb.header.context.lock();
+        let old = core::mem::replace(unsafe { &mut (*self.gcbox.as_ptr()).val }, val);
+        unsafe {
+            T::set_not_root(self);
+            tracer::root_children(&old);
+        }
+        gc.write_barrier(self.gcbox);
+        Ok(old)
+    }
+
+    /// Moves the value out and frees its allocation if this is the only `Gc<_>` pointing to it,
+    /// otherwise gives this back
+    ///
+    /// The `Gc<_>`s in the value are roots again once it's returned. The value isn't finalized.
+    pub fn try_unwrap(this: Self) -> Result<T, Self> {
+        let gcb = this.gcb();
+        // Keeps the context alive until the lock is released, since freeing the box drops the
+        // header's reference to it
+        let ctx = Arc::clone(&gcb.header.context);
+        let mut gc = ctx.lock();
+        if gcb.header.handle_count() != 1 || gcb.header.weak_count() > 0 {
+            return Err(this);
+        }
+
+        gc.unregister_gcbox(this.erased());
+        let (_, gcbox) = Gc::into_parts(this);
+        let GcBox { header, val } = unsafe { gcbox.as_ptr().read() };
+        // The box is gone, so nothing else keeps the value's children alive
+        unsafe { tracer::root_children(&val) };
+        unsafe { ctx.dealloc(gcbox.as_ptr() as *mut u8, header.layout) };
+        drop(gc);
+        drop(header);
+        Ok(val)
+    }
+
+    /// Like [`Gc::try_unwrap`], but drops this if it isn't the only `Gc<_>` pointing to its value
+    ///
+    /// ```
+    /// use gc::{Gc, GcAble, GcConfig, GcContext};
+    ///
+    /// struct Pair(Gc<u32>, Gc<u32>);
+    /// # unsafe impl GcAble for Pair {
+    /// #     unsafe fn mark(&self) {
+    /// #         unsafe { (self.0.mark(), self.1.mark()) };
+    /// #     }
+    /// #     unsafe fn inc_root_count(&self) {
+    /// #         unsafe { (self.0.inc_root_count(), self.1.inc_root_count()) };
+    /// #     }
+    /// #     unsafe fn dec_root_count(&self) {
+    /// #         unsafe { (self.0.dec_root_count(), self.1.dec_root_count()) };
+    /// #     }
+    /// #     unsafe fn set_not_root(&self) {
+    /// #         unsafe { (self.0.set_not_root(), self.1.set_not_root()) };
+    /// #     }
+    /// # }
+    ///
+    /// let ctx = GcContext::with_config(GcConfig::default().deterministic(true));
+    /// let pair = ctx.alloc(Pair(ctx.alloc(1), ctx.alloc(2)));
+    /// let Pair(first, second) = Gc::into_inner(pair).unwrap();
+    /// // Freed right away, while what it pointed to is kept alive by the moved out value
+    /// assert_eq!(ctx.stats().live_allocations, 2);
+    /// ctx.force_collect();
+    /// assert_eq!((*first, *second), (1, 2));
+    ///
+    /// // Shared, so only this handle is dropped
+    /// let shared = first.clone();
+    /// assert_eq!(Gc::into_inner(first), None);
+    /// assert_eq!(*shared, 1);
+    /// let weak = Gc::downgrade(&second);
+    /// let second = Gc::try_unwrap(second).unwrap_err();
+    /// drop(weak);
+    /// assert_eq!(Gc::into_inner(second), Some(2));
+    ///
+    /// drop(shared);
+    /// ctx.force_collect();
+    /// ctx.assert_no_leaks();
+    /// ```
+    pub fn into_inner(this: Self) -> Option<T> {
+        Gc::try_unwrap(this).ok()
+    }
+
+    /// Converts this into a `Gc<dyn GcAble>`, which can be turned back with [`Gc::downcast`]
+    pub fn into_dyn(this: Self) -> Gc<dyn GcAble> {
+        let (is_root, gcbox) = Gc::into_parts(this);
+        Gc {
+            is_root: AtomicBool::new(is_root),
+            gcbox,
+        }
+    }
+
+    /// Consumes this without dropping it, returning a pointer to the value
+    ///
+    /// The pointer keeps the value alive as a root, until it's turned back into a `Gc<T>` with
+    /// [`Gc::from_raw`]
+    ///
+    /// ```
+    /// use gc::{Gc, GcContext};
+    ///
+    /// let ctx = GcContext::new();
+    /// let gc = ctx.alloc(1u32);
+    /// let weak = Gc::downgrade(&gc);
+    /// let raw = Gc::into_raw(gc);
+    ///
+    /// // Only the pointer is left, which still counts as the one root
+    /// let live = ctx.live_allocations();
+    /// assert_eq!((live.len(), live[0].root_count), (1, 1));
+    /// assert_eq!(unsafe { *raw }, 1);
+    ///
+    /// let gc = unsafe { Gc::from_raw(raw) };
+    /// assert_eq!(ctx.live_allocations()[0].root_count, 1);
+    /// assert_eq!(gc.as_ptr(), weak.upgrade().unwrap().as_ptr());
+    /// drop(gc);
+    /// ctx.force_collect();
+    /// assert!(weak.upgrade().is_none());
+    /// ```
+    pub fn into_raw(this: Self) -> *const T {
+        // The pointer always counts as a root, even if this was taken out of a value without being
+        // made one
+        if !this.is_root.load(Ordering::Acquire) {
+            unsafe { this.inc_root_count() };
+        }
+        let (_, gcbox) = Gc::into_parts(this);
+        unsafe { GcBox::val(gcbox.as_ptr()) }
+    }
+
+    /// Reclaims a `Gc<T>` from a pointer returned by [`Gc::into_raw`]
+    ///
+    /// # Safety
+    /// `ptr` must come from [`Gc::into_raw`], and this must be called at most once per call to it
+    pub unsafe fn from_raw(ptr: *const T) -> Gc<T> {
+        let offset = core::mem::offset_of!(GcBox<T>, val);
+        let gcbox = unsafe { ptr.byte_sub(offset) } as *mut GcBox<T>;
+        Gc {
+            is_root: AtomicBool::new(true),
+            gcbox: NonNull::new(gcbox).unwrap(),
+        }
+    }
+}
+
+impl<T: ?Sized + GcAble> Gc<T> {
+    /// Creates a new root `Gc<T>` pointing to `gcbox`
+    ///
+    /// # Safety
+    /// `gcbox` must be live, and must not be collected while this runs
+    unsafe fn from_gcbox(gcbox: NonNull<GcBox<T>>) -> Gc<T> {
+        let gcb = unsafe { gcbox.as_ref() };
+        gcb.header.inc_handle_count();
+        // Only counts as a root once the root count is incremented, which panics if it overflows
+        let mut gc = Gc {
+            is_root: AtomicBool::new(false),
+            gcbox,
+        };
+        unsafe { gc.inc_root_count() };
+        *gc.is_root.get_mut() = true;
+        gc
+    }
+
+    /// The header of a new box allocated with `layout`, as pointed to by only the first root `Gc<T>`
+    fn header_with(
+        ctx: &Arc<ContextInner>,
+        finalizer: Option<Finalizer>,
+        weak: OnceLock<Arc<AtomicBool>>,
+        erase: Erase,
+        layout: Layout,
+        slice_len: usize,
+    ) -> GcBoxHeader {
+        GcBoxHeader {
+            context: Arc::clone(ctx),
+            marked: AtomicBool::new(false),
+            old: AtomicBool::new(false),
+            survivals: AtomicU32::new(0),
+            root_count: AtomicU32::new(1), // < `1` since we are creating the first Gc here
+            handle_count: AtomicU32::new(1),
+            finalizer,
+            type_name: core::any::type_name::<T>(),
+            type_id: TypeId::of::<T>(),
+            erase,
+            layout,
+            slice_len,
+            initialized: AtomicBool::new(true),
+            finalize_queued: AtomicBool::new(false),
+            serial: AtomicU64::new(0),
+            weak,
+        }
+    }
+
+    /// Hands a fully initialized `gcbox` to the collector
+    fn register(gc: &mut GcAlloc, ctx: &ContextInner, gcbox: NonNull<GcBox<T>>) {
+        let gcb = unsafe { gcbox.as_ref() };
+        let erased = (gcb.header.erase)(gcbox.cast());
+        if cfg!(debug_assertions) {
+            unsafe { GcBox::assert_layout(gcbox, erased) };
+        }
+        gc.register_gcbox(erased);
+        if gc.collection_requested {
+            ctx.wake_collector();
+        }
+    }
+
+    /// Panics if a `Gc<_>` directly contained in the value still counts as a root, which means the
+    /// value's `GcAble::set_not_root` missed it, see [`GcConfig::verify_roots`]
+    ///
+    /// Does nothing in release builds
+    fn verify_not_root(&self) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        let gcb = unsafe { self.erased().as_ref() };
+        let rooted = unsafe { tracer::rooted_children(&gcb.val) };
+        if let Some(child) = rooted.first() {
+            let child = &unsafe { child.as_ref() }.header;
+            panic!(
+                "`<{} as GcAble>::set_not_root` missed a `Gc<{}>`, which will never be collected",
+                gcb.header.type_name, child.type_name,
+            );
+        }
+    }
+
+    /// Takes this apart without touching the counts, which the returned parts take over
+    fn into_parts(this: Self) -> (bool, NonNull<GcBox<T>>) {
+        let is_root = this.is_root.load(Ordering::Acquire);
+        let this = ManuallyDrop::new(this);
+        (is_root, this.gcbox)
+    }
+
+    /// Makes this count as a root until the returned guard is dropped
+    ///
+    /// A `Gc<_>` stored inside another `GcAble` value isn't a root, so it's only kept alive by its
+    /// containing value being reachable. This keeps it, and everything reachable from it, alive
+    /// regardless.
+    pub fn root(&self) -> RootGuard<'_, T> {
+        unsafe { self.inc_root_count() };
+        RootGuard { gc: self }
+    }
+
+    /// The `TypeId` of the type the object was allocated as, whatever type this `Gc<_>` has
+    ///
+    /// For a `Gc<dyn Trait>` this is the concrete type of the value, which lets objects be told
+    /// apart by type without downcasting them:
+    ///
+    /// ```
+    /// use std::any::TypeId;
+    /// use gc::{gc_unsize, Gc, GcAble};
+    ///
+    /// trait Value: GcAble {}
+    ///
+    /// #[derive(GcAble)]
+    /// struct Int(i64);
+    /// impl Value for Int {}
+    ///
+    /// #[derive(GcAble)]
+    /// struct Str(String);
+    /// impl Value for Str {}
+    ///
+    /// let (int, str) = (Gc::new(Int(1)), Gc::new(Str("one".to_string())));
+    /// let values: Vec<Gc<dyn Value>> =
+    ///     vec![gc_unsize!(int as dyn Value), gc_unsize!(str as dyn Value)];
+    /// assert_eq!(Gc::type_id(&values[0]), TypeId::of::<Int>());
+    /// assert_eq!(Gc::type_id(&values[1]), TypeId::of::<Str>());
+    /// assert_ne!(Gc::type_id(&values[0]), Gc::type_id(&values[1]));
+    /// ```
+    ///
+    /// This is the type the object is traced and dropped as, which [`Gc::cast`] doesn't change. A
+    /// [`Gc::new_uninit`] box reports the type it's going to hold.
+    pub fn type_id(this: &Self) -> TypeId {
+        this.gcb().header.type_id
+    }
+
+    /// Whether `this` and `other` point to the same object
+    ///
+    /// Every object has its own allocation, so this works for zero sized values as well
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.gcbox.cast::<u8>() == other.gcbox.cast::<u8>()
+    }
+
+    /// Whether the object this points to hasn't been freed
+    ///
+    /// A `Gc<_>` keeps its object alive, so this is only `false` once unsafe code broke a
+    /// [`GcAble`] contract, such as by calling [`GcAble::set_not_root`] on a `Gc<_>` which isn't
+    /// stored in a value. Using the `Gc<_>` from then on panics instead of reading freed memory.
+    ///
+    /// Objects are only tracked in debug builds, so this is always `true` in release builds. A
+    /// freed object's memory may also be reused by a new object, after which this is `true` again.
+    pub fn is_alive(&self) -> bool {
+        tracer::is_registered(AllocAddr::from(self.gcbox.as_ptr()))
+    }
+
+    /// The value, or `None` if the object was freed, see [`Gc::is_alive`]
+    pub fn as_ref_option(&self) -> Option<&T> {
+        self.is_alive().then(|| unsafe { &*self.as_ptr() })
+    }
+
+    pub fn as_ptr(&self) -> *const T {
+        unsafe { GcBox::val(self.gcbox.as_ptr()) }
+    }
+
+    /// Like [`Gc::as_ptr`], but non-null
+    ///
+    /// The pointer is stable, since a value is never moved for as long as it's alive
+    pub fn as_non_null(&self) -> NonNull<T> {
+        NonNull::new(self.as_ptr() as *mut T).unwrap()
+    }
+
+    /// Calls `f` with a new root `Gc<_>` to each object directly pointed to by the value, which
+    /// can be used to walk the heap's graph
+    ///
+    /// The children are found by [`GcAble::mark`], the same way the collector finds them, so
+    /// this waits while a `GcRefMut`, `GcMutRef` or `DeepCloner` in the value's context is alive,
+    /// which must not be on the calling thread. `f` is called once the children have been found,
+    /// so it may use the Gc freely.
+    ///
+    /// ```
+    /// use std::collections::HashSet;
+    ///
+    /// use gc::{Gc, GcAble, GcCell, GcConfig, GcContext};
+    ///
+    /// #[derive(Clone)]
+    /// struct Edges(Vec<Gc<Node>>);
+    /// # unsafe impl GcAble for Edges {
+    /// #     unsafe fn mark(&self) {
+    /// #         self.0.iter().for_each(|gc| unsafe { gc.mark() })
+    /// #     }
+    /// #     unsafe fn inc_root_count(&self) {
+    /// #         self.0.iter().for_each(|gc| unsafe { gc.inc_root_count() })
+    /// #     }
+    /// #     unsafe fn dec_root_count(&self) {
+    /// #         self.0.iter().for_each(|gc| unsafe { gc.dec_root_count() })
+    /// #     }
+    /// #     unsafe fn set_not_root(&self) {
+    /// #         self.0.iter().for_each(|gc| unsafe { gc.set_not_root() })
+    /// #     }
+    /// # }
+    ///
+    /// struct Node {
+    ///     id: u32,
+    ///     edges: GcCell<Edges>,
+    /// }
+    /// # unsafe impl GcAble for Node {
+    /// #     unsafe fn mark(&self) {
+    /// #         unsafe { self.edges.mark() }
+    /// #     }
+    /// #     unsafe fn inc_root_count(&self) {
+    /// #         unsafe { self.edges.inc_root_count() }
+    /// #     }
+    /// #     unsafe fn dec_root_count(&self) {
+    /// #         unsafe { self.edges.dec_root_count() }
+    /// #     }
+    /// #     unsafe fn set_not_root(&self) {
+    /// #         unsafe { self.edges.set_not_root() }
+    /// #     }
+    /// # }
+    ///
+    /// let ctx = GcContext::with_config(GcConfig::default().deterministic(true));
+    /// let node = |id| ctx.alloc(Node { id, edges: GcCell::new(Edges(Vec::new())) });
+    /// let (a, b, c, d) = (node(0), node(1), node(2), node(3));
+    /// a.edges.set(Edges(vec![b.clone(), c.clone(), b.clone()]));
+    /// b.edges.set(Edges(vec![d.clone()]));
+    /// c.edges.set(Edges(vec![a.clone()]));
+    ///
+    /// // A child is visited once per `Gc<_>` pointing to it
+    /// let mut children = Vec::new();
+    /// a.visit_children(|child| children.push(child.downcast_ref::<Node>().unwrap().id));
+    /// assert_eq!(children, [1, 2, 1]);
+    /// d.visit_children(|_| unreachable!());
+    ///
+    /// // Walks everything reachable from `a`, cycle included
+    /// let (mut seen, mut todo) = (HashSet::new(), vec![a.clone()]);
+    /// while let Some(node) = todo.pop() {
+    ///     if seen.insert(node.id) {
+    ///         node.visit_children(|child| todo.push(child.downcast::<Node>().ok().unwrap()));
+    ///     }
+    /// }
+    /// assert_eq!(seen, HashSet::from([0, 1, 2, 3]));
+    ///
+    /// drop((a, b, c, d));
+    /// ctx.force_collect();
+    /// ctx.assert_no_leaks();
+    /// ```
+    pub fn visit_children(&self, f: impl FnMut(Gc<dyn GcAble>)) {
+        let gcb = self.gcb();
+        let children = loop {
+            // Holding the lock keeps the children from being collected until they're rooted
+            let gc = gcb.header.context.lock();
+            if gc.exclusive_borrows == 0 {
+                let erased = unsafe { self.erased().as_ref() };
+                let children = unsafe { tracer::record_children(&erased.val) };
+                break children
+                    .into_iter()
+                    .map(|child| unsafe { Gc::from_gcbox(child) })
+                    .collect::<Vec<_>>();
+            }
+            // The value may be being written to
+            drop(gc);
+            sys::thread::yield_now();
+        };
+        children.into_iter().for_each(f)
+    }
+
+    /// Marks the pointed to value so it survives the current collection, unless it's already marked
+    ///
+    /// The collector scans its children later. Outside of tracing done by the collector this does
+    /// nothing, so the only place to call it is a [`GcAble::mark`] impl, and only for a `Gc<_>`
+    /// directly contained in the value being traced.
+    ///
+    /// # Safety
+    /// Must only be called from [`GcAble::mark`], which only the collector calls while it holds the
+    /// context's lock. It may be called again for the same `Gc<_>` during the same trace, such as
+    /// through an `Arc` shared between objects, but must not be skipped for any child.
+    pub unsafe fn mark(&self) {
+        tracing_stats::record(Call::Mark);
+        tracer::check_registered(AllocAddr::from(self.gcbox.as_ptr()));
+        if unsafe { tracer::visit(self.erased(), || self.is_root.load(Ordering::Acquire)) } {
+            unsafe { self.set_root() }
+        }
+    }
+
+    /// The box this points to
+    ///
+    /// Panics in debug builds if it was already freed, see [`Gc::is_alive`]
+    pub(crate) fn gcb(&self) -> &GcBox<T> {
+        self.assert_alive();
+        unsafe { self.gcbox.as_ref() }
+    }
+
+    #[track_caller]
+    pub(crate) fn assert_alive(&self) {
+        let addr = AllocAddr::from(self.gcbox.as_ptr());
+        assert!(
+            tracer::is_registered(addr),
+            "a `Gc<{}>` was used after the object at {addr} it points to was freed",
+            core::any::type_name::<T>(),
+        );
+    }
+
+    /// The pointer to this `Gc<_>`'s box, as used by the collector
+    fn erased(&self) -> NonNull<GcBox<dyn GcAble>> {
+        let gcb = self.gcb();
+        (gcb.header.erase)(self.gcbox.cast())
+    }
+
+    /// Makes this no longer count as a root, which is needed once it's stored inside a `GcAble` value
+    ///
+    /// Does nothing if this already isn't a root, so calling it again is harmless. The crate calls
+    /// [`GcAble::set_not_root`] on a value as it's moved into a `Gc<_>`, and whenever a `Gc<_>`
+    /// may have been stored in it through a [`GcRefMut`] or [`GcCell`].
+    ///
+    /// # Safety
+    /// Must only be called while the value containing this is owned by a `Gc`, or is being moved
+    /// into one, otherwise the object this points to may be collected while still in use
     pub unsafe fn set_not_root(&self) {
-        let mut is_root = self.is_root.lock().unwrap();
-        if *is_root {
-            unsafe { self.dec_root_count() };
+        tracing_stats::record(Call::SetNotRoot);
+        if self.is_root.swap(false, Ordering::AcqRel) {
+            unsafe { self.change_root_count::<NegOne>() };
+        }
+    }
+    /// Undoes [`Gc::set_not_root`]
+    ///
+    /// # Safety
+    /// Must only be called once the value containing this has been moved out of its `Gc`
+    unsafe fn set_root(&self) {
+        // Only counts as a root once the root count is incremented, which panics if it overflows
+        if !self.is_root.load(Ordering::Acquire) {
+            unsafe { self.change_root_count::<PosOne>() };
+            self.is_root.store(true, Ordering::Release);
         }
-        *is_root = false;
     }
+    /// Keeps the pointed to object alive until a matching [`Gc::dec_root_count`], even if this
+    /// `Gc<_>` isn't a root
+    ///
+    /// Unlike [`Gc::set_not_root`], calls nest, each one adding to the object's root count. Panics
+    /// if the count overflows.
+    ///
+    /// # Safety
+    /// Must be balanced by a later call to [`Gc::dec_root_count`] on a `Gc<_>` pointing to the same
+    /// object, otherwise it leaks
     pub unsafe fn inc_root_count(&self) {
+        tracing_stats::record(Call::IncRootCount);
         unsafe { self.change_root_count::<PosOne>() }
     }
+    /// Undoes one [`Gc::inc_root_count`]
+    ///
+    /// # Safety
+    /// Must balance an earlier call to [`Gc::inc_root_count`] on a `Gc<_>` pointing to the same
+    /// object, otherwise it may be collected while still in use
     pub unsafe fn dec_root_count(&self) {
+        tracing_stats::record(Call::DecRootCount);
         unsafe { self.change_root_count::<NegOne>() }
     }
     unsafe fn change_root_count<Delta: IncOrDec>(&self) {
-        let gcb = unsafe { self.gcbox.as_ref() };
-        let mut rc = gcb.header.root_count.lock().unwrap();
+        let gcb = self.gcb();
+        tracing_stats::record_root_count(Delta::get().into());
         match Delta::get() {
             -1 => {
                 // Should never underflow
-                *rc -= 1;
+                let prev = gcb.header.root_count.fetch_sub(1, Ordering::SeqCst);
+                debug_assert_ne!(prev, 0);
             }
             1 => {
-                *rc = rc.checked_add(1).unwrap();
+                if !gcb.header.try_inc_root_count() {
+                    root_count_overflow(gcb.header.type_name)
+                }
             }
             _ => unreachable!(),
         }
     }
 }
 
-impl<T: GcAble> Clone for Gc<T> {
+/// A root count is a `u32`, which only a program leaking roots should ever exhaust
+#[cold]
+#[inline(never)]
+fn root_count_overflow(type_name: &'static str) -> ! {
+    panic!("{}", RootCountOverflow { type_name })
+}
+
+/// The root count of an object is at its maximum, so no more roots can point to it, see
+/// [`Gc::try_clone`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RootCountOverflow {
+    type_name: &'static str,
+}
+
+impl RootCountOverflow {
+    /// `std::any::type_name` of the object's value
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+impl core::fmt::Display for RootCountOverflow {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "the root count of a `Gc<{}>` overflowed, meaning more than {} roots point to it at \
+             once, which usually means `Gc<_>`s are being leaked with `mem::forget` or \
+             `Gc::into_raw`",
+            self.type_name,
+            u32::MAX,
+        )
+    }
+}
+
+impl core::error::Error for RootCountOverflow {}
+
+impl<T: ?Sized + GcAble> Clone for Gc<T> {
     fn clone(&self) -> Self {
-        unsafe { self.inc_root_count() };
-        Self {
-            is_root: Mutex::new(true),
-            gcbox: self.gcbox,
-        }
+        unsafe { Self::from_gcbox(self.gcbox) }
     }
 }
 
-impl<T: GcAble> Drop for Gc<T> {
+/// Only atomics are touched, never the context's lock, so dropping is fine after the context was
+/// shut down, from thread-local destructors, and while the lock is poisoned
+impl<T: ?Sized + GcAble> Drop for Gc<T> {
     fn drop(&mut self) {
-        if *self.is_root.lock().unwrap() {
+        // Leaking is better than aborting by panicking again while already unwinding
+        if sys::thread::panicking() && !self.is_alive() {
+            return;
+        }
+        self.gcb().header.dec_handle_count();
+        // Once this stops being a root, another thread may collect the object at any point
+        if *self.is_root.get_mut() {
             unsafe { self.dec_root_count() };
         }
     }
 }
 
-impl<T: GcAble> Deref for Gc<T> {
+impl<T: ?Sized + GcAble> Deref for Gc<T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
+        self.assert_alive();
         unsafe { &*self.as_ptr() }
     }
 }
 
-impl<T: GcAble> AsRef<T> for Gc<T> {
+impl<T: ?Sized + GcAble> AsRef<T> for Gc<T> {
     fn as_ref(&self) -> &T {
-        &*self
+        self
+    }
+}
+
+/// Borrows the value, consistently with the comparison and `Hash` impls, which all compare values
+/// like `Rc<T>`'s do, so a `HashMap<Gc<K>, V>` can be looked up with a `&K`. Use [`Gc::ptr_eq`]
+/// to compare pointers.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use gc::Gc;
+///
+/// let mut names = HashMap::new();
+/// names.insert(Gc::new(1u32), "one");
+/// names.insert(Gc::new(2u32), "two");
+///
+/// assert_eq!(names.get(&2), Some(&"two"));
+/// assert_eq!(names.get(&3), None);
+/// assert_eq!(Gc::new(1u32), Gc::new(1u32));
+/// assert!(Gc::new(1u32) < Gc::new(2u32));
+/// ```
+impl<T: ?Sized + GcAble> Borrow<T> for Gc<T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+/// Lets a `HashMap<Gc<String>, V>` be looked up with a `&str`, which hashes and compares the same
+/// as the `String` it borrows from
+///
+/// ```
+/// use std::collections::HashMap;
+/// use gc::Gc;
+///
+/// let mut ids = HashMap::new();
+/// ids.insert(Gc::new("one".to_string()), 1);
+/// ids.insert(Gc::new("two".to_string()), 2);
+///
+/// assert_eq!(ids.get("two"), Some(&2));
+/// assert_eq!(ids.get(&"one".to_string()), Some(&1));
+/// assert_eq!(ids.get("three"), None);
+/// ```
+impl Borrow<str> for Gc<String> {
+    fn borrow(&self) -> &str {
+        self
+    }
+}
+
+/// Compares the values, which compares any `Gc<_>`s in them the same way, so this never finishes
+/// on a cyclic graph, see [`Gc::structural_eq`]. This goes for `PartialEq` derived on a type with
+/// `Gc<_>` fields, and for the `PartialOrd`, `Ord` and `Hash` impls too.
+impl<T: ?Sized + GcAble + PartialEq> PartialEq for Gc<T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + GcAble + Eq> Eq for Gc<T> {}
+
+impl<T: ?Sized + GcAble + PartialOrd> PartialOrd for Gc<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: ?Sized + GcAble + Ord> Ord for Gc<T> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+/// Hashes the value, which never finishes on a cyclic graph, see [`Gc::structural_hash`]
+impl<T: ?Sized + GcAble + Hash> Hash for Gc<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+/// Allocates the default value in the global context, see [`Gc::new`]
+///
+/// ```
+/// use gc::{Gc, GcAble};
+///
+/// #[derive(Default)]
+/// struct Counter {
+///     count: Gc<i32>,
+/// }
+///
+/// # // SAFETY: every method forwards to `count`, the only `Gc<_>` in a `Counter`
+/// # unsafe impl GcAble for Counter {
+/// #     unsafe fn mark(&self) {
+/// #         unsafe { self.count.mark() }
+/// #     }
+/// #
+/// #     unsafe fn inc_root_count(&self) {
+/// #         unsafe { self.count.inc_root_count() }
+/// #     }
+/// #
+/// #     unsafe fn dec_root_count(&self) {
+/// #         unsafe { self.count.dec_root_count() }
+/// #     }
+/// #
+/// #     unsafe fn set_not_root(&self) {
+/// #         unsafe { self.count.set_not_root() }
+/// #     }
+/// # }
+/// #
+/// let counter = Gc::new(Counter::default());
+/// gc::force_collect();
+/// assert_eq!(*counter.count, 0);
+/// ```
+impl<T: GcAble + Default> Default for Gc<T> {
+    fn default() -> Self {
+        Gc::new(T::default())
+    }
+}
+
+/// Moves the value into the global context, see [`Gc::new`]
+///
+/// ```
+/// use gc::Gc;
+///
+/// fn store(val: impl Into<Gc<u32>>) -> Gc<u32> {
+///     val.into()
+/// }
+///
+/// let from_val: Gc<u32> = 1.into();
+/// let from_box: Gc<u32> = Box::new(2).into();
+/// let stored = store(3);
+/// gc::force_collect();
+/// assert_eq!((*from_val, *from_box, *stored), (1, 2, 3));
+/// ```
+impl<T: GcAble> From<T> for Gc<T> {
+    fn from(val: T) -> Self {
+        Gc::new(val)
+    }
+}
+
+/// Moves the boxed value into the global context, see [`Gc::from_box`]
+impl<T: GcAble> From<Box<T>> for Gc<T> {
+    fn from(owned_ptr: Box<T>) -> Self {
+        Gc::from_box(owned_ptr)
+    }
+}
+
+/// Formats the value, or with `{:#?}` the value along with the object's address, root count and
+/// whether it was marked by the last collection
+///
+/// ```
+/// use gc::Gc;
+///
+/// let gc = Gc::new(7u32);
+/// assert_eq!(format!("{gc:?}"), "7");
+///
+/// gc::force_collect();
+/// let meta = format!("{gc:#?}");
+/// assert!(meta.starts_with("Gc {\n    addr: 0x"));
+/// assert!(meta.contains("root_count: 1,"));
+/// assert!(meta.contains("marked: true,"));
+/// assert!(meta.contains("value: 7,"));
+/// ```
+impl<T: ?Sized + GcAble + Debug> Debug for Gc<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if !f.alternate() {
+            return write!(f, "{:?}", self.as_ref());
+        }
+        let header = &self.gcb().header;
+        f.debug_struct("Gc")
+            .field(
+                "addr",
+                &format_args!("{}", AllocAddr::from(self.gcbox.as_ptr())),
+            )
+            .field("root_count", &header.root_count())
+            .field("marked", &header.marked())
+            .field("value", &self.as_ref())
+            .finish()
     }
 }
 
-impl<T: GcAble + Debug> Debug for Gc<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self.as_ref())
+impl Gc<dyn GcAble> {
+    /// Converts this back into a `Gc<T>` if its value is a `T`, otherwise gives it back
+    ///
+    /// ```
+    /// use gc::{Gc, GcAble, GcContext};
+    ///
+    /// let ctx = GcContext::new();
+    /// let values: Vec<Gc<dyn GcAble>> = vec![
+    ///     Gc::into_dyn(ctx.alloc(1u32)),
+    ///     Gc::into_dyn(ctx.alloc(2u64)),
+    /// ];
+    /// assert_eq!(values[0].downcast_ref::<u32>(), Some(&1));
+    /// assert_eq!(values[0].downcast_ref::<u64>(), None);
+    /// assert_eq!(values[1].downcast_ref::<u64>(), Some(&2));
+    ///
+    /// let mut values = values.into_iter();
+    /// let one = values.next().unwrap();
+    /// // The wrong type gives the same object back, still rooted
+    /// let one = one.downcast::<u64>().unwrap_err();
+    /// ctx.force_collect();
+    /// let one: Gc<u32> = one.downcast().unwrap();
+    /// assert_eq!(*one, 1);
+    ///
+    /// drop((one, values));
+    /// ctx.force_collect();
+    /// ctx.assert_no_leaks();
+    /// ```
+    pub fn downcast<T: GcAble>(self) -> Result<Gc<T>, Self> {
+        if !self.is::<T>() {
+            return Err(self);
+        }
+        let (is_root, gcbox) = Gc::into_parts(self);
+        Ok(Gc {
+            is_root: AtomicBool::new(is_root),
+            gcbox: gcbox.cast(),
+        })
+    }
+
+    /// Returns a reference to the value if it's a `T`
+    pub fn downcast_ref<T: GcAble>(&self) -> Option<&T> {
+        self.is::<T>()
+            .then(|| unsafe { &*GcBox::val(self.gcbox.cast::<GcBox<T>>().as_ptr()) })
+    }
+
+    /// Whether the value is a `T`
+    ///
+    /// The value of a [`Gc::new_uninit`] box only counts as a `T` once it's initialized
+    pub fn is<T: GcAble>(&self) -> bool {
+        let header = &self.gcb().header;
+        header.type_id == TypeId::of::<T>() && header.initialized.load(Ordering::Acquire)
+    }
+}
+
+impl Debug for Gc<dyn GcAble> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let gcb = self.gcb();
+        write!(f, "Gc<{}>", gcb.header.type_name)
+    }
+}
+
+/// A unique mutable borrow of a `Gc<_>`'s value, see [`Gc::get_mut`]
+pub struct GcRefMut<'a, T: GcAble> {
+    gc: &'a mut Gc<T>,
+}
+
+impl<T: GcAble> Deref for GcRefMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.gc
+    }
+}
+
+impl<T: GcAble> DerefMut for GcRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut (*self.gc.gcbox.as_ptr()).val }
+    }
+}
+
+impl<T: GcAble> Drop for GcRefMut<'_, T> {
+    fn drop(&mut self) {
+        let gcb = self.gc.gcb();
+        let mut gc = gcb.header.context.lock();
+        // Any `Gc<_>` which was stored in the value is now reachable through it
+        unsafe { T::set_not_root(self.gc) };
+        gc.write_barrier(self.gc.gcbox);
+        gc.exclusive_borrows -= 1;
+    }
+}
+
+/// Keeps a `Gc<_>` rooted while alive, see [`Gc::root`]
+pub struct RootGuard<'a, T: ?Sized + GcAble> {
+    gc: &'a Gc<T>,
+}
+
+impl<T: ?Sized + GcAble> Drop for RootGuard<'_, T> {
+    fn drop(&mut self) {
+        unsafe { self.gc.dec_root_count() };
     }
 }
 
 /// An item which can be used and tracked by the Gc
+///
+/// `Arc<T>` isn't `GcAble`, since a clone of the `Arc` kept outside the heap would keep the value
+/// alive after the `Gc<_>`s in it stopped being roots, so they could be freed while still in use:
+///
+/// ```compile_fail
+/// use std::sync::Arc;
+/// use gc::Gc;
+///
+/// let shared = Gc::new(Arc::new(Gc::new(1u32)));
+/// ```
+///
+/// A `Gc<T>` is already shared, so it takes the place of the `Arc`:
+///
+/// ```
+/// use gc::Gc;
+///
+/// let shared = Gc::new(1u32);
+/// let (a, b) = (Gc::new(shared.clone()), Gc::new(shared));
+/// drop(a);
+/// gc::force_collect();
+/// assert_eq!(**b, 1);
+/// ```
+///
+/// Every method must call the method of the same name on each `GcAble` value directly contained in
+/// this one, which for a `Gc<_>` field is the method on [`Gc`] itself. The collector doesn't only
+/// call [`GcAble::mark`] to mark: it also calls it to list an object's children, to make them
+/// roots again when a value is moved out of its `Gc<_>`, and to check that they stopped being
+/// roots when it was moved in. Because of that, each method must visit the same `Gc<_>`s every
+/// time it's called, as long as the value isn't mutated in between.
+///
+/// A `Gc<_>` can be sent to and shared with other threads, and the collector may trace the value
+/// from its own thread, so every `GcAble` type is `Send + Sync`. `Cell` and `RefCell` aren't
+/// `Sync`, so they can't be fields of a `GcAble` value. [`GcCell`] is the interior mutability for
+/// values in the Gc instead, which also keeps the root counts of the `Gc<_>`s it holds right.
+///
+/// A value without any `Gc<_>` in it implements every method as a no-op. A struct or enum whose
+/// fields are all `GcAble` can derive it, which forwards every method to every field:
+///
+/// ```
+/// use gc::{Gc, GcAble};
+///
+/// #[derive(GcAble)]
+/// struct Node<T: GcAble> {
+///     value: T,
+///     next: Option<Gc<Node<T>>>,
+/// }
+///
+/// #[derive(GcAble)]
+/// enum Tree<const ARITY: usize> {
+///     Leaf(String),
+///     Branch { children: Vec<Gc<Tree<ARITY>>> },
+/// }
+///
+/// let list = Gc::new(Node { value: 1u32, next: Some(Gc::new(Node { value: 2, next: None })) });
+/// let leaf = Gc::new(Tree::Leaf("leaf".to_string()));
+/// let tree = Gc::new(Tree::<2>::Branch { children: vec![leaf] });
+/// gc::force_collect();
+/// assert_eq!(list.next.as_ref().unwrap().value, 2);
+/// let Tree::Branch { children } = &*tree else { unreachable!() };
+/// assert!(matches!(&*children[0], Tree::Leaf(name) if name == "leaf"));
+/// ```
+///
+/// The derived impl requires every type parameter to be `GcAble` and every lifetime to be
+/// `'static`. That's wrong when a type parameter isn't stored directly, in which case
+/// `#[gc(bound = "...")]` replaces the bounds on the type parameters with its own:
+///
+/// ```
+/// use gc::{Gc, GcAble};
+///
+/// #[derive(GcAble)]
+/// #[gc(bound = "I: Iterator + Send + Sync + 'static, I::Item: GcAble")]
+/// struct Last<I: Iterator> {
+///     item: Option<I::Item>,
+/// }
+///
+/// // `Range<u32>` isn't `GcAble`, but the `u32`s it yields are
+/// let last = Gc::new(Last::<std::ops::Range<u32>> { item: (0..3).last() });
+/// assert_eq!(last.item, Some(2));
+/// ```
+///
+/// Without the attribute, the impl would require `I: GcAble` instead:
+///
+/// ```compile_fail
+/// use gc::{Gc, GcAble};
+///
+/// #[derive(GcAble)]
+/// struct Last<I: Iterator> {
+///     item: Option<I::Item>,
+/// }
+///
+/// let last = Gc::new(Last::<std::ops::Range<u32>> { item: (0..3).last() });
+/// ```
+///
+/// A type parameter which is only used by `PhantomData<_>` and `PhantomPinned` fields only has to
+/// be `Send + Sync + 'static`, since those fields hold nothing:
+///
+/// ```
+/// use std::marker::PhantomData;
+/// use gc::{Gc, GcAble};
+///
+/// #[derive(GcAble)]
+/// struct Inner(u32);
+///
+/// #[derive(GcAble)]
+/// struct Typed<T> {
+///     gc: Gc<Inner>,
+///     _marker: PhantomData<T>,
+/// }
+///
+/// #[derive(GcAble)]
+/// enum Slot<T> {
+///     Empty,
+///     Full(Gc<Inner>, PhantomData<fn() -> T>),
+/// }
+///
+/// // Not `GcAble`
+/// struct Meters;
+///
+/// let typed = Gc::new(Typed::<Meters> { gc: Gc::new(Inner(3)), _marker: PhantomData });
+/// let slots = Gc::new(vec![Slot::<Meters>::Empty, Slot::Full(typed.gc.clone(), PhantomData)]);
+/// drop(typed);
+/// gc::force_collect();
+/// let Slot::Full(inner, _) = &slots[1] else { unreachable!() };
+/// assert_eq!(inner.0, 3);
+/// ```
+///
+/// For anything else, [`impl_gc_able!`] writes the four methods from a single list of children,
+/// or they can be written by hand:
+///
+/// ```
+/// use gc::{Gc, GcAble, GcCell};
+///
+/// struct Entry {
+///     id: u32,
+///     name: &'static str,
+///     key: Gc<u32>,
+///     value: GcCell<Gc<u64>>,
+/// }
+///
+/// // SAFETY: every method forwards to `key` and `value`, and `id` and `name` contain no `Gc<_>`
+/// // to forward to
+/// unsafe impl GcAble for Entry {
+///     unsafe fn mark(&self) {
+///         unsafe {
+///             self.key.mark();
+///             self.value.mark();
+///         }
+///     }
+///
+///     unsafe fn inc_root_count(&self) {
+///         unsafe {
+///             self.key.inc_root_count();
+///             self.value.inc_root_count();
+///         }
+///     }
+///
+///     unsafe fn dec_root_count(&self) {
+///         unsafe {
+///             self.key.dec_root_count();
+///             self.value.dec_root_count();
+///         }
+///     }
+///
+///     unsafe fn set_not_root(&self) {
+///         unsafe {
+///             self.key.set_not_root();
+///             self.value.set_not_root();
+///         }
+///     }
+/// }
+///
+/// let entry = Gc::new(Entry {
+///     id: 7,
+///     name: "answer",
+///     key: Gc::new(1),
+///     value: GcCell::new(Gc::new(42)),
+/// });
+///
+/// // `key` and `value` stopped being roots once moved into `entry`, and only survive because
+/// // the collector follows them through `mark`
+/// gc::force_collect();
+/// assert_eq!((entry.id, entry.name), (7, "answer"));
+/// assert_eq!((*entry.key, *entry.value.get()), (1, 42));
+/// ```
+///
+/// # Safety
+/// Every method must forward to the method of the same name on each `Gc<_>` directly contained in
+/// this value, and to nothing else. Each method must visit the same `Gc<_>`s as the others, and
+/// must not allocate, collect, or otherwise call into the Gc, since the collector may be holding
+/// the context's lock while it runs.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't be stored in a `Gc<_>`",
+    label = "`{Self}` doesn't implement `GcAble`",
+    note = "derive `GcAble` or use `gc::impl_gc_able!` for your own types, see the docs of `GcAble`",
+    note = "`Cell` and `RefCell` can never be `GcAble` since they aren't `Sync`, use `gc::GcCell` instead"
+)]
 pub unsafe trait GcAble: Send + Sync + 'static {
     /// Call `Gc::mark(..)` on every `Gc<_>` in this struct
+    ///
+    /// # Safety
+    /// Only the crate calls this, see [`Gc::mark`]
     unsafe fn mark(&self);
     /// Call `Gc::inc_root_count` on every `Gc<_>` in this struct
+    ///
+    /// # Safety
+    /// Must be balanced by a later call to [`GcAble::dec_root_count`] on the same value, see
+    /// [`Gc::inc_root_count`]
     unsafe fn inc_root_count(&self);
     /// Call `Gc::dec_root_count` on every `Gc<_>` in this struct
+    ///
+    /// # Safety
+    /// Must balance an earlier call to [`GcAble::inc_root_count`] on the same value, see
+    /// [`Gc::dec_root_count`]
     unsafe fn dec_root_count(&self);
     /// Call `Gc::set_not_root` on every `Gc<_>` in this struct
+    ///
+    /// # Safety
+    /// See [`Gc::set_not_root`]
     unsafe fn set_not_root(&self);
 }
 
+/// Implements [`GcAble`] for a type from a single list of the `GcAble` values it contains
+///
+/// The closure-like body is given the value and a `visit` function, which must be called once on
+/// each field that may contain a `Gc<_>`. Every method of the impl runs the same body, so the
+/// children can't get out of sync between them the way they can in a hand-written impl.
+///
+/// ```
+/// use gc::{impl_gc_able, Gc};
+///
+/// struct Scope {
+///     depth: usize,
+///     parent: Option<Gc<Scope>>,
+///     locals: Vec<Gc<u64>>,
+/// }
+///
+/// impl_gc_able!(Scope => |scope, visit| {
+///     if let Some(parent) = &scope.parent {
+///         visit(parent);
+///     }
+///     for local in &scope.locals {
+///         visit(local);
+///     }
+/// });
+///
+/// let global = Gc::new(Scope { depth: 0, parent: None, locals: Vec::new() });
+/// let inner = Gc::new(Scope {
+///     depth: 1,
+///     parent: Some(global),
+///     locals: vec![Gc::new(42)],
+/// });
+/// gc::force_collect();
+/// assert_eq!(inner.parent.as_ref().unwrap().depth, 0);
+/// assert_eq!(*inner.locals[0], 42);
+/// ```
+///
+/// The body must visit every `Gc<_>` in the value and nothing else, the same way every time as
+/// long as the value isn't mutated. It must not call into the Gc either, see the safety section of
+/// [`GcAble`].
+#[macro_export]
+macro_rules! impl_gc_able {
+    ($t:ty => |$this:ident, $visit:ident| $body:expr) => {
+        $crate::impl_gc_able!(@impl $t, $this, $visit, $body, [
+            mark, inc_root_count, dec_root_count, set_not_root
+        ]);
+    };
+    (@impl $t:ty, $this:ident, $visit:ident, $body:expr, [$($method:ident),*]) => {
+        // SAFETY: the caller guarantees `$body` visits exactly the value's children
+        unsafe impl $crate::GcAble for $t {
+            $(
+                unsafe fn $method(&self) {
+                    #[allow(unused_variables)]
+                    let $this = self;
+                    #[allow(unused_mut, unused_variables)]
+                    let mut $visit = |child: &dyn $crate::GcAble| unsafe { child.$method() };
+                    $body;
+                }
+            )*
+        }
+    };
+}
+
+/// Hook which runs when the collector finds an object unreachable, registered with [`Gc::new_finalized`]
+///
+/// When a collection finds a set of objects unreachable, first every finalizer in the set is run,
+/// then every value in the set is dropped, and only then is any of their memory deallocated.
+/// This means that a finalizer may freely read the `Gc<_>`s in its object, even if they point
+/// to other unreachable objects, while a `Drop` impl must not dereference them.
+///
+/// A finalizer runs exactly once per object, and never for an object which is still reachable
+/// from a root, even if that object isn't a root itself.
+///
+/// A finalizer must not resurrect its object, meaning it must not clone any `Gc<_>` pointing to
+/// an unreachable object. It runs while the collector holds the global lock, so it also must not
+/// allocate or otherwise call into the Gc.
+///
+/// With [`GcConfig::defer_finalizers`], finalizers are queued by the collection which found their
+/// objects unreachable instead, and run once it has released the lock. Everything reachable from
+/// a queued object is kept alive until its finalizer has run, so a deferred finalizer may allocate,
+/// call into the Gc and clone the `Gc<_>`s it holds. Its object is freed by a later collection,
+/// like any other.
+pub trait Finalize: GcAble {
+    fn finalize(&self);
+}
+
+unsafe impl<T: ?Sized + GcAble> GcAble for Gc<T> {
+    unsafe fn mark(&self) {
+        unsafe { Gc::mark(self) }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { Gc::inc_root_count(self) }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { Gc::dec_root_count(self) }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { Gc::set_not_root(self) }
+    }
+}
+
+unsafe impl<T: GcAble> GcAble for Pin<Gc<T>> {
+    unsafe fn mark(&self) {
+        unsafe { Gc::mark(pinned_gc(self)) }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { Gc::inc_root_count(pinned_gc(self)) }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { Gc::dec_root_count(pinned_gc(self)) }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { Gc::set_not_root(pinned_gc(self)) }
+    }
+}
+
+/// The `Gc<_>` inside `pin`, which must only be used for bookkeeping
+fn pinned_gc<T: GcAble>(pin: &Pin<Gc<T>>) -> &Gc<T> {
+    // SAFETY: `Pin` is `repr(transparent)`, and changing the root count doesn't move the value
+    unsafe { &*(pin as *const Pin<Gc<T>> as *const Gc<T>) }
+}
+
+unsafe impl<T: GcAble> GcAble for Option<T> {
+    unsafe fn mark(&self) {
+        if let Some(val) = self {
+            unsafe { val.mark() }
+        }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        if let Some(val) = self {
+            unsafe { val.inc_root_count() }
+        }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        if let Some(val) = self {
+            unsafe { val.dec_root_count() }
+        }
+    }
+
+    unsafe fn set_not_root(&self) {
+        if let Some(val) = self {
+            unsafe { val.set_not_root() }
+        }
+    }
+}
+
+unsafe impl<T: GcAble> GcAble for Vec<T> {
+    unsafe fn mark(&self) {
+        unsafe { self[..].mark() }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { self[..].inc_root_count() }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self[..].dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { self[..].set_not_root() }
+    }
+}
+
+unsafe impl<T: ?Sized + GcAble> GcAble for Box<T> {
+    unsafe fn mark(&self) {
+        unsafe { T::mark(self) }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { T::inc_root_count(self) }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { T::dec_root_count(self) }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { T::set_not_root(self) }
+    }
+}
+
 macro_rules! impl_gc_no_children {
     ($t:ty) => {
         unsafe impl GcAble for $t {
@@ -307,6 +3001,22 @@ macro_rules! impl_gc_no_children {
 
             unsafe fn set_not_root(&self) {}
         }
+
+        impl DeepClone for $t {
+            fn clone_deep(&self, _: &mut DeepCloner) -> Self {
+                self.clone()
+            }
+        }
+
+        unsafe impl LocalGcAble for $t {
+            unsafe fn mark(&self) {}
+
+            unsafe fn inc_root_count(&self) {}
+
+            unsafe fn dec_root_count(&self) {}
+
+            unsafe fn set_not_root(&self) {}
+        }
     };
 }
 
@@ -323,3 +3033,34 @@ impl_gc_no_children!(u16);
 impl_gc_no_children!(u32);
 impl_gc_no_children!(u64);
 impl_gc_no_children!(u128);
+
+impl_gc_no_children!(String);
+
+impl_gc_no_children!(PhantomPinned);
+
+/// Holds nothing, whatever `T` is
+unsafe impl<T: ?Sized + Send + Sync + 'static> GcAble for PhantomData<T> {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+impl<T: ?Sized + Send + Sync + 'static> DeepClone for PhantomData<T> {
+    fn clone_deep(&self, _: &mut DeepCloner) -> Self {
+        PhantomData
+    }
+}
+
+unsafe impl<T: ?Sized + 'static> LocalGcAble for PhantomData<T> {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
@@ -0,0 +1,484 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    alloc::Layout,
+    cell::{Cell, Ref, RefCell},
+    fmt::Debug,
+    ops::Deref,
+    ptr::{self, addr_of_mut, NonNull},
+};
+
+/// The number of allocations after which the thread's local heap is collected
+const ALLOC_WATERMARK: usize = 1024;
+
+/// An item which can be used and tracked by [`LocalGc`]
+///
+/// This is [`crate::GcAble`] for values which never leave their thread, so it isn't bound by
+/// `Send + Sync`. It's derived the same way too, with `#[derive(LocalGcAble)]`.
+///
+/// # Safety
+/// Every method must forward to the method of the same name on each `LocalGc<_>` directly
+/// contained in this value, and to nothing else
+pub unsafe trait LocalGcAble: 'static {
+    /// Call `LocalGc::mark(..)` on every `LocalGc<_>` in this struct
+    ///
+    /// # Safety
+    /// See [`LocalGc::mark`]
+    unsafe fn mark(&self);
+    /// Call `LocalGc::inc_root_count` on every `LocalGc<_>` in this struct
+    ///
+    /// # Safety
+    /// See [`LocalGc::inc_root_count`]
+    unsafe fn inc_root_count(&self);
+    /// Call `LocalGc::dec_root_count` on every `LocalGc<_>` in this struct
+    ///
+    /// # Safety
+    /// See [`LocalGc::dec_root_count`]
+    unsafe fn dec_root_count(&self);
+    /// Call `LocalGc::set_not_root` on every `LocalGc<_>` in this struct
+    ///
+    /// # Safety
+    /// See [`LocalGc::set_not_root`]
+    unsafe fn set_not_root(&self);
+}
+
+/// The current thread's heap
+///
+/// The lock held during a collection in [`crate::GcContext`] is replaced by temporarily taking
+/// `objects`, so user code run by the collector may still allocate
+struct LocalAlloc {
+    objects: Vec<NonNull<LocalBox<dyn LocalGcAble>>>,
+    /// Objects which are marked but whose children may not be yet
+    grey: Vec<NonNull<LocalBox<dyn LocalGcAble>>>,
+    allocs_since_collection: usize,
+    collecting: bool,
+    /// Whether `LocalGc::mark` makes each `LocalGc<_>` a root again instead, see `root_children`
+    rooting: bool,
+}
+
+thread_local! {
+    static LOCAL: RefCell<LocalAlloc> = const {
+        RefCell::new(LocalAlloc {
+            objects: Vec::new(),
+            grey: Vec::new(),
+            allocs_since_collection: 0,
+            collecting: false,
+            rooting: false,
+        })
+    };
+}
+
+/// Resets `LocalAlloc::collecting` once dropped, even if unwinding
+struct CollectingGuard;
+
+impl Drop for CollectingGuard {
+    fn drop(&mut self) {
+        LOCAL.with_borrow_mut(|local| local.collecting = false);
+    }
+}
+
+/// Makes sure all memory in the current thread's local heap that can be freed at the moment is freed
+///
+/// Does nothing if called from user code run by a collection of the local heap
+pub fn force_collect_local() {
+    let objects = LOCAL.with_borrow_mut(|local| {
+        if local.collecting {
+            return None;
+        }
+        local.collecting = true;
+        local.allocs_since_collection = 0;
+        Some(core::mem::take(&mut local.objects))
+    });
+    let Some(objects) = objects else {
+        return;
+    };
+    let _guard = CollectingGuard;
+
+    // Mark
+    for nn in &objects {
+        unsafe { nn.as_ref() }.header.marked.set(false);
+    }
+    for nn in &objects {
+        let header = &unsafe { nn.as_ref() }.header;
+        if header.root_count.get() > 0 {
+            header.marked.set(true);
+            LOCAL.with_borrow_mut(|local| local.grey.push(*nn));
+        }
+    }
+    while let Some(nn) = LOCAL.with_borrow_mut(|local| local.grey.pop()) {
+        unsafe { nn.as_ref().val.mark() };
+    }
+
+    // Sweep
+    let (live, unreachable): (Vec<_>, Vec<_>) = objects
+        .into_iter()
+        .partition(|nn| unsafe { nn.as_ref() }.header.marked.get());
+    LOCAL.with_borrow_mut(|local| local.objects.extend(live));
+
+    for nn in &unreachable {
+        unsafe { ptr::drop_in_place(addr_of_mut!((*nn.as_ptr()).val)) }
+    }
+    for nn in unreachable {
+        let layout = unsafe { nn.as_ref() }.header.layout;
+        unsafe { alloc::alloc::dealloc(nn.as_ptr() as *mut u8, layout) };
+    }
+}
+
+/// Makes every `LocalGc<_>` directly contained in `val` a root again, undoing
+/// [`LocalGcAble::set_not_root`]
+///
+/// # Safety
+/// `val` must have just been moved out of the object it was in
+unsafe fn root_children(val: &dyn LocalGcAble) {
+    LOCAL.with_borrow_mut(|local| local.rooting = true);
+    unsafe { val.mark() };
+    LOCAL.with_borrow_mut(|local| local.rooting = false);
+}
+
+fn rooting() -> bool {
+    LOCAL.with_borrow(|local| local.rooting)
+}
+
+/// Bookkeeping for a single local allocation
+struct LocalBoxHeader {
+    root_count: Cell<u32>,
+    marked: Cell<bool>,
+    /// The layout of the whole `LocalBox<_>`, which it's deallocated with
+    layout: Layout,
+}
+
+#[repr(C)]
+struct LocalBox<T: ?Sized + LocalGcAble> {
+    header: LocalBoxHeader,
+    val: T,
+}
+
+/// A garbage collected pointer into the current thread's local heap
+///
+/// Unlike [`crate::Gc`], this never takes a lock or touches an atomic, but it can't be sent to
+/// another thread. The local heap is collected synchronously once enough has been allocated, or by
+/// [`force_collect_local`]. Objects which are still live once their thread exits are leaked.
+///
+/// ```compile_fail
+/// let gc = gc::LocalGc::new(1u32);
+/// std::thread::spawn(move || *gc);
+/// ```
+pub struct LocalGc<T: LocalGcAble> {
+    is_root: Cell<bool>,
+    gcbox: NonNull<LocalBox<T>>,
+}
+
+impl<T: LocalGcAble> LocalGc<T> {
+    /// Moves `val` into the current thread's local heap
+    pub fn new(val: T) -> LocalGc<T> {
+        let collect = LOCAL.with_borrow_mut(|local| {
+            local.allocs_since_collection += 1;
+            local.allocs_since_collection >= ALLOC_WATERMARK
+        });
+        if collect {
+            force_collect_local();
+        }
+
+        unsafe { val.set_not_root() };
+        let layout = Layout::new::<LocalBox<T>>();
+        let gcbox = match NonNull::new(unsafe { alloc::alloc::alloc(layout) }) {
+            Some(gcbox) => gcbox.cast::<LocalBox<T>>(),
+            None => alloc::alloc::handle_alloc_error(layout),
+        };
+        unsafe {
+            gcbox.as_ptr().write(LocalBox {
+                header: LocalBoxHeader {
+                    root_count: Cell::new(1),
+                    marked: Cell::new(false),
+                    layout,
+                },
+                val,
+            })
+        };
+        LOCAL.with_borrow_mut(|local| local.objects.push(gcbox));
+
+        LocalGc {
+            is_root: Cell::new(true),
+            gcbox,
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const T {
+        unsafe { ptr::addr_of!((*self.gcbox.as_ptr()).val) }
+    }
+
+    /// Marks the pointed to value so it survives the current collection, unless it's already marked
+    ///
+    /// # Safety
+    /// Must only be called by the local collector
+    pub unsafe fn mark(&self) {
+        if rooting() {
+            if !self.is_root.replace(true) {
+                unsafe { self.inc_root_count() };
+            }
+            return;
+        }
+        let header = &unsafe { self.gcbox.as_ref() }.header;
+        if header.marked.replace(true) {
+            return;
+        }
+        let nn: NonNull<LocalBox<dyn LocalGcAble>> = self.gcbox;
+        LOCAL.with_borrow_mut(|local| local.grey.push(nn));
+    }
+
+    /// Makes this no longer count as a root, which is needed once it's stored inside a
+    /// `LocalGcAble` value
+    ///
+    /// Does nothing if this already isn't a root
+    ///
+    /// # Safety
+    /// Must only be called while the value containing this is owned by a `LocalGc`, or is being
+    /// moved into one
+    pub unsafe fn set_not_root(&self) {
+        if self.is_root.replace(false) {
+            unsafe { self.dec_root_count() };
+        }
+    }
+    /// # Safety
+    /// Must be balanced by a later call to [`LocalGc::dec_root_count`]
+    pub unsafe fn inc_root_count(&self) {
+        let rc = &unsafe { self.gcbox.as_ref() }.header.root_count;
+        rc.set(rc.get().checked_add(1).unwrap());
+    }
+    /// # Safety
+    /// Must balance an earlier call to [`LocalGc::inc_root_count`]
+    pub unsafe fn dec_root_count(&self) {
+        let rc = &unsafe { self.gcbox.as_ref() }.header.root_count;
+        debug_assert_ne!(rc.get(), 0);
+        rc.set(rc.get() - 1);
+    }
+}
+
+impl<T: LocalGcAble> Clone for LocalGc<T> {
+    fn clone(&self) -> Self {
+        let gc = LocalGc {
+            is_root: Cell::new(true),
+            gcbox: self.gcbox,
+        };
+        unsafe { gc.inc_root_count() };
+        gc
+    }
+}
+
+impl<T: LocalGcAble> Drop for LocalGc<T> {
+    fn drop(&mut self) {
+        if self.is_root.get() {
+            unsafe { self.dec_root_count() };
+        }
+    }
+}
+
+impl<T: LocalGcAble> Deref for LocalGc<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.as_ptr() }
+    }
+}
+
+impl<T: LocalGcAble + Debug> Debug for LocalGc<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?}", **self)
+    }
+}
+
+unsafe impl<T: LocalGcAble> LocalGcAble for LocalGc<T> {
+    unsafe fn mark(&self) {
+        unsafe { LocalGc::mark(self) }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { LocalGc::inc_root_count(self) }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { LocalGc::dec_root_count(self) }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { LocalGc::set_not_root(self) }
+    }
+}
+
+unsafe impl<T: LocalGcAble> LocalGcAble for Option<T> {
+    unsafe fn mark(&self) {
+        if let Some(val) = self {
+            unsafe { val.mark() }
+        }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        if let Some(val) = self {
+            unsafe { val.inc_root_count() }
+        }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        if let Some(val) = self {
+            unsafe { val.dec_root_count() }
+        }
+    }
+
+    unsafe fn set_not_root(&self) {
+        if let Some(val) = self {
+            unsafe { val.set_not_root() }
+        }
+    }
+}
+
+unsafe impl<T: LocalGcAble> LocalGcAble for Vec<T> {
+    unsafe fn mark(&self) {
+        self.iter().for_each(|val| unsafe { val.mark() })
+    }
+
+    unsafe fn inc_root_count(&self) {
+        self.iter().for_each(|val| unsafe { val.inc_root_count() })
+    }
+
+    unsafe fn dec_root_count(&self) {
+        self.iter().for_each(|val| unsafe { val.dec_root_count() })
+    }
+
+    unsafe fn set_not_root(&self) {
+        self.iter().for_each(|val| unsafe { val.set_not_root() })
+    }
+}
+
+unsafe impl<T: ?Sized + LocalGcAble> LocalGcAble for Box<T> {
+    unsafe fn mark(&self) {
+        unsafe { T::mark(self) }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { T::inc_root_count(self) }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { T::dec_root_count(self) }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { T::set_not_root(self) }
+    }
+}
+
+/// A mutable memory location which can be stored inside a `LocalGcAble` value, like
+/// [`crate::GcCell`] for [`LocalGc`]
+///
+/// Replacing the contents keeps the root counts of the `LocalGc<_>`s going in and coming out
+/// correct, which is what makes cycles possible:
+///
+/// ```
+/// use gc::{force_collect_local, LocalGc, LocalGcAble, LocalGcCell};
+///
+/// #[derive(LocalGcAble)]
+/// struct Node {
+///     next: LocalGcCell<Option<LocalGc<Node>>>,
+/// }
+///
+/// let a = LocalGc::new(Node { next: LocalGcCell::new(None) });
+/// let b = LocalGc::new(Node { next: LocalGcCell::new(Some(a.clone())) });
+/// a.next.set(Some(b.clone()));
+/// // Taken out of `b`, so it's a root again
+/// let taken = b.next.replace(None).unwrap();
+/// drop((a, b));
+/// force_collect_local();
+/// assert!(taken.next.borrow().is_some());
+/// ```
+pub struct LocalGcCell<T: LocalGcAble> {
+    value: RefCell<T>,
+    /// Set once this is stored in a `LocalGc<_>`, after which the `LocalGc<_>`s in `value` aren't
+    /// roots
+    managed: Cell<bool>,
+}
+
+impl<T: LocalGcAble> LocalGcCell<T> {
+    pub fn new(val: T) -> Self {
+        Self {
+            value: RefCell::new(val),
+            managed: Cell::new(false),
+        }
+    }
+
+    /// Returns a clone of the contents
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        self.borrow().clone()
+    }
+
+    /// Immutably borrows the contents
+    ///
+    /// # Panics
+    /// If the contents are being replaced
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.value.borrow()
+    }
+
+    /// Replaces the contents, dropping the old value
+    pub fn set(&self, val: T) {
+        drop(self.replace(val))
+    }
+
+    /// Replaces the contents, returning the old value
+    ///
+    /// The `LocalGc<_>`s in the returned value are roots again, so it may be kept around freely
+    ///
+    /// # Panics
+    /// If the contents are borrowed
+    pub fn replace(&self, val: T) -> T {
+        let old = self.value.replace(val);
+        if self.managed.get() {
+            unsafe {
+                self.value.borrow().set_not_root();
+                root_children(&old);
+            }
+        }
+        old
+    }
+}
+
+impl<T: LocalGcAble + Default> Default for LocalGcCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: LocalGcAble + Debug> Debug for LocalGcCell<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut d = f.debug_struct("LocalGcCell");
+        match self.value.try_borrow() {
+            Ok(value) => d.field("value", &*value),
+            Err(_) => d.field("value", &format_args!("<borrowed>")),
+        };
+        d.finish()
+    }
+}
+
+unsafe impl<T: LocalGcAble> LocalGcAble for LocalGcCell<T> {
+    unsafe fn mark(&self) {
+        // This is being moved out of its `LocalGc<_>` along with its containing value
+        if rooting() {
+            self.managed.set(false);
+        }
+        unsafe { self.value.borrow().mark() }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { self.value.borrow().inc_root_count() }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { self.value.borrow().dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        self.managed.set(true);
+        unsafe { self.value.borrow().set_not_root() }
+    }
+}
@@ -0,0 +1,138 @@
+use alloc::{sync::Arc, vec::Vec};
+use core::{fmt::Debug, ops::Deref, ptr::NonNull};
+
+use crate::{
+    context::ContextInner,
+    sys::sync::{Mutex, MutexGuard, PoisonError},
+    Gc, GcAble, GcBox, GcContext,
+};
+
+/// The objects in a `RootSet`, as the collector sees them, see [`crate::GcAlloc::grey_roots`]
+#[derive(Default)]
+pub(crate) struct RootList(Mutex<Vec<NonNull<GcBox<dyn GcAble>>>>);
+
+// Safety: the pointers are only dereferenced by the collector, while holding the context's lock
+unsafe impl Send for RootList {}
+// Safety: see `Send`
+unsafe impl Sync for RootList {}
+
+impl RootList {
+    pub fn lock(&self) -> MutexGuard<'_, Vec<NonNull<GcBox<dyn GcAble>>>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A stack of `Gc<T>`s which are roots without each counting as one, such as an interpreter's
+/// value stack
+///
+/// The collector marks the objects in every `RootSet` of a context directly, so pushing and popping
+/// only touches this set's own lock. Keeping the same `Gc<_>`s in a rooted container object
+/// instead means tracing the whole container every collection, and a `Vec` of root `Gc<_>`s
+/// changes each object's root count on every push and pop.
+///
+/// ```
+/// use gc::{Gc, GcContext};
+///
+/// let ctx = GcContext::new();
+/// let mut stack = ctx.root_set();
+/// stack.push(ctx.alloc(1u32));
+/// stack.push(ctx.alloc(2u32));
+/// ctx.force_collect();
+/// assert_eq!(ctx.stats().live_allocations, 2);
+///
+/// assert_eq!(*stack.pop().unwrap(), 2);
+/// ctx.force_collect();
+/// assert_eq!(ctx.stats().live_allocations, 1);
+/// assert_eq!(*stack[0], 1);
+/// ```
+pub struct RootSet<T: ?Sized + GcAble> {
+    context: Arc<ContextInner>,
+    list: Arc<RootList>,
+    /// The objects in `list`, as `Gc<_>`s which aren't roots
+    gcs: Vec<Gc<T>>,
+}
+
+impl<T: ?Sized + GcAble> RootSet<T> {
+    /// Creates an empty set in the global context
+    pub fn new() -> Self {
+        GcContext::global().root_set()
+    }
+
+    pub(crate) fn new_in(context: &Arc<ContextInner>) -> Self {
+        let list = Arc::new(RootList::default());
+        context.lock().root_sets.push(Arc::clone(&list));
+        Self {
+            context: Arc::clone(context),
+            list,
+            gcs: Vec::new(),
+        }
+    }
+
+    /// Adds `gc` to the top of the stack, keeping its object alive until it's popped off
+    ///
+    /// Panics if `gc` belongs to a different context than this set
+    pub fn push(&mut self, gc: Gc<T>) {
+        assert!(
+            Arc::ptr_eq(&gc.gcb().header.context, &self.context),
+            "a `Gc<_>` was pushed onto a `RootSet` of another context",
+        );
+        let erased = gc.erased();
+        let mut list = self.list.lock();
+        list.push(erased);
+        // Only once it's in the list, so the collector finds it one way or the other
+        unsafe { gc.set_not_root() };
+        drop(list);
+        self.gcs.push(gc);
+    }
+
+    /// Removes the `Gc<_>` on top of the stack, which is a root again
+    pub fn pop(&mut self) -> Option<Gc<T>> {
+        let gc = self.gcs.pop()?;
+        let mut list = self.list.lock();
+        // Before it's out of the list, so the collector finds it one way or the other
+        unsafe { gc.set_root() };
+        list.pop();
+        Some(gc)
+    }
+
+    /// Removes every `Gc<_>` above the first `len`
+    pub fn truncate(&mut self, len: usize) {
+        self.list.lock().truncate(len);
+        self.gcs.truncate(len);
+    }
+
+    /// Removes every `Gc<_>`
+    pub fn clear(&mut self) {
+        self.truncate(0)
+    }
+}
+
+impl<T: ?Sized + GcAble> Default for RootSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The `Gc<_>`s aren't roots themselves, but cloning one gives a root
+impl<T: ?Sized + GcAble> Deref for RootSet<T> {
+    type Target = [Gc<T>];
+
+    fn deref(&self) -> &[Gc<T>] {
+        &self.gcs
+    }
+}
+
+impl<T: ?Sized + GcAble + Debug> Debug for RootSet<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(&self.gcs).finish()
+    }
+}
+
+impl<T: ?Sized + GcAble> Drop for RootSet<T> {
+    fn drop(&mut self) {
+        self.context
+            .lock()
+            .root_sets
+            .retain(|list| !Arc::ptr_eq(list, &self.list));
+    }
+}
@@ -0,0 +1,237 @@
+use alloc::vec::Vec;
+use core::{cell::RefCell, ptr::NonNull, sync::atomic::Ordering};
+
+use crate::{
+    context::ContextId,
+    sys::collections::{HashMap, HashSet},
+    tracer, AllocAddr, GcAble, GcAlloc, GcBox, GcContext, Sweep, SweepPhase,
+};
+
+/// The objects allocated on one thread while inside a [`GcContext::scope`]
+struct Scope {
+    ctx: ContextId,
+    /// The address and serial of each object, since an object which was collected before the
+    /// scope ended may have had its address reused by another
+    allocs: Vec<(AllocAddr, u64)>,
+}
+
+thread_local! {
+    /// The scopes this thread is inside of, innermost last
+    static SCOPES: RefCell<Vec<Scope>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Tags the object at `addr` with the innermost scope of `ctx` this thread is inside of, if any
+pub(crate) fn registered(ctx: ContextId, addr: AllocAddr, serial: u64) {
+    let _ = SCOPES.try_with(|scopes| {
+        let mut scopes = scopes.borrow_mut();
+        if let Some(scope) = scopes.iter_mut().rev().find(|scope| scope.ctx == ctx) {
+            scope.allocs.push((addr, serial));
+        }
+    });
+}
+
+/// Leaves the innermost scope if the closure passed to `GcContext::scope` panics, leaving the
+/// objects allocated in it to the collector
+struct Unwind;
+
+impl Drop for Unwind {
+    fn drop(&mut self) {
+        let _ = SCOPES.try_with(|scopes| scopes.borrow_mut().pop());
+    }
+}
+
+/// An object allocated in a scope which is still referenced by one allocated outside of it
+struct Escape {
+    type_name: &'static str,
+    referrer: &'static str,
+}
+
+impl GcContext {
+    /// Runs `f`, then frees every object this thread allocated in this context while `f` ran,
+    /// without tracing the rest of the heap
+    ///
+    /// This is region-based collection for request or frame shaped workloads, where nearly
+    /// everything allocated while handling one request is garbage once it's handled. Cycles are
+    /// freed along with everything else, since they don't need to be found to be unreachable.
+    ///
+    /// Objects which are still roots when `f` returns escape the scope and are kept, along with
+    /// every object from the scope they reach, which includes any `Gc<_>` in the value `f`
+    /// returns. Kept objects are left to the collector, or to the enclosing scope if this is
+    /// nested in another scope of the same context. If `f` panics, nothing is freed. With
+    /// [`crate::GcConfig::defer_finalizers`], objects whose finalizer hasn't run yet are kept the
+    /// same way until it has.
+    ///
+    /// Objects allocated by other threads, or in other contexts, aren't part of the scope.
+    ///
+    /// ```
+    /// use gc::{Gc, GcCell, GcContext};
+    ///
+    /// #[derive(gc::GcAble)]
+    /// struct Node {
+    ///     next: GcCell<Option<Gc<Node>>>,
+    /// }
+    ///
+    /// let ctx = GcContext::new();
+    /// let kept = unsafe {
+    ///     ctx.scope(|| {
+    ///         let a = ctx.alloc(Node { next: GcCell::new(None) });
+    ///         let b = ctx.alloc(Node { next: GcCell::new(Some(a.clone())) });
+    ///         a.next.set(Some(b));
+    ///         ctx.alloc(Node { next: GcCell::new(None) })
+    ///     })
+    /// };
+    /// // The cycle is gone without a collection, and only the returned node is left
+    /// assert_eq!(ctx.stats().live_allocations, 1);
+    /// # drop(kept);
+    /// ```
+    ///
+    /// # Safety
+    /// No object allocated in the scope may still be referenced, when `f` returns, by a `Gc<_>`
+    /// stored in an object allocated outside of it, unless the object is also reachable from a
+    /// root which was allocated in the scope. Such a `Gc<_>` would point to freed memory
+    /// otherwise.
+    ///
+    /// In debug builds, the whole heap is checked for such references before anything is freed.
+    /// If there are any, nothing is freed and this panics instead.
+    pub unsafe fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        SCOPES.with(|scopes| {
+            scopes.borrow_mut().push(Scope {
+                ctx: self.inner.id,
+                allocs: Vec::new(),
+            })
+        });
+        let unwind = Unwind;
+        let res = f();
+        core::mem::forget(unwind);
+        let scope = SCOPES.with(|scopes| scopes.borrow_mut().pop()).unwrap();
+
+        let kept = self.lock().free_scope(scope.allocs);
+        match kept {
+            Ok(kept) => {
+                for (addr, serial) in kept {
+                    registered(self.inner.id, addr, serial);
+                }
+            }
+            Err(Escape {
+                type_name,
+                referrer,
+            }) => panic!(
+                "a `{type_name}` allocated in `GcContext::scope` is still referenced by a \
+                 `{referrer}` allocated outside of it, so nothing allocated in the scope was freed"
+            ),
+        }
+        res
+    }
+}
+
+impl GcAlloc {
+    /// Frees every object in `allocs` which isn't reachable from one of them which is a root,
+    /// returning the ones which weren't freed
+    fn free_scope(
+        &mut self,
+        allocs: Vec<(AllocAddr, u64)>,
+    ) -> Result<Vec<(AllocAddr, u64)>, Escape> {
+        // Values can't be traced while they may be written to, so everything is left to the
+        // collector instead
+        if self.exclusive_borrows > 0 {
+            return Ok(allocs);
+        }
+        // The reclaiming below needs `sweeping` to itself
+        self.sweep_step(usize::MAX);
+
+        let mut tagged: HashMap<AllocAddr, NonNull<GcBox<dyn GcAble>>> = allocs
+            .into_iter()
+            .filter_map(|(addr, serial)| {
+                let nn = *self.young.get(&addr).or_else(|| self.old.get(&addr))?;
+                let same = unsafe { nn.as_ref() }.header.serial() == serial;
+                same.then_some((addr, nn))
+            })
+            .collect();
+
+        // Objects with a deferred finalizer are rooted until it's run
+        if self.config.defer_finalizers {
+            let mut finalizable: Vec<_> = tagged
+                .values()
+                .copied()
+                .filter(|nn| unsafe { nn.as_ref() }.header.needs_finalizing())
+                .collect();
+            finalizable.sort_by_key(|nn| unsafe { nn.as_ref() }.header.serial());
+            for nn in finalizable {
+                self.queue_finalizer(nn);
+            }
+        }
+
+        // Everything from the scope reachable from a root escapes it
+        let mut kept = Vec::new();
+        let mut escaping: Vec<AllocAddr> = tagged
+            .iter()
+            .filter(|(_, nn)| unsafe { nn.as_ref() }.header.is_rooted())
+            .map(|(addr, _)| *addr)
+            .collect();
+        let in_root_sets = self.root_sets.iter().flat_map(|list| list.lock().clone());
+        escaping.extend(in_root_sets.map(|nn| AllocAddr::from(nn.as_ptr())));
+        while let Some(addr) = escaping.pop() {
+            let Some(nn) = tagged.remove(&addr) else {
+                continue;
+            };
+            let gcb = unsafe { nn.as_ref() };
+            kept.push((addr, gcb.header.serial()));
+            let children = unsafe { tracer::record_children(&gcb.val) };
+            escaping.extend(children.iter().map(|child| AllocAddr::from(child.as_ptr())));
+        }
+
+        if cfg!(debug_assertions) && !tagged.is_empty() {
+            let mut escape = None;
+            self.for_each_alloc(|nn| {
+                let addr = AllocAddr::from(nn.as_ptr());
+                if escape.is_some() || tagged.contains_key(&addr) {
+                    return;
+                }
+                let gcb = unsafe { nn.as_ref() };
+                let children = unsafe { tracer::record_children(&gcb.val) };
+                if let Some(child) = children
+                    .iter()
+                    .find(|child| tagged.contains_key(&AllocAddr::from(child.as_ptr())))
+                {
+                    escape = Some(Escape {
+                        type_name: unsafe { child.as_ref() }.header.type_name,
+                        referrer: gcb.header.type_name,
+                    });
+                }
+            });
+            if let Some(escape) = escape {
+                return Err(escape);
+            }
+        }
+
+        let freed: HashSet<AllocAddr> = tagged.keys().copied().collect();
+        let mut unreachable: Vec<_> = tagged.into_values().collect();
+        for nn in &unreachable {
+            let addr = AllocAddr::from(nn.as_ptr());
+            self.young.remove(&addr).or_else(|| self.old.remove(&addr));
+            self.remembered.remove(&addr);
+            self.pinned.remove(&addr);
+            if let Some(alive) = unsafe { nn.as_ref() }.header.weak.get() {
+                alive.store(false, Ordering::Release);
+            }
+        }
+        self.grey
+            .retain(|nn| !freed.contains(&AllocAddr::from(nn.as_ptr())));
+        self.ephemerons.retain(|(key, value)| {
+            !freed.contains(&AllocAddr::from(key.as_ptr()))
+                && !freed.contains(&AllocAddr::from(value.as_ptr()))
+        });
+        if self.config.deterministic {
+            unreachable.sort_by_key(|nn| unsafe { nn.as_ref() }.header.serial());
+        }
+        self.sweeping = Some(Sweep {
+            unreachable,
+            phase: SweepPhase::Finalize,
+            next: 0,
+            panicked: None,
+            collected: false,
+        });
+        self.sweep_step(usize::MAX);
+        Ok(kept)
+    }
+}
@@ -0,0 +1,77 @@
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use serde::{ser::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Gc, GcAble, GcCell};
+
+thread_local! {
+    /// The address of every object being serialized on this thread, outermost first
+    static SERIALIZING: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Removes its object from `SERIALIZING` once dropped, even if serializing it failed
+struct PathGuard;
+
+impl Drop for PathGuard {
+    fn drop(&mut self) {
+        let _ = SERIALIZING.try_with(|path| path.borrow_mut().pop());
+    }
+}
+
+/// Serializes the value, as if it wasn't behind a `Gc<_>`
+///
+/// Serde has no notion of shared references, so an object reachable through several `Gc<_>`s is
+/// written out once for each, and deserialized into as many separate objects. A cycle can't be
+/// written out at all: reaching an object again while it's still being serialized fails with an
+/// error, rather than recursing forever.
+///
+/// ```
+/// use gc::Gc;
+///
+/// let one = Gc::new(1u32);
+/// assert_eq!(serde_json::to_string(&(one.clone(), one)).unwrap(), "[1,1]");
+///
+/// let two: Gc<u32> = serde_json::from_str("2").unwrap();
+/// assert_eq!(*two, 2);
+/// ```
+impl<T: ?Sized + GcAble + Serialize> Serialize for Gc<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let addr = self.as_ptr() as *const () as usize;
+        let cycle = SERIALIZING.with_borrow_mut(|path| {
+            if path.contains(&addr) {
+                return true;
+            }
+            path.push(addr);
+            false
+        });
+        if cycle {
+            return Err(S::Error::custom(format_args!(
+                "a `Gc<{}>` is part of a cycle, which can't be serialized",
+                core::any::type_name::<T>(),
+            )));
+        }
+        let _guard = PathGuard;
+        (**self).serialize(serializer)
+    }
+}
+
+/// Deserializes a value into a new object in the global context, see [`Gc::new`]
+impl<'de, T: GcAble + Deserialize<'de>> Deserialize<'de> for Gc<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Gc::new)
+    }
+}
+
+/// Serializes the contents, see [`GcCell::borrow`]
+impl<T: GcAble + Serialize> Serialize for GcCell<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.borrow().serialize(serializer)
+    }
+}
+
+impl<'de, T: GcAble + Deserialize<'de>> Deserialize<'de> for GcCell<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(GcCell::new)
+    }
+}
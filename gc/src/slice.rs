@@ -0,0 +1,195 @@
+use alloc::{sync::Arc, vec::Vec};
+use core::{
+    alloc::Layout,
+    mem,
+    ptr::{self, addr_of_mut, NonNull},
+    sync::atomic::AtomicBool,
+};
+
+use crate::{
+    context::ContextInner, sys::sync::OnceLock, Gc, GcAble, GcBox, GcBoxHeader, GcContext,
+};
+
+unsafe impl<T: GcAble> GcAble for [T] {
+    unsafe fn mark(&self) {
+        for val in self {
+            unsafe { val.mark() }
+        }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        for val in self {
+            unsafe { val.inc_root_count() }
+        }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        for val in self {
+            unsafe { val.dec_root_count() }
+        }
+    }
+
+    unsafe fn set_not_root(&self) {
+        for val in self {
+            unsafe { val.set_not_root() }
+        }
+    }
+}
+
+/// What the collector sees the value of a `GcBox<[T]>` as
+///
+/// A slice can't be turned into a `dyn GcAble`, so the box is erased as a `GcBox<ErasedSlice<T>>`
+/// instead, whose value is at the same offset. The number of elements is kept in the header.
+#[repr(C)]
+struct ErasedSlice<T: GcAble> {
+    elems: [T; 0],
+}
+
+impl<T: GcAble> ErasedSlice<T> {
+    /// The elements starting at `this`
+    ///
+    /// # Safety
+    /// `this` must point to the value of a live `GcBox<ErasedSlice<T>>`
+    unsafe fn elems(this: *const Self) -> *mut [T] {
+        let offset = mem::offset_of!(GcBox<Self>, val);
+        let gcbox = unsafe { this.byte_sub(offset) } as *const GcBox<Self>;
+        let len = unsafe { (*gcbox).header.slice_len };
+        ptr::slice_from_raw_parts_mut(this as *mut T, len)
+    }
+}
+
+impl<T: GcAble> Drop for ErasedSlice<T> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(Self::elems(self)) }
+    }
+}
+
+unsafe impl<T: GcAble> GcAble for ErasedSlice<T> {
+    unsafe fn mark(&self) {
+        unsafe { (*Self::elems(self)).mark() }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        unsafe { (*Self::elems(self)).inc_root_count() }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        unsafe { (*Self::elems(self)).dec_root_count() }
+    }
+
+    unsafe fn set_not_root(&self) {
+        unsafe { (*Self::elems(self)).set_not_root() }
+    }
+}
+
+fn erase_slice<T: GcAble>(gcbox: NonNull<u8>) -> NonNull<GcBox<dyn GcAble>> {
+    gcbox.cast::<GcBox<ErasedSlice<T>>>()
+}
+
+impl<T: GcAble> Gc<[T]> {
+    /// Copies `vals` into a single allocation in the global context, see [`GcContext::alloc_slice`]
+    pub fn from_slice(vals: &[T]) -> Gc<[T]>
+    where
+        T: Clone,
+    {
+        GcContext::global().alloc_slice(vals)
+    }
+
+    pub(crate) fn from_vec_in(ctx: &Arc<ContextInner>, vals: Vec<T>) -> Gc<[T]> {
+        let len = vals.len();
+        let (layout, offset) = Layout::new::<GcBoxHeader>()
+            .extend(Layout::array::<T>(len).unwrap())
+            .unwrap();
+        let layout = layout.pad_to_align();
+        debug_assert_eq!(offset, mem::offset_of!(GcBox<ErasedSlice<T>>, val));
+
+        let Some(raw) = NonNull::new(unsafe { ctx.alloc(layout) }) else {
+            alloc::alloc::handle_alloc_error(layout)
+        };
+        let gcbox = ptr::slice_from_raw_parts_mut(raw.as_ptr().cast::<T>(), len) as *mut GcBox<[T]>;
+        let gcbox = NonNull::new(gcbox).unwrap();
+
+        // Hold the lock from the moment the elements' children stop being roots until they're
+        // registered, otherwise a collection in between could free them
+        let mut gc = ctx.lock();
+        let mut vals = vals;
+        unsafe {
+            vals[..].set_not_root();
+            addr_of_mut!((*gcbox.as_ptr()).header).write(Gc::<[T]>::header_with(
+                ctx,
+                None,
+                OnceLock::new(),
+                erase_slice::<T>,
+                layout,
+                len,
+            ));
+            // The elements are moved out of `vals`, which only frees its buffer
+            ptr::copy_nonoverlapping(vals.as_ptr(), raw.as_ptr().add(offset).cast::<T>(), len);
+            vals.set_len(0);
+        }
+        let verify = gc.config.verify_roots;
+        Gc::register(&mut gc, ctx, gcbox);
+        drop(gc);
+
+        let this = Gc {
+            is_root: AtomicBool::new(true),
+            gcbox,
+        };
+        if verify {
+            this.verify_not_root();
+        }
+        this
+    }
+}
+
+impl<T: GcAble> From<Vec<T>> for Gc<[T]> {
+    fn from(vals: Vec<T>) -> Self {
+        Gc::from_vec_in(&GcContext::global().inner, vals)
+    }
+}
+
+impl<T: GcAble + Clone> From<&[T]> for Gc<[T]> {
+    fn from(vals: &[T]) -> Self {
+        Gc::from_slice(vals)
+    }
+}
+
+impl<T: GcAble> FromIterator<T> for Gc<[T]> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Gc::from(iter.into_iter().collect::<Vec<T>>())
+    }
+}
+
+impl<T: GcAble> Gc<Vec<T>> {
+    /// Moves `vals` into the global context, keeping it a `Vec`
+    ///
+    /// The elements stop being roots, so `Gc<_>`s among them are kept alive by the vector alone.
+    /// `Gc::<[T]>::from(vals)` stores the elements inline instead, without a second allocation:
+    ///
+    /// ```
+    /// use gc::Gc;
+    ///
+    /// #[derive(gc::GcAble)]
+    /// struct ExampleNum(u64);
+    ///
+    /// let nums: Vec<Gc<ExampleNum>> = (0..3).map(|n| Gc::new(ExampleNum(n))).collect();
+    /// let weak = Gc::downgrade(&nums[1]);
+    /// let arr: Gc<Vec<_>> = nums.into_iter().collect();
+    /// assert_eq!(Gc::strong_count(&arr[1]), 0);
+    ///
+    /// gc::force_collect();
+    /// assert_eq!(weak.upgrade().unwrap().0, 1);
+    /// drop(arr);
+    /// gc::force_collect();
+    /// assert!(weak.upgrade().is_none());
+    /// ```
+    pub fn from_vec(vals: Vec<T>) -> Gc<Vec<T>> {
+        Gc::new(vals)
+    }
+}
+
+impl<T: GcAble> FromIterator<T> for Gc<Vec<T>> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Gc::from_vec(iter.into_iter().collect())
+    }
+}
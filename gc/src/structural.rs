@@ -0,0 +1,179 @@
+use core::hash::Hasher;
+
+use crate::{sys::collections::HashMap, AllocAddr, Gc, GcAble};
+
+/// A value which can be compared along with every `Gc<_>` it references, see
+/// [`Gc::structural_eq`]
+///
+/// Values without any `Gc<_>`s can implement this with `==`
+pub trait StructuralEq: GcAble {
+    /// Compares this to `other`, using [`StructuralCmp::eq_gc`] on every pair of `Gc<_>`s directly
+    /// contained in them
+    fn structural_eq(&self, other: &Self, cmp: &mut StructuralCmp) -> bool;
+}
+
+/// A value which can be hashed along with every `Gc<_>` it references, see
+/// [`Gc::structural_hash`]
+///
+/// Values without any `Gc<_>`s can implement this with [`core::hash::Hash`]
+pub trait StructuralHash: GcAble {
+    /// Hashes this into `hasher`, using [`StructuralHasher::hash_gc`] on every `Gc<_>` directly
+    /// contained in it, in the same order [`StructuralEq::structural_eq`] compares them
+    fn structural_hash(&self, hasher: &mut StructuralHasher<'_>);
+}
+
+/// Compares two graphs while remembering which objects were already paired up, so a cycle is
+/// only followed once
+#[derive(Default)]
+pub struct StructuralCmp {
+    /// The object each object on the left was paired with on the right
+    left: HashMap<AllocAddr, AllocAddr>,
+    /// The object each object on the right was paired with on the left
+    right: HashMap<AllocAddr, AllocAddr>,
+}
+
+impl StructuralCmp {
+    /// Compares the values of `a` and `b`, if neither has been reached before
+    ///
+    /// Objects which were reached before are only equal if they were paired with each other, so
+    /// two graphs are only equal if they have the same shape, and not just when following both
+    /// gives equal values forever.
+    pub fn eq_gc<T: ?Sized + StructuralEq>(&mut self, a: &Gc<T>, b: &Gc<T>) -> bool {
+        let a_addr = AllocAddr::from(a.gcbox.as_ptr());
+        let b_addr = AllocAddr::from(b.gcbox.as_ptr());
+        match (self.left.get(&a_addr), self.right.get(&b_addr)) {
+            (None, None) => {
+                // Paired before comparing, since the values may lead back to them
+                self.left.insert(a_addr, b_addr);
+                self.right.insert(b_addr, a_addr);
+                T::structural_eq(a, b, self)
+            }
+            (Some(paired_b), Some(paired_a)) => *paired_b == b_addr && *paired_a == a_addr,
+            _ => false,
+        }
+    }
+}
+
+/// Hashes a graph while numbering the objects in the order they're reached, so an object reached
+/// again is hashed as its number instead of following a cycle forever
+///
+/// Hashes anything hashed into it into the `Hasher` passed to [`Gc::structural_hash`]
+pub struct StructuralHasher<'a> {
+    state: &'a mut dyn Hasher,
+    /// The number of every object reached so far
+    reached: HashMap<AllocAddr, usize>,
+}
+
+impl StructuralHasher<'_> {
+    /// Hashes the value of `gc`, or its number if it was reached before
+    pub fn hash_gc<T: ?Sized + StructuralHash>(&mut self, gc: &Gc<T>) {
+        let addr = AllocAddr::from(gc.gcbox.as_ptr());
+        if let Some(&n) = self.reached.get(&addr) {
+            self.write_u8(1);
+            self.write_usize(n);
+            return;
+        }
+        self.reached.insert(addr, self.reached.len());
+        self.write_u8(0);
+        T::structural_hash(gc, self)
+    }
+}
+
+impl Hasher for StructuralHasher<'_> {
+    fn finish(&self) -> u64 {
+        self.state.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.state.write(bytes)
+    }
+}
+
+impl<T: ?Sized + StructuralEq> Gc<T> {
+    /// Compares the values of two graphs, following every `Gc<_>` in them, including through
+    /// cycles
+    ///
+    /// `==` on a `Gc<T>` compares values by following every `Gc<_>` recursively too, so it never
+    /// finishes on a cyclic graph. This pairs up the objects of both graphs as they're reached
+    /// instead, and the graphs are equal if the paired objects have equal values and reference
+    /// each other the same way.
+    ///
+    /// Deeply nested graphs, such as long linked lists, are still compared recursively.
+    ///
+    /// ```
+    /// use std::hash::{BuildHasher, RandomState};
+    /// use gc::{Gc, GcCell, StructuralCmp, StructuralEq, StructuralHash, StructuralHasher};
+    ///
+    /// #[derive(gc::GcAble)]
+    /// struct Node {
+    ///     id: u32,
+    ///     next: GcCell<Option<Gc<Node>>>,
+    /// }
+    ///
+    /// impl StructuralEq for Node {
+    ///     fn structural_eq(&self, other: &Self, cmp: &mut StructuralCmp) -> bool {
+    ///         self.id == other.id
+    ///             && match (self.next.get(), other.next.get()) {
+    ///                 (Some(a), Some(b)) => cmp.eq_gc(&a, &b),
+    ///                 (a, b) => a.is_none() && b.is_none(),
+    ///             }
+    ///     }
+    /// }
+    ///
+    /// impl StructuralHash for Node {
+    ///     fn structural_hash(&self, hasher: &mut StructuralHasher<'_>) {
+    ///         use std::hash::Hash;
+    ///         self.id.hash(hasher);
+    ///         self.next.get().is_some().hash(hasher);
+    ///         if let Some(next) = self.next.get() {
+    ///             hasher.hash_gc(&next);
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// /// A ring of nodes numbered by `ids`
+    /// fn ring(ids: &[u32]) -> Gc<Node> {
+    ///     let first = Gc::new(Node { id: ids[0], next: GcCell::new(None) });
+    ///     let mut last = first.clone();
+    ///     for &id in &ids[1..] {
+    ///         let node = Gc::new(Node { id, next: GcCell::new(None) });
+    ///         last.next.set(Some(node.clone()));
+    ///         last = node;
+    ///     }
+    ///     last.next.set(Some(first.clone()));
+    ///     first
+    /// }
+    ///
+    /// let (a, b) = (ring(&[1, 2, 3]), ring(&[1, 2, 3]));
+    /// assert!(Gc::structural_eq(&a, &b));
+    /// assert!(!Gc::structural_eq(&a, &ring(&[1, 2, 4])));
+    /// // Following either gives 1, 2, 1, 2, .. forever, but they aren't the same shape
+    /// assert!(!Gc::structural_eq(&ring(&[1, 2]), &ring(&[1, 2, 1, 2])));
+    ///
+    /// let state = RandomState::new();
+    /// let hash = |gc: &Gc<Node>| {
+    ///     let mut hasher = state.build_hasher();
+    ///     Gc::structural_hash(gc, &mut hasher);
+    ///     std::hash::Hasher::finish(&hasher)
+    /// };
+    /// assert_eq!(hash(&a), hash(&b));
+    /// ```
+    pub fn structural_eq(this: &Self, other: &Self) -> bool {
+        StructuralCmp::default().eq_gc(this, other)
+    }
+}
+
+impl<T: ?Sized + StructuralHash> Gc<T> {
+    /// Hashes the value, following every `Gc<_>` in it, including through cycles, consistently
+    /// with [`Gc::structural_eq`]
+    ///
+    /// Objects are hashed the first time they're reached, and as the order they were first reached
+    /// in every time after that.
+    pub fn structural_hash<H: Hasher>(this: &Self, state: &mut H) {
+        let mut hasher = StructuralHasher {
+            state,
+            reached: HashMap::new(),
+        };
+        hasher.hash_gc(this)
+    }
+}
@@ -0,0 +1,383 @@
+//! What the Gc needs from the platform, under the same paths as in `std`
+//!
+//! With the `std` feature these are `std`'s own. Without it, locks are spin locks, thread locals
+//! are plain statics and there is no clock, so every `GcContext` must only be used by one thread at
+//! a time, and collection only happens when asked for, see [`crate::Collector`]. Panics can't be
+//! caught either, so a panicking finalizer or `Drop` is expected to abort.
+
+/// Declares thread locals like `std::thread_local!`, see [`thread::LocalKey`]
+#[cfg(feature = "std")]
+macro_rules! thread_local {
+    ($($decls:tt)*) => {
+        std::thread_local!($($decls)*);
+    };
+}
+
+/// Declares thread locals like `std::thread_local!`, see [`thread::LocalKey`]
+#[cfg(not(feature = "std"))]
+macro_rules! thread_local {
+    () => {};
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = const { $init:expr } $(; $($rest:tt)*)?) => {
+        thread_local!($(#[$attr])* $vis static $name: $t = $init $(; $($rest)*)?);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr $(; $($rest:tt)*)?) => {
+        $(#[$attr])*
+        $vis static $name: $crate::sys::thread::LocalKey<$t> =
+            $crate::sys::thread::LocalKey::new(|| $init);
+        $(thread_local!($($rest)*);)?
+    };
+}
+
+pub(crate) mod collections {
+    #[cfg(feature = "std")]
+    pub(crate) use std::collections::{HashMap, HashSet};
+
+    // Only ever keyed by types which are also `Ord`
+    #[cfg(not(feature = "std"))]
+    pub(crate) use alloc::collections::{BTreeMap as HashMap, BTreeSet as HashSet};
+
+    /// `HashMap::with_capacity`, or an empty map without `std`, since trees can't be presized
+    pub(crate) fn map_with_capacity<K, V>(capacity: usize) -> HashMap<K, V> {
+        #[cfg(feature = "std")]
+        return HashMap::with_capacity(capacity);
+        #[cfg(not(feature = "std"))]
+        {
+            let _ = capacity;
+            HashMap::new()
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+pub(crate) use std::{panic, sync, thread, time};
+
+#[cfg(not(feature = "std"))]
+pub(crate) mod panic {
+    use alloc::boxed::Box;
+    use core::any::Any;
+
+    pub(crate) struct AssertUnwindSafe<T>(pub T);
+
+    /// Runs `f`, a panic can't be caught without `std`
+    pub(crate) fn catch_unwind<R>(
+        f: AssertUnwindSafe<impl FnOnce() -> R>,
+    ) -> Result<R, Box<dyn Any + Send>> {
+        Ok((f.0)())
+    }
+
+    pub(crate) fn resume_unwind(_: Box<dyn Any + Send>) -> ! {
+        unreachable!("no panic is ever caught without `std`")
+    }
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) mod sync {
+    use core::{
+        cell::UnsafeCell,
+        fmt::{self, Debug, Display},
+        hint,
+        mem::MaybeUninit,
+        ops::{Deref, DerefMut},
+        sync::atomic::{AtomicU8, AtomicUsize, Ordering},
+    };
+
+    /// Never returned, since a spin lock isn't poisoned by a panic
+    pub(crate) struct PoisonError<G>(G);
+
+    impl<G> PoisonError<G> {
+        pub fn into_inner(self) -> G {
+            self.0
+        }
+    }
+
+    impl<G> Debug for PoisonError<G> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("PoisonError").finish_non_exhaustive()
+        }
+    }
+
+    impl<G> Display for PoisonError<G> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "poisoned lock")
+        }
+    }
+
+    pub(crate) type LockResult<G> = Result<G, PoisonError<G>>;
+
+    pub(crate) enum TryLockError<G> {
+        #[allow(dead_code)]
+        Poisoned(PoisonError<G>),
+        WouldBlock,
+    }
+
+    pub(crate) type TryLockResult<G> = Result<G, TryLockError<G>>;
+
+    /// A readers-writer spin lock, which is a mutex when only `write` is used
+    pub(crate) struct RwLock<T: ?Sized> {
+        /// `usize::MAX` while written to, and the number of readers otherwise
+        state: AtomicUsize,
+        value: UnsafeCell<T>,
+    }
+
+    unsafe impl<T: ?Sized + Send> Send for RwLock<T> {}
+    unsafe impl<T: ?Sized + Send + Sync> Sync for RwLock<T> {}
+
+    const WRITING: usize = usize::MAX;
+
+    impl<T> RwLock<T> {
+        pub const fn new(value: T) -> Self {
+            Self {
+                state: AtomicUsize::new(0),
+                value: UnsafeCell::new(value),
+            }
+        }
+    }
+
+    impl<T: ?Sized> RwLock<T> {
+        pub fn read(&self) -> LockResult<RwLockReadGuard<'_, T>> {
+            loop {
+                match self.try_read() {
+                    Err(TryLockError::WouldBlock) => hint::spin_loop(),
+                    res => return res.map_err(|_| unreachable!()),
+                }
+            }
+        }
+
+        pub fn try_read(&self) -> TryLockResult<RwLockReadGuard<'_, T>> {
+            self.state
+                .try_update(Ordering::Acquire, Ordering::Relaxed, |readers| {
+                    (readers < WRITING - 1).then_some(readers + 1)
+                })
+                .map(|_| RwLockReadGuard { lock: self })
+                .map_err(|_| TryLockError::WouldBlock)
+        }
+
+        pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, T>> {
+            loop {
+                match self.try_write() {
+                    Err(TryLockError::WouldBlock) => hint::spin_loop(),
+                    res => return res.map_err(|_| unreachable!()),
+                }
+            }
+        }
+
+        pub fn try_write(&self) -> TryLockResult<RwLockWriteGuard<'_, T>> {
+            self.state
+                .compare_exchange(0, WRITING, Ordering::Acquire, Ordering::Relaxed)
+                .map(|_| RwLockWriteGuard { lock: self })
+                .map_err(|_| TryLockError::WouldBlock)
+        }
+    }
+
+    pub(crate) struct RwLockReadGuard<'a, T: ?Sized> {
+        lock: &'a RwLock<T>,
+    }
+
+    impl<T: ?Sized> Deref for RwLockReadGuard<'_, T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            unsafe { &*self.lock.value.get() }
+        }
+    }
+
+    impl<T: ?Sized> Drop for RwLockReadGuard<'_, T> {
+        fn drop(&mut self) {
+            self.lock.state.fetch_sub(1, Ordering::Release);
+        }
+    }
+
+    pub(crate) struct RwLockWriteGuard<'a, T: ?Sized> {
+        lock: &'a RwLock<T>,
+    }
+
+    impl<T: ?Sized> Deref for RwLockWriteGuard<'_, T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            unsafe { &*self.lock.value.get() }
+        }
+    }
+
+    impl<T: ?Sized> DerefMut for RwLockWriteGuard<'_, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            unsafe { &mut *self.lock.value.get() }
+        }
+    }
+
+    impl<T: ?Sized> Drop for RwLockWriteGuard<'_, T> {
+        fn drop(&mut self) {
+            self.lock.state.store(0, Ordering::Release);
+        }
+    }
+
+    pub(crate) struct Mutex<T: ?Sized>(RwLock<T>);
+
+    unsafe impl<T: ?Sized + Send> Sync for Mutex<T> {}
+
+    pub(crate) type MutexGuard<'a, T> = RwLockWriteGuard<'a, T>;
+
+    impl<T> Mutex<T> {
+        pub const fn new(value: T) -> Self {
+            Self(RwLock::new(value))
+        }
+    }
+
+    impl<T: ?Sized> Mutex<T> {
+        pub fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+            self.0.write()
+        }
+    }
+
+    impl<T: Default> Default for Mutex<T> {
+        fn default() -> Self {
+            Self::new(T::default())
+        }
+    }
+
+    const UNINIT: u8 = 0;
+    const INITIALIZING: u8 = 1;
+    const INIT: u8 = 2;
+
+    /// Initialized at most once, by whichever caller gets there first
+    pub(crate) struct OnceLock<T> {
+        state: AtomicU8,
+        value: UnsafeCell<MaybeUninit<T>>,
+    }
+
+    unsafe impl<T: Send> Send for OnceLock<T> {}
+    unsafe impl<T: Send + Sync> Sync for OnceLock<T> {}
+
+    impl<T> OnceLock<T> {
+        pub const fn new() -> Self {
+            Self {
+                state: AtomicU8::new(UNINIT),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            }
+        }
+
+        pub fn get(&self) -> Option<&T> {
+            let init = self.state.load(Ordering::Acquire) == INIT;
+            init.then(|| unsafe { (*self.value.get()).assume_init_ref() })
+        }
+
+        pub fn get_or_init(&self, f: impl FnOnce() -> T) -> &T {
+            let claimed = self.state.compare_exchange(
+                UNINIT,
+                INITIALIZING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            );
+            if claimed.is_ok() {
+                unsafe { (*self.value.get()).write(f()) };
+                self.state.store(INIT, Ordering::Release);
+            }
+            loop {
+                match self.get() {
+                    Some(value) => return value,
+                    None => hint::spin_loop(),
+                }
+            }
+        }
+    }
+
+    impl<T> From<T> for OnceLock<T> {
+        fn from(value: T) -> Self {
+            Self {
+                state: AtomicU8::new(INIT),
+                value: UnsafeCell::new(MaybeUninit::new(value)),
+            }
+        }
+    }
+
+    impl<T> Drop for OnceLock<T> {
+        fn drop(&mut self) {
+            if *self.state.get_mut() == INIT {
+                unsafe { self.value.get_mut().assume_init_drop() }
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) mod thread {
+    use core::{cell::RefCell, hint};
+
+    use super::sync::OnceLock;
+
+    /// A "thread local" which is shared by the whole program, since there's only one thread
+    pub(crate) struct LocalKey<T: 'static> {
+        init: fn() -> T,
+        value: OnceLock<T>,
+    }
+
+    // Safety: without `std` the Gc is only used by one thread at a time
+    unsafe impl<T> Sync for LocalKey<T> {}
+
+    /// Never returned, since the statics standing in for thread locals are never destroyed
+    #[derive(Debug)]
+    pub(crate) struct AccessError;
+
+    impl<T: 'static> LocalKey<T> {
+        pub const fn new(init: fn() -> T) -> Self {
+            Self {
+                init,
+                value: OnceLock::new(),
+            }
+        }
+
+        pub fn with<R>(&'static self, f: impl FnOnce(&T) -> R) -> R {
+            f(self.value.get_or_init(self.init))
+        }
+
+        pub fn try_with<R>(&'static self, f: impl FnOnce(&T) -> R) -> Result<R, AccessError> {
+            Ok(self.with(f))
+        }
+    }
+
+    impl<T: 'static> LocalKey<RefCell<T>> {
+        pub fn with_borrow<R>(&'static self, f: impl FnOnce(&T) -> R) -> R {
+            self.with(|cell| f(&cell.borrow()))
+        }
+
+        pub fn with_borrow_mut<R>(&'static self, f: impl FnOnce(&mut T) -> R) -> R {
+            self.with(|cell| f(&mut cell.borrow_mut()))
+        }
+    }
+
+    /// Always `false`, since a panic can't be caught without `std`
+    pub(crate) fn panicking() -> bool {
+        false
+    }
+
+    pub(crate) fn yield_now() {
+        hint::spin_loop();
+    }
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) mod time {
+    use core::{ops::Sub, time::Duration};
+
+    /// There's no clock without `std`, so this can't be created and no time is ever measured
+    #[derive(Debug, Clone, Copy)]
+    pub(crate) enum Instant {}
+
+    impl Instant {
+        pub fn now() -> Self {
+            unreachable!("there's no clock without `std`")
+        }
+
+        pub fn elapsed(&self) -> Duration {
+            match *self {}
+        }
+    }
+
+    impl Sub for Instant {
+        type Output = Duration;
+
+        fn sub(self, _: Self) -> Duration {
+            match self {}
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use alloc::{collections::BTreeSet, vec::Vec};
+use core::{cell::RefCell, ptr::NonNull};
+
+use crate::{
+    context::ContextId,
+    sys::sync::{Mutex, MutexGuard, PoisonError},
+    AllocAddr, GcAble, GcBox,
+};
+
+/// What `Gc::mark` does on the current thread
+enum Tracer {
+    /// Not inside any tracing done by the Gc, so `Gc::mark` does nothing
+    Idle,
+    /// Mark every unmarked object visited and push it onto `grey`, which is what the collector of
+    /// the context does
+    ///
+    /// Old objects aren't followed during minor collections
+    Mark {
+        ctx: ContextId,
+        minor: bool,
+        grey: Vec<NonNull<GcBox<dyn GcAble>>>,
+        /// The key and value of every `Ephemeron` visited whose key wasn't marked yet
+        ephemerons: Vec<PendingEphemeron>,
+    },
+    /// Record every `Gc<_>` visited without following it
+    Record(Vec<NonNull<GcBox<dyn GcAble>>>),
+    /// Make every `Gc<_>` visited a root again without following it
+    Root,
+    /// Record every `Gc<_>` visited which still counts as a root, without following it
+    Verify(Vec<NonNull<GcBox<dyn GcAble>>>),
+}
+
+/// The number of locks the registered addresses are spread across
+const SHARDS: usize = 64;
+
+/// The address of every registered box in every context, only kept in debug builds
+///
+/// Every use of a `Gc<_>` checks this, so it's split by address to keep threads using unrelated
+/// objects from waiting on each other
+static REGISTERED: [Mutex<BTreeSet<AllocAddr>>; SHARDS] =
+    [const { Mutex::new(BTreeSet::new()) }; SHARDS];
+
+/// The part of `REGISTERED` holding `addr`
+fn shard(addr: AllocAddr) -> MutexGuard<'static, BTreeSet<AllocAddr>> {
+    // Boxes are aligned, so the lowest bits of their addresses are all the same
+    REGISTERED[(addr.0.get() >> 4) % SHARDS]
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Records that the box at `addr` was registered
+pub(crate) fn registered(addr: AllocAddr) {
+    if cfg!(debug_assertions) {
+        shard(addr).insert(addr);
+    }
+}
+
+/// Records that the box at `addr` is about to be freed
+pub(crate) fn freed(addr: AllocAddr) {
+    if cfg!(debug_assertions) {
+        shard(addr).remove(&addr);
+    }
+}
+
+/// Whether the box at `addr` is registered, which is always assumed in release builds
+pub(crate) fn is_registered(addr: AllocAddr) -> bool {
+    !cfg!(debug_assertions) || shard(addr).contains(&addr)
+}
+
+/// Called by `Gc::mark` before it touches the object it points to, panics if the collector is
+/// following it but it isn't registered, which means it has already been freed
+///
+/// Only checked in debug builds
+pub(crate) fn check_registered(addr: AllocAddr) {
+    if !cfg!(debug_assertions) || !TRACER.with_borrow(|t| matches!(t, Tracer::Mark { .. })) {
+        return;
+    }
+    assert!(
+        is_registered(addr),
+        "the collector reached a `Gc<_>` pointing to {addr}, which isn't a live object"
+    );
+}
+
+thread_local! {
+    static TRACER: RefCell<Tracer> = const { RefCell::new(Tracer::Idle) };
+}
+
+/// Sets the current thread's tracer, and restores the previous one once dropped, even if unwinding
+struct TracerGuard {
+    prev: Option<Tracer>,
+}
+
+impl TracerGuard {
+    fn set(tracer: Tracer) -> Self {
+        let prev = TRACER.with_borrow_mut(|t| core::mem::replace(t, tracer));
+        Self { prev: Some(prev) }
+    }
+
+    /// Restores the previous tracer, returning the one that was set
+    fn finish(mut self) -> Tracer {
+        let prev = self.prev.take().unwrap();
+        TRACER.with_borrow_mut(|t| core::mem::replace(t, prev))
+    }
+}
+
+impl Drop for TracerGuard {
+    fn drop(&mut self) {
+        if let Some(prev) = self.prev.take() {
+            TRACER.with_borrow_mut(|t| *t = prev);
+        }
+    }
+}
+
+/// The key and value of an `Ephemeron` whose key wasn't marked when it was visited
+pub(crate) type PendingEphemeron = (NonNull<GcBox<dyn GcAble>>, NonNull<GcBox<dyn GcAble>>);
+
+/// Scans up to `budget` objects from `grey`, which are marked objects whose children may not be,
+/// for objects in the context `ctx`
+///
+/// Every newly marked object is pushed onto `grey`. Whenever `grey` runs out, the value of every
+/// ephemeron in `ephemerons` whose key has been marked since is marked too, until that doesn't
+/// mark anything new. Returns `true` once `grey` is empty, which leaves the ephemerons whose keys
+/// are unreachable in `ephemerons`.
+///
+/// # Safety
+/// Every object in `grey` and `ephemerons` must be live, and the context's lock must be held
+pub(crate) unsafe fn mark_in(
+    ctx: ContextId,
+    minor: bool,
+    grey: &mut Vec<NonNull<GcBox<dyn GcAble>>>,
+    ephemerons: &mut Vec<PendingEphemeron>,
+    budget: usize,
+) -> bool {
+    let guard = TracerGuard::set(Tracer::Mark {
+        ctx,
+        minor,
+        grey: core::mem::take(grey),
+        ephemerons: core::mem::take(ephemerons),
+    });
+    for _ in 0..budget {
+        let next = TRACER.with_borrow_mut(|t| match t {
+            Tracer::Mark {
+                minor,
+                grey,
+                ephemerons,
+                ..
+            } => {
+                if grey.is_empty() {
+                    resolve_ephemerons(*minor, grey, ephemerons);
+                }
+                grey.pop()
+            }
+            _ => unreachable!(),
+        });
+        let Some(next) = next else {
+            break;
+        };
+        unsafe { next.as_ref().val.mark() };
+    }
+    let Tracer::Mark {
+        grey: rest,
+        ephemerons: pending,
+        ..
+    } = guard.finish()
+    else {
+        unreachable!()
+    };
+    *grey = rest;
+    *ephemerons = pending;
+    // The budget may have run out right as `grey` did
+    if grey.is_empty() {
+        resolve_ephemerons(minor, grey, ephemerons);
+    }
+    grey.is_empty()
+}
+
+/// Marks the value of every ephemeron whose key is marked, and forgets about those ephemerons
+fn resolve_ephemerons(
+    minor: bool,
+    grey: &mut Vec<NonNull<GcBox<dyn GcAble>>>,
+    ephemerons: &mut Vec<PendingEphemeron>,
+) {
+    ephemerons.retain(|&(key, value)| {
+        if !unsafe { key_alive(minor, key) } {
+            return true;
+        }
+        unsafe { shade(minor, grey, value) };
+        false
+    });
+}
+
+/// Whether the marking so far has found `key` reachable, which is assumed of old objects during
+/// minor collections
+///
+/// # Safety
+/// `key` must be live
+unsafe fn key_alive(minor: bool, key: NonNull<GcBox<dyn GcAble>>) -> bool {
+    let header = unsafe { &key.as_ref().header };
+    (minor && header.is_old()) || header.marked()
+}
+
+/// Marks `gcbox` and pushes it onto `grey` if it's being collected and isn't marked yet
+///
+/// # Safety
+/// `gcbox` must be live
+unsafe fn shade(
+    minor: bool,
+    grey: &mut Vec<NonNull<GcBox<dyn GcAble>>>,
+    gcbox: NonNull<GcBox<dyn GcAble>>,
+) {
+    let header = unsafe { &gcbox.as_ref().header };
+    if !(minor && header.is_old()) && !header.marked() {
+        header.mark();
+        grey.push(gcbox);
+    }
+}
+
+/// Returns every `Gc<_>` directly contained in `val`
+///
+/// # Safety
+/// Same as [`GcAble::mark`]
+pub(crate) unsafe fn record_children(val: &dyn GcAble) -> Vec<NonNull<GcBox<dyn GcAble>>> {
+    let guard = TracerGuard::set(Tracer::Record(Vec::new()));
+    unsafe { val.mark() };
+    let Tracer::Record(children) = guard.finish() else {
+        unreachable!()
+    };
+    children
+}
+
+/// Makes every `Gc<_>` directly contained in `val` a root again, undoing [`GcAble::set_not_root`]
+///
+/// # Safety
+/// `val` must have just been moved out of the object it was in
+pub(crate) unsafe fn root_children(val: &dyn GcAble) {
+    let guard = TracerGuard::set(Tracer::Root);
+    unsafe { val.mark() };
+    guard.finish();
+}
+
+/// Returns every `Gc<_>` directly contained in `val` which still counts as a root, which should be
+/// none of them once `val` has been moved into a `Gc<_>`
+///
+/// # Safety
+/// Same as [`GcAble::mark`]
+pub(crate) unsafe fn rooted_children(val: &dyn GcAble) -> Vec<NonNull<GcBox<dyn GcAble>>> {
+    let guard = TracerGuard::set(Tracer::Verify(Vec::new()));
+    unsafe { val.mark() };
+    let Tracer::Verify(rooted) = guard.finish() else {
+        unreachable!()
+    };
+    rooted
+}
+
+/// Whether [`root_children`] is running on this thread
+pub(crate) fn rooting() -> bool {
+    TRACER.with_borrow(|t| matches!(t, Tracer::Root))
+}
+
+/// Called by `Gc::mark` with the object it points to, returns `true` if the `Gc<_>` should become
+/// a root
+///
+/// `is_root` is only called if the `Gc<_>` being a root matters to the current tracer
+///
+/// Panics if the object belongs to a different context than the one being collected, since that
+/// context may free it at any point
+///
+/// # Safety
+/// `gcbox` must be live
+pub(crate) unsafe fn visit(
+    gcbox: NonNull<GcBox<dyn GcAble>>,
+    is_root: impl FnOnce() -> bool,
+) -> bool {
+    let header = unsafe { &gcbox.as_ref().header };
+    TRACER.with_borrow_mut(|t| match t {
+        Tracer::Idle => false,
+        Tracer::Mark {
+            ctx, minor, grey, ..
+        } => {
+            assert_eq!(
+                *ctx, header.context.id,
+                "a `Gc<_>` from one `GcContext` was stored in an object of another"
+            );
+            unsafe { shade(*minor, grey, gcbox) };
+            false
+        }
+        Tracer::Record(children) => {
+            children.push(gcbox);
+            false
+        }
+        Tracer::Root => true,
+        Tracer::Verify(rooted) => {
+            if is_root() {
+                rooted.push(gcbox);
+            }
+            false
+        }
+    })
+}
+
+/// Called by `Ephemeron::mark` with its key and value, where the value is only followed like a
+/// `Gc<_>` once the key is marked
+///
+/// Listing children counts the value as one, so that an old object holding an ephemeron with a
+/// young value stays remembered, while the other tracers ignore ephemerons since they hold no
+/// roots
+///
+/// # Safety
+/// `key` and `value` must be live
+pub(crate) unsafe fn visit_ephemeron(
+    key: NonNull<GcBox<dyn GcAble>>,
+    value: NonNull<GcBox<dyn GcAble>>,
+) {
+    TRACER.with_borrow_mut(|t| match t {
+        Tracer::Mark {
+            ctx,
+            minor,
+            grey,
+            ephemerons,
+        } => {
+            assert_eq!(
+                *ctx,
+                unsafe { &value.as_ref().header }.context.id,
+                "an `Ephemeron` from one `GcContext` was stored in an object of another"
+            );
+            match unsafe { key_alive(*minor, key) } {
+                true => unsafe { shade(*minor, grey, value) },
+                false => ephemerons.push((key, value)),
+            }
+        }
+        Tracer::Record(children) => children.push(value),
+        Tracer::Idle | Tracer::Root | Tracer::Verify(_) => {}
+    })
+}
@@ -0,0 +1,133 @@
+//! Counts the calls made to the `GcAble` methods of `Gc<_>`, with the `debug-tracing` feature
+
+#[cfg(feature = "debug-tracing")]
+use core::{cell::Cell, ops::Sub};
+
+#[cfg(feature = "debug-tracing")]
+use crate::{tracer, GcAble};
+
+/// Which method of `Gc<_>` was called, see [`record`]
+pub(crate) enum Call {
+    Mark,
+    IncRootCount,
+    DecRootCount,
+    SetNotRoot,
+}
+
+/// The calls made to the `GcAble` methods of every `Gc<_>` on one thread, see [`tracing_stats`]
+#[cfg(feature = "debug-tracing")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TracingStats {
+    pub mark: u64,
+    pub inc_root_count: u64,
+    pub dec_root_count: u64,
+    pub set_not_root: u64,
+    /// The sum of every change made to a root count, including by [`crate::Gc::set_not_root`] and
+    /// by a `Gc<_>` becoming a root again when its value is moved out of its `Gc<_>`
+    pub root_count_delta: i64,
+}
+
+#[cfg(feature = "debug-tracing")]
+impl Sub for TracingStats {
+    type Output = TracingStats;
+
+    /// The calls made between two snapshots
+    fn sub(self, earlier: Self) -> Self {
+        Self {
+            mark: self.mark - earlier.mark,
+            inc_root_count: self.inc_root_count - earlier.inc_root_count,
+            dec_root_count: self.dec_root_count - earlier.dec_root_count,
+            set_not_root: self.set_not_root - earlier.set_not_root,
+            root_count_delta: self.root_count_delta - earlier.root_count_delta,
+        }
+    }
+}
+
+#[cfg(feature = "debug-tracing")]
+thread_local! {
+    static STATS: Cell<TracingStats> = Cell::new(TracingStats::default());
+}
+
+/// Counts a call to a `Gc<_>`'s method
+pub(crate) fn record(call: Call) {
+    #[cfg(feature = "debug-tracing")]
+    update(|stats| match call {
+        Call::Mark => stats.mark += 1,
+        Call::IncRootCount => stats.inc_root_count += 1,
+        Call::DecRootCount => stats.dec_root_count += 1,
+        Call::SetNotRoot => stats.set_not_root += 1,
+    });
+    #[cfg(not(feature = "debug-tracing"))]
+    let _ = call;
+}
+
+/// Counts a change of `delta` to an object's root count
+pub(crate) fn record_root_count(delta: i64) {
+    #[cfg(feature = "debug-tracing")]
+    update(|stats| stats.root_count_delta += delta);
+    #[cfg(not(feature = "debug-tracing"))]
+    let _ = delta;
+}
+
+#[cfg(feature = "debug-tracing")]
+fn update(f: impl FnOnce(&mut TracingStats)) {
+    let _ = STATS.try_with(|stats| {
+        let mut new = stats.get();
+        f(&mut new);
+        stats.set(new);
+    });
+}
+
+/// The calls made on the current thread so far to the `GcAble` methods of every `Gc<_>`
+///
+/// Each thread counts its own calls, so those made by the collector's thread while it marks don't
+/// show up on the threads using the Gc. Subtracting two snapshots gives the calls made between
+/// them, which for a value's `GcAble` method is the number of `Gc<_>`s it visited.
+#[cfg(feature = "debug-tracing")]
+pub fn tracing_stats() -> TracingStats {
+    STATS.try_with(Cell::get).unwrap_or_default()
+}
+
+/// Panics if `val`'s [`GcAble`] impl doesn't visit the same `Gc<_>`s from each of its methods
+///
+/// The value is rooted with [`GcAble::inc_root_count`], moved out of the Gc and back in with
+/// [`GcAble::set_not_root`] and [`GcAble::mark`], then unrooted with [`GcAble::dec_root_count`].
+/// Every method has to visit the same number of `Gc<_>`s, and the root counts have to end up where
+/// they started, or the impl leaks or frees objects which are still in use. This catches a field
+/// which is missing from some of the methods, but not one which is missing from all of them.
+///
+/// `val` must not be inside a `Gc<_>`, so that the `Gc<_>`s in it are roots.
+#[cfg(feature = "debug-tracing")]
+#[track_caller]
+pub fn assert_root_counts_balance<T: GcAble>(val: &T) {
+    let start = tracing_stats();
+    unsafe { val.inc_root_count() };
+    let rooted = tracing_stats();
+    unsafe {
+        val.set_not_root();
+        tracer::root_children(val);
+    }
+    let moved = tracing_stats();
+    unsafe { val.dec_root_count() };
+    let end = tracing_stats();
+
+    let (inc, moved, dec) = (rooted - start, moved - rooted, end - moved);
+    let type_name = core::any::type_name::<T>();
+    assert!(
+        inc.inc_root_count == dec.dec_root_count
+            && inc.inc_root_count == moved.set_not_root
+            && inc.inc_root_count == moved.mark,
+        "the `GcAble` impl of `{type_name}` visits different `Gc<_>`s from different methods: \
+         `inc_root_count` visited {}, `dec_root_count` {}, `set_not_root` {} and `mark` {}",
+        inc.inc_root_count,
+        dec.dec_root_count,
+        moved.set_not_root,
+        moved.mark,
+    );
+    let delta = (end - start).root_count_delta;
+    assert!(
+        delta == 0,
+        "the `GcAble` impl of `{type_name}` changed root counts by {delta} in total, instead of \
+         leaving them as they were",
+    );
+}
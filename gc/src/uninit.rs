@@ -0,0 +1,160 @@
+use alloc::sync::Arc;
+use core::{
+    alloc::Layout,
+    mem::{self, MaybeUninit},
+    ptr::{self, NonNull},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{context::ContextInner, sys::sync::OnceLock, Gc, GcAble, GcBox, GcContext};
+
+/// Whether a `MaybeUninit<T>` holds a value isn't known, so nothing in it is traced
+///
+/// The value of a [`Gc::new_uninit`] box is traced once [`Gc::assume_init`] is called, until then a
+/// `Gc<_>` written into it stays a root.
+unsafe impl<T: GcAble> GcAble for MaybeUninit<T> {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
+
+/// What the collector sees the value of a `GcBox<MaybeUninit<T>>` from [`Gc::new_uninit`] as
+///
+/// The value is only traced and dropped once the header says it's initialized
+#[repr(transparent)]
+struct ErasedUninit<T: GcAble> {
+    val: MaybeUninit<T>,
+}
+
+impl<T: GcAble> ErasedUninit<T> {
+    /// The value, if it's been initialized
+    ///
+    /// # Safety
+    /// `this` must point to the value of a live `GcBox<ErasedUninit<T>>`
+    unsafe fn get(this: *const Self) -> Option<*mut T> {
+        let offset = mem::offset_of!(GcBox<Self>, val);
+        let gcbox = unsafe { this.byte_sub(offset) } as *const GcBox<Self>;
+        let initialized = unsafe { (*gcbox).header.initialized.load(Ordering::Acquire) };
+        initialized.then_some(this as *mut T)
+    }
+}
+
+impl<T: GcAble> Drop for ErasedUninit<T> {
+    fn drop(&mut self) {
+        if let Some(val) = unsafe { Self::get(self) } {
+            unsafe { ptr::drop_in_place(val) }
+        }
+    }
+}
+
+unsafe impl<T: GcAble> GcAble for ErasedUninit<T> {
+    unsafe fn mark(&self) {
+        if let Some(val) = unsafe { Self::get(self) } {
+            unsafe { (*val).mark() }
+        }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        if let Some(val) = unsafe { Self::get(self) } {
+            unsafe { (*val).inc_root_count() }
+        }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        if let Some(val) = unsafe { Self::get(self) } {
+            unsafe { (*val).dec_root_count() }
+        }
+    }
+
+    unsafe fn set_not_root(&self) {
+        if let Some(val) = unsafe { Self::get(self) } {
+            unsafe { (*val).set_not_root() }
+        }
+    }
+}
+
+fn erase_uninit<T: GcAble>(gcbox: NonNull<u8>) -> NonNull<GcBox<dyn GcAble>> {
+    gcbox.cast::<GcBox<ErasedUninit<T>>>()
+}
+
+impl<T: GcAble> Gc<T> {
+    /// Allocates a box for a `T` in the global context without initializing it, see
+    /// [`GcContext::alloc_uninit`]
+    pub fn new_uninit() -> Gc<MaybeUninit<T>> {
+        GcContext::global().alloc_uninit()
+    }
+
+    pub(crate) fn new_uninit_in(ctx: &Arc<ContextInner>) -> Gc<MaybeUninit<T>> {
+        let gcbox = Gc::<MaybeUninit<T>>::alloc_uninit(ctx);
+        let mut header = Gc::<T>::header_with(
+            ctx,
+            None,
+            OnceLock::new(),
+            erase_uninit::<T>,
+            Layout::new::<GcBox<T>>(),
+            0,
+        );
+        *header.initialized.get_mut() = false;
+
+        let mut gc = ctx.lock();
+        unsafe { ptr::addr_of_mut!((*gcbox.as_ptr()).header).write(header) };
+        Gc::register(&mut gc, ctx, gcbox);
+        drop(gc);
+
+        Gc {
+            is_root: AtomicBool::new(true),
+            gcbox,
+        }
+    }
+}
+
+impl<T: GcAble> Gc<MaybeUninit<T>> {
+    /// Starts treating the value of a box from [`Gc::new_uninit`] as an initialized `T`
+    ///
+    /// From then on the collector traces the value, and drops it once the box is collected. The
+    /// `Gc<_>`s in it stop being roots, like they would if the value had been moved into a new box.
+    ///
+    /// ```
+    /// use gc::Gc;
+    ///
+    /// let mut uninit = Gc::<Gc<u32>>::new_uninit();
+    /// uninit.get_mut().unwrap().write(Gc::new(5));
+    /// let gc = unsafe { Gc::assume_init(uninit) };
+    /// gc::force_collect();
+    /// assert_eq!(**gc, 5);
+    /// ```
+    ///
+    /// # Safety
+    /// The value must have been fully initialized, through [`Gc::get_mut`] or a pointer from
+    /// [`Gc::as_ptr`], and `this` must come from [`Gc::new_uninit`] or
+    /// [`GcContext::alloc_uninit`]. Other `Gc<MaybeUninit<T>>`s pointing to the box may still be
+    /// used afterwards, but must not be passed to this again.
+    pub unsafe fn assume_init(this: Self) -> Gc<T> {
+        this.assert_alive();
+        let (is_root, gcbox) = Gc::into_parts(this);
+        let gcb = unsafe { gcbox.as_ref() };
+        let gcbox = gcbox.cast::<GcBox<T>>();
+
+        // The value's children have to stop being roots together with the collector starting to
+        // trace them, and the box may have been scanned already
+        let mut gc = gcb.header.context.lock();
+        let verify = gc.config.verify_roots;
+        unsafe { (*GcBox::val(gcbox.as_ptr())).set_not_root() };
+        gcb.header.initialized.store(true, Ordering::Release);
+        gc.write_barrier((gcb.header.erase)(gcbox.cast()));
+        drop(gc);
+
+        let this = Gc {
+            is_root: AtomicBool::new(is_root),
+            gcbox,
+        };
+        if verify {
+            this.verify_not_root();
+        }
+        this
+    }
+}
@@ -0,0 +1,102 @@
+use core::{ptr::NonNull, sync::atomic::AtomicBool};
+
+use crate::{Gc, GcAble, GcBox};
+
+/// Converts a `Gc<T>` into a `Gc<dyn Trait>`, for any trait `T` implements
+///
+/// ```ignore
+/// trait Shape: GcAble {
+///     fn area(&self) -> f64;
+/// }
+///
+/// let shapes: Vec<Gc<dyn Shape>> = vec![
+///     gc_unsize!(circle as dyn Shape),
+///     gc_unsize!(square as dyn Shape),
+/// ];
+/// ```
+///
+/// `Gc<T>` can't coerce by itself since `CoerceUnsized` is unstable, so this does the same
+/// coercion on the pointer inside instead, which works on stable. The trait has to have `GcAble`
+/// as a supertrait for `Gc<dyn Trait>` to exist. The `Gc` has to be a single token, so anything
+/// other than a variable needs to be wrapped in parentheses.
+#[macro_export]
+macro_rules! gc_unsize {
+    ($gc:tt as $dyn:ty) => {{
+        #[allow(unused_parens)]
+        let gc = $gc;
+        // Safety: the closure only coerces the pointer
+        unsafe {
+            $crate::Gc::__unsize(
+                gc,
+                |gcbox| -> ::core::ptr::NonNull<$crate::__private::GcBox<$dyn>> { gcbox },
+            )
+        }
+    }};
+}
+
+impl<T: GcAble> Gc<T> {
+    /// The implementation of [`gc_unsize!`]
+    ///
+    /// # Safety
+    /// `coerce` must return `gcbox` with only its type changed
+    #[doc(hidden)]
+    pub unsafe fn __unsize<U: ?Sized + GcAble>(
+        this: Self,
+        coerce: impl FnOnce(NonNull<GcBox<T>>) -> NonNull<GcBox<U>>,
+    ) -> Gc<U> {
+        let (is_root, gcbox) = Gc::into_parts(this);
+        let unsized_box = coerce(gcbox);
+        debug_assert_eq!(unsized_box.cast::<u8>(), gcbox.cast::<u8>());
+        debug_assert_eq!(
+            unsafe { GcBox::val(unsized_box.as_ptr()) } as *const u8,
+            unsafe { GcBox::val(gcbox.as_ptr()) } as *const u8
+        );
+        Gc {
+            is_root: AtomicBool::new(is_root),
+            gcbox: unsized_box,
+        }
+    }
+}
+
+impl<T: GcAble> Gc<T> {
+    /// Reinterprets the value as a `U`, without moving or reallocating it
+    ///
+    /// Meant for converting between a `#[repr(transparent)]` newtype and the type it wraps. The
+    /// object keeps being traced, finalized and dropped as a `T`, as well as counted as one by
+    /// [`crate::histogram_by_type`], whichever `Gc<_>`s point to it.
+    ///
+    /// ```
+    /// use gc::Gc;
+    ///
+    /// #[repr(transparent)]
+    /// struct Meters(u32);
+    /// gc::impl_gc_able!(Meters => |_meters, _visit| {});
+    ///
+    /// let meters = unsafe { Gc::new(5u32).cast::<Meters>() };
+    /// assert_eq!(meters.0, 5);
+    /// let raw: Gc<u32> = unsafe { meters.cast() };
+    /// assert_eq!(*raw, 5);
+    /// ```
+    ///
+    /// Fails to compile if `T` and `U` have different sizes or alignments.
+    ///
+    /// # Safety
+    /// A `T` must be valid to read as a `U` and the other way around, which holds if one is a
+    /// `#[repr(transparent)]` wrapper of the other. `U`'s [`GcAble`] impl must also visit exactly the
+    /// same `Gc<_>`s as `T`'s, since some operations trace the value as a `U` and the collector
+    /// traces it as a `T`, so casting between types which don't contain the same `Gc<_>`s is
+    /// undefined behavior even if their layouts match.
+    pub unsafe fn cast<U: GcAble>(self) -> Gc<U> {
+        const {
+            assert!(
+                size_of::<T>() == size_of::<U>() && align_of::<T>() == align_of::<U>(),
+                "`Gc::cast` between types with different layouts",
+            )
+        };
+        let (is_root, gcbox) = Gc::into_parts(self);
+        Gc {
+            is_root: AtomicBool::new(is_root),
+            gcbox: gcbox.cast(),
+        }
+    }
+}
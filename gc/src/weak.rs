@@ -0,0 +1,146 @@
+use alloc::sync::Arc;
+use core::{
+    fmt::Debug,
+    ptr::NonNull,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{context::ContextInner, Gc, GcAble, GcBox};
+
+/// A handle to a `Gc<_>`'s value which doesn't keep it alive, see [`Gc::downgrade`]
+///
+/// A `WeakGc<_>` is never traced, so it may be freely stored inside a `GcAble` value
+///
+/// ```
+/// use gc::{Gc, GcContext};
+///
+/// let ctx = GcContext::new();
+/// let num = ctx.alloc(7);
+/// let clone = num.clone();
+/// let weak = Gc::downgrade(&num);
+/// let weak_clone = weak.clone();
+/// assert_eq!((Gc::strong_count(&num), Gc::weak_count(&num)), (2, 2));
+/// assert_eq!((weak.strong_count(), weak.weak_count()), (2, 2));
+///
+/// drop((clone, weak_clone));
+/// assert_eq!((Gc::strong_count(&num), Gc::weak_count(&num)), (1, 1));
+/// assert_eq!(*weak.upgrade().unwrap(), 7);
+///
+/// // Once collected, the value can't be upgraded to anymore, but the weak count is still kept
+/// drop(num);
+/// ctx.force_collect();
+/// assert!(weak.upgrade().is_none());
+/// let weak_clone = weak.clone();
+/// assert_eq!((weak.strong_count(), weak.weak_count()), (0, 2));
+/// drop(weak_clone);
+/// assert_eq!(weak.weak_count(), 1);
+/// ctx.assert_no_leaks();
+/// ```
+pub struct WeakGc<T: GcAble> {
+    gcbox: NonNull<GcBox<T>>,
+    /// `false` once `gcbox` is collected, after which it must not be dereferenced
+    alive: Arc<AtomicBool>,
+    context: Arc<ContextInner>,
+}
+
+// SAFETY: `gcbox` is only dereferenced through `upgrade`, which synchronizes with the collector
+unsafe impl<T: GcAble> Send for WeakGc<T> {}
+// SAFETY: See `Send`
+unsafe impl<T: GcAble> Sync for WeakGc<T> {}
+
+impl<T: GcAble> WeakGc<T> {
+    /// # Safety
+    /// `alive` must be the flag of `gcbox`, which must belong to `context`
+    pub(crate) unsafe fn new(
+        gcbox: NonNull<GcBox<T>>,
+        alive: Arc<AtomicBool>,
+        context: Arc<ContextInner>,
+    ) -> Self {
+        Self {
+            gcbox,
+            alive,
+            context,
+        }
+    }
+
+    /// Returns a new root `Gc<T>` to the value if it hasn't been collected
+    ///
+    /// Whether the value was collected is kept outside of its box, by every `WeakGc<_>` to it, so
+    /// this never reads a freed box. That includes values collected by
+    /// [`crate::GcContext::shutdown`], which collects everything unreachable before shutting down,
+    /// and values whose context was shut down and had every `GcContext` to it dropped.
+    pub fn upgrade(&self) -> Option<Gc<T>> {
+        // Holding the lock makes sure the value isn't collected while it's being rooted
+        let _gc = self.context.lock();
+        if !self.alive.load(Ordering::Acquire) {
+            return None;
+        }
+        Some(unsafe { Gc::from_gcbox(self.gcbox) })
+    }
+
+    /// The box this points to, as the collector sees it, if it hasn't been collected
+    ///
+    /// Must only be called while holding the context's lock, so that the box can't be freed while
+    /// the pointer is in use
+    pub(crate) fn erased(&self) -> Option<NonNull<GcBox<dyn GcAble>>> {
+        if !self.alive.load(Ordering::Acquire) {
+            return None;
+        }
+        let erase = unsafe { self.gcbox.as_ref() }.header.erase;
+        Some(erase(self.gcbox.cast()))
+    }
+
+    /// Whether the value has been collected, after which this can never be upgraded again
+    #[cfg(feature = "std")]
+    pub(crate) fn is_collected(&self) -> bool {
+        !self.alive.load(Ordering::Acquire)
+    }
+
+    /// The number of root `Gc<_>`s pointing to the value, or `0` if it has been collected
+    pub fn strong_count(&self) -> u32 {
+        let _gc = self.context.lock();
+        if !self.alive.load(Ordering::Acquire) {
+            return 0;
+        }
+        unsafe { self.gcbox.as_ref() }.header.root_count()
+    }
+
+    /// The number of `WeakGc<_>`s pointing to the value, including this one
+    ///
+    /// This keeps counting once the value has been collected
+    pub fn weak_count(&self) -> usize {
+        // The collector drops the box's own reference to `alive` while holding the lock
+        let _gc = self.context.lock();
+        let strong = Arc::strong_count(&self.alive);
+        match self.alive.load(Ordering::Acquire) {
+            true => strong - 1,
+            false => strong,
+        }
+    }
+}
+
+impl<T: GcAble> Clone for WeakGc<T> {
+    fn clone(&self) -> Self {
+        Self {
+            gcbox: self.gcbox,
+            alive: Arc::clone(&self.alive),
+            context: Arc::clone(&self.context),
+        }
+    }
+}
+
+impl<T: GcAble> Debug for WeakGc<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "(WeakGc)")
+    }
+}
+
+unsafe impl<T: GcAble> GcAble for WeakGc<T> {
+    unsafe fn mark(&self) {}
+
+    unsafe fn inc_root_count(&self) {}
+
+    unsafe fn dec_root_count(&self) {}
+
+    unsafe fn set_not_root(&self) {}
+}
@@ -0,0 +1,148 @@
+use alloc::vec::Vec;
+use core::{borrow::Borrow, fmt::Debug, hash::Hash};
+use std::collections::HashMap;
+
+use crate::{Gc, GcAble, WeakGc};
+
+/// A map whose values are held weakly, so an entry goes away once its value is collected
+///
+/// Dead entries are dropped when they're looked up, when iterating, and by [`WeakTable::prune`],
+/// which makes this a good fit for caches and interning tables
+///
+/// ```
+/// use gc::{GcConfig, GcContext, WeakTable};
+///
+/// let ctx = GcContext::with_config(GcConfig::default().deterministic(true));
+/// let (one, two, three) = (ctx.alloc(1u32), ctx.alloc(2u32), ctx.alloc(3u32));
+/// let mut table = WeakTable::new();
+/// table.insert("one", &one);
+/// table.insert("two", &two);
+/// table.insert("three", &three);
+///
+/// drop((one, three));
+/// ctx.force_collect();
+/// // Dead entries are only dropped once noticed
+/// assert_eq!(table.len(), 3);
+/// assert!(table.get("one").is_none());
+/// assert_eq!(table.len(), 2);
+/// table.prune();
+/// assert_eq!(table.len(), 1);
+///
+/// assert_eq!(*table.get("two").unwrap(), 2);
+/// let entries = table.entries();
+/// assert_eq!(entries.len(), 1);
+/// assert_eq!((*entries[0].0, *entries[0].1), ("two", 2));
+/// drop(entries);
+///
+/// drop(two);
+/// ctx.force_collect();
+/// assert!(table.entries().is_empty());
+/// assert!(table.is_empty());
+/// ctx.assert_no_leaks();
+/// ```
+pub struct WeakTable<K, T: GcAble> {
+    entries: HashMap<K, WeakGc<T>>,
+}
+
+impl<K: Eq + Hash, T: GcAble> WeakTable<K, T> {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Stores a weak handle to `val` under `key`, returning the value it replaced if that hasn't
+    /// been collected
+    pub fn insert(&mut self, key: K, val: &Gc<T>) -> Option<Gc<T>> {
+        let old = self.entries.insert(key, Gc::downgrade(val))?;
+        old.upgrade()
+    }
+
+    /// Returns the value stored under `key` if it hasn't been collected, dropping the entry if it
+    /// has
+    pub fn get<Q>(&mut self, key: &Q) -> Option<Gc<T>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let val = self.entries.get(key)?.upgrade();
+        if val.is_none() {
+            self.entries.remove(key);
+        }
+        val
+    }
+
+    /// Removes the entry under `key`, returning its value if that hasn't been collected
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<Gc<T>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.remove(key)?.upgrade()
+    }
+
+    /// Drops every entry whose value has been collected
+    pub fn prune(&mut self) {
+        self.entries.retain(|_, weak| !weak.is_collected());
+    }
+
+    /// The number of entries, including ones whose value has been collected since the last time
+    /// they were dropped
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Drops every dead entry, then returns every entry whose value is still alive
+    ///
+    /// The values are rooted, so none of them are collected while the returned `Vec` is alive
+    pub fn entries(&mut self) -> Vec<(&K, Gc<T>)> {
+        self.prune();
+        self.entries
+            .iter()
+            .filter_map(|(key, weak)| Some((key, weak.upgrade()?)))
+            .collect()
+    }
+}
+
+impl<K: Eq + Hash, T: GcAble> Default for WeakTable<K, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Debug, T: GcAble> Debug for WeakTable<K, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_set().entries(self.entries.keys()).finish()
+    }
+}
+
+// The values are `WeakGc`s, which are never traced, so only the keys are
+unsafe impl<K: GcAble, T: GcAble> GcAble for WeakTable<K, T> {
+    unsafe fn mark(&self) {
+        for key in self.entries.keys() {
+            unsafe { key.mark() }
+        }
+    }
+
+    unsafe fn inc_root_count(&self) {
+        for key in self.entries.keys() {
+            unsafe { key.inc_root_count() }
+        }
+    }
+
+    unsafe fn dec_root_count(&self) {
+        for key in self.entries.keys() {
+            unsafe { key.dec_root_count() }
+        }
+    }
+
+    unsafe fn set_not_root(&self) {
+        for key in self.entries.keys() {
+            unsafe { key.set_not_root() }
+        }
+    }
+}
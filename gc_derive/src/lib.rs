@@ -0,0 +1,686 @@
+//! `#[derive(GcAble)]`, `#[derive(LocalGcAble)]` and `#[derive(GcClone)]`, which are re-exported
+//! by the `gc` crate and documented there
+//!
+//! This crate has no dependencies, so the item is parsed by hand. Only as much of it is parsed as
+//! the impl needs: the generic parameters, the where clause, and the names of the fields.
+
+use proc_macro::{Delimiter, Spacing, TokenStream, TokenTree};
+
+/// The methods of `GcAble` and `LocalGcAble`, each of which forwards to every field
+const METHODS: [&str; 4] = ["mark", "inc_root_count", "dec_root_count", "set_not_root"];
+
+#[proc_macro_derive(GcAble, attributes(gc))]
+pub fn derive_gc_able(input: TokenStream) -> TokenStream {
+    derive(input, Item::expand)
+}
+
+#[proc_macro_derive(LocalGcAble, attributes(gc))]
+pub fn derive_local_gc_able(input: TokenStream) -> TokenStream {
+    derive(input, Item::expand_local)
+}
+
+#[proc_macro_derive(GcClone, attributes(gc))]
+pub fn derive_gc_clone(input: TokenStream) -> TokenStream {
+    derive(input, Item::expand_clone)
+}
+
+fn derive(input: TokenStream, expand: fn(&Item) -> String) -> TokenStream {
+    let out = match Item::parse(input) {
+        Ok(item) => expand(&item),
+        Err(msg) => format!("::core::compile_error!({msg:?});"),
+    };
+    out.parse().unwrap()
+}
+
+/// A generic parameter of the item
+struct Param {
+    kind: ParamKind,
+    /// How the parameter is referred to, such as `'a`, `T` or `N`
+    name: String,
+    /// The parameter as declared, without its default
+    decl: String,
+}
+
+#[derive(PartialEq)]
+enum ParamKind {
+    Lifetime,
+    Type,
+    Const,
+}
+
+struct Field {
+    /// The field's name, or its index for a tuple field
+    name: String,
+    /// Set by `#[gc(clone = "deep")]`
+    deep: bool,
+    /// Whether the field is a `PhantomData<_>` or `PhantomPinned`, which holds nothing at runtime
+    marker: bool,
+    /// Every identifier in the field's type
+    idents: Vec<String>,
+}
+
+enum Fields {
+    Named(Vec<Field>),
+    Unnamed(Vec<Field>),
+    Unit,
+}
+
+/// Where attributes are being parsed, which decides which `#[gc(..)]` arguments are allowed
+#[derive(PartialEq)]
+enum AttrsOf {
+    Item,
+    Field,
+    Other,
+}
+
+/// The arguments of the `#[gc(..)]` attributes of an item or field
+#[derive(Default)]
+struct Attrs {
+    bound: Option<String>,
+    deep: bool,
+}
+
+enum Body {
+    Struct(Fields),
+    Enum(Vec<(String, Fields)>),
+}
+
+struct Item {
+    name: String,
+    params: Vec<Param>,
+    /// The predicates of the item's own where clause
+    predicates: Vec<String>,
+    /// Set by `#[gc(bound = "...")]`, replaces the `T: GcAble`, `T: LocalGcAble` or `T: Clone`
+    /// bound on every type parameter
+    bound: Option<String>,
+    body: Body,
+}
+
+impl Item {
+    fn parse(input: TokenStream) -> Result<Self, String> {
+        let tokens: Vec<TokenTree> = input.into_iter().collect();
+        let mut i = 0;
+
+        let bound = parse_attrs(&tokens, &mut i, AttrsOf::Item)?.bound;
+        skip_vis(&tokens, &mut i);
+        let keyword = expect_ident(&tokens, &mut i)?;
+        let name = expect_ident(&tokens, &mut i)?;
+
+        let mut params = Vec::new();
+        if is_punct(tokens.get(i), '<') {
+            let start = i + 1;
+            let end = closing_angle(&tokens, start)?;
+            for param in split_commas(&tokens[start..end]) {
+                params.push(parse_param(param)?);
+            }
+            i = end + 1;
+        }
+
+        let mut predicates = parse_where(&tokens, &mut i);
+        let body = match keyword.as_str() {
+            "struct" => {
+                let fields = match tokens.get(i) {
+                    Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+                        parse_fields(g.delimiter(), &g.stream())?
+                    }
+                    Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+                        let fields = parse_fields(g.delimiter(), &g.stream())?;
+                        i += 1;
+                        predicates = parse_where(&tokens, &mut i);
+                        fields
+                    }
+                    _ => Fields::Unit,
+                };
+                Body::Struct(fields)
+            }
+            "enum" => match tokens.get(i) {
+                Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+                    Body::Enum(parse_variants(&g.stream())?)
+                }
+                _ => return Err("expected the variants of the enum".to_string()),
+            },
+            "union" => {
+                return Err(
+                    "`GcAble` can't be derived for a union, since which field is \
+                            live isn't known"
+                        .to_string(),
+                )
+            }
+            other => return Err(format!("expected a struct or enum, found `{other}`")),
+        };
+
+        Ok(Self {
+            name,
+            params,
+            predicates,
+            bound,
+            body,
+        })
+    }
+
+    fn expand(&self) -> String {
+        self.expand_trait(
+            "::gc::GcAble",
+            "::core::marker::Send + ::core::marker::Sync + 'static",
+        )
+    }
+
+    fn expand_local(&self) -> String {
+        self.expand_trait("::gc::LocalGcAble", "'static")
+    }
+
+    /// An impl of `GcAble` or `LocalGcAble`, whichever `tr` is, whose methods forward to every
+    /// field
+    ///
+    /// A type parameter only used by marker fields is bounded by `marker_bound`, which is what the
+    /// trait's impl for `PhantomData<_>` requires
+    fn expand_trait(&self, tr: &str, marker_bound: &str) -> String {
+        let (impl_generics, ty_generics, where_clause) = self.generics(tr, Some(marker_bound));
+        let methods: String = METHODS
+            .iter()
+            .map(|method| {
+                format!(
+                    "unsafe fn {method}(&self) {{ {} }}",
+                    self.forward(tr, method)
+                )
+            })
+            .collect();
+        format!(
+            "#[automatically_derived] \
+             unsafe impl{impl_generics} {tr} for {}{ty_generics} {where_clause} {{ {methods} }}",
+            self.name,
+        )
+    }
+
+    fn expand_clone(&self) -> String {
+        let (impl_generics, ty_generics, where_clause) =
+            self.generics("::core::clone::Clone", None);
+        format!(
+            "#[automatically_derived] \
+             impl{impl_generics} ::core::clone::Clone for {}{ty_generics} {where_clause} {{ \
+                 fn clone(&self) -> Self {{ {} }} \
+             }}",
+            self.name,
+            self.clone_body(),
+        )
+    }
+
+    /// The generics of the impl, the generics of the type, and the where clause of the impl, which
+    /// bounds every type parameter by `default_bound` unless `#[gc(bound = "...")]` is given
+    ///
+    /// A type parameter which is only used by marker fields, such as the `T` of a `PhantomData<T>`,
+    /// is bounded by `marker_bound` instead, if any
+    fn generics(
+        &self,
+        default_bound: &str,
+        marker_bound: Option<&str>,
+    ) -> (String, String, String) {
+        let mut predicates = self.predicates.clone();
+        for param in &self.params {
+            // `GcAble` and `LocalGcAble` require `'static`, which no shorter lifetime could
+            // satisfy anyway
+            if param.kind == ParamKind::Lifetime {
+                predicates.push(format!("{}: 'static", param.name));
+            }
+        }
+        match &self.bound {
+            Some(bound) if bound.trim().is_empty() => {}
+            Some(bound) => predicates.push(bound.clone()),
+            None => {
+                for param in &self.params {
+                    if param.kind != ParamKind::Type {
+                        continue;
+                    }
+                    let bound = match self.only_in_markers(&param.name) {
+                        true => marker_bound,
+                        false => Some(default_bound),
+                    };
+                    if let Some(bound) = bound {
+                        predicates.push(format!("{}: {bound}", param.name));
+                    }
+                }
+            }
+        }
+
+        let (impl_generics, ty_generics) = match self.params.is_empty() {
+            true => (String::new(), String::new()),
+            false => (
+                format!("<{}>", join(self.params.iter().map(|p| &p.decl))),
+                format!("<{}>", join(self.params.iter().map(|p| &p.name))),
+            ),
+        };
+        let where_clause = match predicates.is_empty() {
+            true => String::new(),
+            false => format!("where {}", join(predicates.iter())),
+        };
+        (impl_generics, ty_generics, where_clause)
+    }
+
+    /// Whether `param` is used by the type of a marker field, and not by the type of any other
+    fn only_in_markers(&self, param: &str) -> bool {
+        let fields: Vec<&Field> = match &self.body {
+            Body::Struct(fields) => fields.fields().iter().collect(),
+            Body::Enum(variants) => variants
+                .iter()
+                .flat_map(|(_, fields)| fields.fields())
+                .collect(),
+        };
+        let uses = |marker: bool| {
+            fields
+                .iter()
+                .any(|f| f.marker == marker && f.idents.iter().any(|ident| ident == param))
+        };
+        uses(true) && !uses(false)
+    }
+
+    /// The body of `tr`'s `method`, which calls it on every field
+    fn forward(&self, tr: &str, method: &str) -> String {
+        let call = |field: &str| format!("unsafe {{ {tr}::{method}({field}) }};");
+        match &self.body {
+            Body::Struct(Fields::Named(fields) | Fields::Unnamed(fields)) => fields
+                .iter()
+                .map(|f| call(&format!("&self.{}", f.name)))
+                .collect(),
+            Body::Struct(Fields::Unit) => String::new(),
+            Body::Enum(variants) if variants.is_empty() => "match *self {}".to_string(),
+            Body::Enum(variants) => {
+                let arms: String = variants
+                    .iter()
+                    .map(|(variant, fields)| {
+                        let calls: String =
+                            fields.bindings().iter().map(|(_, f)| call(f)).collect();
+                        format!("{} => {{ {calls} }}", fields.pattern(variant))
+                    })
+                    .collect();
+                format!("match self {{ {arms} }}")
+            }
+        }
+    }
+
+    /// The body of `Clone::clone`, which clones every field by `Gc::clone_deep` if it has
+    /// `#[gc(clone = "deep")]`, or by `Clone::clone` otherwise
+    fn clone_body(&self) -> String {
+        let clone = |field: &Field, value: &str| match field.deep {
+            true => format!("::gc::Gc::clone_deep({value})"),
+            false => format!("::core::clone::Clone::clone({value})"),
+        };
+        match &self.body {
+            Body::Struct(Fields::Named(fields)) => format!(
+                "Self {{ {} }}",
+                join(fields.iter().map(|f| format!(
+                    "{}: {}",
+                    f.name,
+                    clone(f, &format!("&self.{}", f.name))
+                ))),
+            ),
+            Body::Struct(Fields::Unnamed(fields)) => format!(
+                "Self({})",
+                join(
+                    fields
+                        .iter()
+                        .map(|f| clone(f, &format!("&self.{}", f.name)))
+                ),
+            ),
+            Body::Struct(Fields::Unit) => "Self".to_string(),
+            Body::Enum(variants) if variants.is_empty() => "match *self {}".to_string(),
+            Body::Enum(variants) => {
+                let arms: String = variants
+                    .iter()
+                    .map(|(variant, fields)| {
+                        let bindings = fields.bindings();
+                        let value = match fields {
+                            Fields::Named(_) => format!(
+                                "Self::{variant} {{ {} }}",
+                                join(bindings.iter().map(|(f, binding)| format!(
+                                    "{}: {}",
+                                    f.name,
+                                    clone(f, binding)
+                                ))),
+                            ),
+                            Fields::Unnamed(_) => format!(
+                                "Self::{variant}({})",
+                                join(bindings.iter().map(|(f, binding)| clone(f, binding))),
+                            ),
+                            Fields::Unit => format!("Self::{variant}"),
+                        };
+                        format!("{} => {value},", fields.pattern(variant))
+                    })
+                    .collect();
+                format!("match self {{ {arms} }}")
+            }
+        }
+    }
+}
+
+impl Fields {
+    fn fields(&self) -> &[Field] {
+        match self {
+            Fields::Named(fields) | Fields::Unnamed(fields) => fields,
+            Fields::Unit => &[],
+        }
+    }
+
+    /// Every field, with the name it's bound to by [`Fields::pattern`]
+    fn bindings(&self) -> Vec<(&Field, String)> {
+        match self {
+            Fields::Named(fields) => fields.iter().map(|f| (f, f.name.clone())).collect(),
+            Fields::Unnamed(fields) => fields
+                .iter()
+                .map(|f| (f, format!("__field{}", f.name)))
+                .collect(),
+            Fields::Unit => Vec::new(),
+        }
+    }
+
+    /// A pattern matching `variant`, which binds every field by reference
+    fn pattern(&self, variant: &str) -> String {
+        let names = self.bindings().into_iter().map(|(_, binding)| binding);
+        match self {
+            Fields::Named(_) => format!("Self::{variant} {{ {} }}", join(names)),
+            Fields::Unnamed(_) => format!("Self::{variant}({})", join(names)),
+            Fields::Unit => format!("Self::{variant}"),
+        }
+    }
+}
+
+fn join<T: AsRef<str>>(items: impl Iterator<Item = T>) -> String {
+    items
+        .map(|item| item.as_ref().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn is_punct(token: Option<&TokenTree>, ch: char) -> bool {
+    matches!(token, Some(TokenTree::Punct(p)) if p.as_char() == ch)
+}
+
+fn is_ident(token: Option<&TokenTree>, name: &str) -> bool {
+    matches!(token, Some(TokenTree::Ident(i)) if i.to_string() == name)
+}
+
+fn expect_ident(tokens: &[TokenTree], i: &mut usize) -> Result<String, String> {
+    match tokens.get(*i) {
+        Some(TokenTree::Ident(ident)) => {
+            *i += 1;
+            Ok(ident.to_string())
+        }
+        Some(other) => Err(format!("expected an identifier, found `{other}`")),
+        None => Err("expected an identifier".to_string()),
+    }
+}
+
+fn to_string(tokens: &[TokenTree]) -> String {
+    tokens.iter().cloned().collect::<TokenStream>().to_string()
+}
+
+/// Skips the attributes starting at `i`, returning the arguments of the `#[gc(..)]` ones
+///
+/// `bound` is only allowed on the item itself, and `clone` only on fields
+fn parse_attrs(tokens: &[TokenTree], i: &mut usize, of: AttrsOf) -> Result<Attrs, String> {
+    let mut attrs = Attrs::default();
+    while is_punct(tokens.get(*i), '#') {
+        let Some(TokenTree::Group(attr)) = tokens.get(*i + 1) else {
+            break;
+        };
+        *i += 2;
+        let attr: Vec<TokenTree> = attr.stream().into_iter().collect();
+        if !is_ident(attr.first(), "gc") {
+            continue;
+        }
+        let args: Vec<TokenTree> = match attr.get(1) {
+            Some(TokenTree::Group(args)) => args.stream().into_iter().collect(),
+            _ => return Err(expected_attr(&of)),
+        };
+        let (key, value) = match args.as_slice() {
+            [TokenTree::Ident(key), TokenTree::Punct(eq), TokenTree::Literal(lit)]
+                if eq.as_char() == '=' =>
+            {
+                (key.to_string(), parse_str(&lit.to_string())?)
+            }
+            _ => return Err(expected_attr(&of)),
+        };
+        match (key.as_str(), &of) {
+            ("bound", AttrsOf::Item) => attrs.bound = Some(value),
+            ("clone", AttrsOf::Field) => {
+                attrs.deep = match value.as_str() {
+                    "deep" => true,
+                    "shared" => false,
+                    other => {
+                        return Err(format!(
+                            "expected `#[gc(clone = \"deep\")]` or `#[gc(clone = \"shared\")]`, \
+                             found `{other}`"
+                        ))
+                    }
+                };
+            }
+            _ => return Err(expected_attr(&of)),
+        }
+    }
+    Ok(attrs)
+}
+
+fn expected_attr(of: &AttrsOf) -> String {
+    match of {
+        AttrsOf::Item => "expected `#[gc(bound = \"...\")]`".to_string(),
+        AttrsOf::Field => "expected `#[gc(clone = \"deep\")]`".to_string(),
+        AttrsOf::Other => "`#[gc(..)]` is only allowed on the item and its fields".to_string(),
+    }
+}
+
+/// The contents of a string literal
+fn parse_str(lit: &str) -> Result<String, String> {
+    if let Some(raw) = lit.strip_prefix('r') {
+        let raw = raw.trim_matches('#');
+        return Ok(raw[1..raw.len() - 1].to_string());
+    }
+    let Some(inner) = lit.strip_prefix('"').and_then(|lit| lit.strip_suffix('"')) else {
+        return Err(format!("expected a string, found `{lit}`"));
+    };
+    let mut s = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n' | 't' | 'r') => s.push(' '),
+                Some(c) => s.push(c),
+                None => {}
+            },
+            c => s.push(c),
+        }
+    }
+    Ok(s)
+}
+
+fn skip_vis(tokens: &[TokenTree], i: &mut usize) {
+    if is_ident(tokens.get(*i), "pub") {
+        *i += 1;
+        if matches!(tokens.get(*i), Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis)
+        {
+            *i += 1;
+        }
+    }
+}
+
+/// Whether the `>` at `i` is part of a `->` rather than closing a `<`
+fn is_arrow(tokens: &[TokenTree], i: usize) -> bool {
+    i > 0
+        && matches!(&tokens[i - 1], TokenTree::Punct(p) if p.as_char() == '-' && p.spacing() == Spacing::Joint)
+}
+
+/// The index of the `>` closing the `<` just before `start`
+fn closing_angle(tokens: &[TokenTree], start: usize) -> Result<usize, String> {
+    let mut depth = 1;
+    for i in start..tokens.len() {
+        if is_punct(tokens.get(i), '<') {
+            depth += 1;
+        } else if is_punct(tokens.get(i), '>') && !is_arrow(tokens, i) {
+            depth -= 1;
+            if depth == 0 {
+                return Ok(i);
+            }
+        }
+    }
+    Err("unclosed generic parameters".to_string())
+}
+
+/// Splits `tokens` at every comma which isn't between `<` and `>`, leaving out empty parts
+fn split_commas(tokens: &[TokenTree]) -> Vec<&[TokenTree]> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for i in 0..tokens.len() {
+        if is_punct(tokens.get(i), '<') {
+            depth += 1;
+        } else if is_punct(tokens.get(i), '>') && !is_arrow(tokens, i) {
+            depth -= 1;
+        } else if is_punct(tokens.get(i), ',') && depth == 0 {
+            parts.push(&tokens[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&tokens[start..]);
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+fn parse_param(tokens: &[TokenTree]) -> Result<Param, String> {
+    let mut i = 0;
+    parse_attrs(tokens, &mut i, AttrsOf::Other)?;
+    let tokens = &tokens[i..];
+    // The default, if any, starts at the first `=` outside of `<` and `>`
+    let mut depth = 0;
+    let mut end = tokens.len();
+    for i in 0..tokens.len() {
+        if is_punct(tokens.get(i), '<') {
+            depth += 1;
+        } else if is_punct(tokens.get(i), '>') && !is_arrow(tokens, i) {
+            depth -= 1;
+        } else if is_punct(tokens.get(i), '=') && depth == 0 {
+            end = i;
+            break;
+        }
+    }
+    let decl = to_string(&tokens[..end]);
+
+    let (kind, name) = match tokens {
+        [TokenTree::Punct(p), TokenTree::Ident(name), ..] if p.as_char() == '\'' => {
+            (ParamKind::Lifetime, format!("'{name}"))
+        }
+        [TokenTree::Ident(kw), TokenTree::Ident(name), ..] if kw.to_string() == "const" => {
+            (ParamKind::Const, name.to_string())
+        }
+        [TokenTree::Ident(name), ..] => (ParamKind::Type, name.to_string()),
+        _ => return Err(format!("unsupported generic parameter `{decl}`")),
+    };
+    Ok(Param { kind, name, decl })
+}
+
+/// Parses the where clause starting at `i` if there is one, stopping before the body or the `;`
+fn parse_where(tokens: &[TokenTree], i: &mut usize) -> Vec<String> {
+    if !is_ident(tokens.get(*i), "where") {
+        return Vec::new();
+    }
+    *i += 1;
+    let start = *i;
+    while *i < tokens.len() {
+        let at_end = match &tokens[*i] {
+            TokenTree::Group(g) => g.delimiter() == Delimiter::Brace,
+            TokenTree::Punct(p) => p.as_char() == ';',
+            _ => false,
+        };
+        if at_end {
+            break;
+        }
+        *i += 1;
+    }
+    split_commas(&tokens[start..*i])
+        .into_iter()
+        .map(to_string)
+        .collect()
+}
+
+fn parse_fields(delimiter: Delimiter, stream: &TokenStream) -> Result<Fields, String> {
+    let tokens: Vec<TokenTree> = stream.clone().into_iter().collect();
+    let fields = split_commas(&tokens);
+    let mut parsed = Vec::new();
+    for (index, field) in fields.into_iter().enumerate() {
+        let mut i = 0;
+        let deep = parse_attrs(field, &mut i, AttrsOf::Field)?.deep;
+        skip_vis(field, &mut i);
+        let name = match delimiter {
+            Delimiter::Parenthesis => index.to_string(),
+            _ => {
+                let name = expect_ident(field, &mut i)?;
+                // The `:`
+                i += 1;
+                name
+            }
+        };
+        let ty = field.get(i..).unwrap_or_default();
+        let mut idents = Vec::new();
+        collect_idents(ty, &mut idents);
+        parsed.push(Field {
+            name,
+            deep,
+            marker: is_marker(ty),
+            idents,
+        });
+    }
+    Ok(match delimiter {
+        Delimiter::Parenthesis => Fields::Unnamed(parsed),
+        _ => Fields::Named(parsed),
+    })
+}
+
+/// Whether `ty` is a path to `PhantomData<_>` or `PhantomPinned`, going by its last segment
+fn is_marker(ty: &[TokenTree]) -> bool {
+    let end = ty
+        .iter()
+        .position(|token| is_punct(Some(token), '<'))
+        .unwrap_or(ty.len());
+    let last = ty[..end].iter().rev().find_map(|token| match token {
+        TokenTree::Ident(ident) => Some(ident.to_string()),
+        _ => None,
+    });
+    matches!(last.as_deref(), Some("PhantomData" | "PhantomPinned"))
+}
+
+/// Appends every identifier in `tokens` to `idents`, including those inside groups
+fn collect_idents(tokens: &[TokenTree], idents: &mut Vec<String>) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => idents.push(ident.to_string()),
+            TokenTree::Group(g) => {
+                let inner: Vec<TokenTree> = g.stream().into_iter().collect();
+                collect_idents(&inner, idents)
+            }
+            _ => {}
+        }
+    }
+}
+
+fn parse_variants(stream: &TokenStream) -> Result<Vec<(String, Fields)>, String> {
+    let tokens: Vec<TokenTree> = stream.clone().into_iter().collect();
+    let mut variants = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        parse_attrs(&tokens, &mut i, AttrsOf::Other)?;
+        let name = expect_ident(&tokens, &mut i)?;
+        let fields = match tokens.get(i) {
+            Some(TokenTree::Group(g))
+                if matches!(g.delimiter(), Delimiter::Brace | Delimiter::Parenthesis) =>
+            {
+                i += 1;
+                parse_fields(g.delimiter(), &g.stream())?
+            }
+            _ => Fields::Unit,
+        };
+        // Skip the discriminant, which can't contain a comma outside of a group
+        while i < tokens.len() && !is_punct(tokens.get(i), ',') {
+            i += 1;
+        }
+        i += 1;
+        variants.push((name, fields));
+    }
+    Ok(variants)
+}